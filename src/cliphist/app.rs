@@ -1,19 +1,25 @@
 use std::cell::RefCell;
+use std::path::Path;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
 use gdk4::prelude::*;
 use gtk4::prelude::*;
 use gtk4::{
-    Align, Application, ApplicationWindow, Box as GtkBox, CssProvider, Entry, EventControllerKey,
-    Label, ListBox, Orientation, ScrolledWindow,
+    Align, Application, ApplicationWindow, Box as GtkBox, Entry, EventControllerKey, Label,
+    Orientation, Picture, Popover, ScrolledWindow, Spinner, TextView,
 };
 
 use common::{
-    css::load_css,
-    keys::match_action,
+    anim::{self, is_expanded},
+    cli::{take_query_request, take_window_request, WindowRequest},
+    css::{css_provider, load_css, CssSource},
+    i18n::{tr, trf},
+    keys::{match_action, status_hints},
     layer::{apply_layer_shell, update_cursor_position},
     logging::log,
+    nav::{Nav, NavOptions},
+    session,
     vim::{
         get_vim_mode, handle_vim_insert_key, handle_vim_normal_key, set_vim_mode,
         update_mode_display,
@@ -21,18 +27,32 @@ use common::{
     Action, Anchor, VimAction, VimMode,
 };
 
-use crate::config::{default_css, Config, APP_NAME};
+use crate::config::{default_css, Config, OpenAnimation, APP_NAME};
 use crate::entries::{
-    delete_entry, fetch_entries_fast, generate_thumbnails_background, get_filtered_entry,
-    poll_thumbnail_results, select_entry, update_entry_thumbnail, ClipEntry, ThumbnailResult,
+    apply_binary_marker, complete_query, copy_image_path, copy_joined, decode_preview,
+    delete_entry, fetch_entries_fast, generate_thumbnails_background, large_thumbnail_background,
+    magick_command, paste_into_focused, poll_thumbnail_results, printable, select_entry,
+    update_entry_thumbnail, ClipEntry, ThumbnailResult,
 };
+use crate::mask::{apply_masking, MASK};
+use crate::preview::{hex_dump, prefix_text, set_preview_text};
 use crate::ui::{populate_list, update_row_thumbnail};
+use crate::view::EntryView;
 
 pub struct AppWidgets {
     pub search: Entry,
-    pub listbox: ListBox,
+    pub view: EntryView,
+    pub scroll: ScrolledWindow,
+    pub section_label: Label,
+    pub status_bar: GtkBox,
     pub status: Label,
+    pub spinner: Spinner,
     pub mode_label: Label,
+    pub preview: TextView,
+    pub preview_scroll: ScrolledWindow,
+    pub image_popover: Popover,
+    pub image_picture: Picture,
+    pub container: GtkBox,
     pub entries: Rc<RefCell<Vec<ClipEntry>>>,
 }
 
@@ -41,6 +61,285 @@ thread_local! {
     pub static CONFIG: RefCell<Config> = RefCell::new(Config::default());
     pub static THUMB_RESULTS: RefCell<Option<Arc<Mutex<Vec<ThumbnailResult>>>>> = const { RefCell::new(None) };
     pub static THUMB_POLL_COUNT: RefCell<usize> = const { RefCell::new(0) };
+    pub static THUMB_TOTAL: RefCell<usize> = const { RefCell::new(0) };
+    pub static OPEN_ANIMATION_GEN: RefCell<u64> = const { RefCell::new(0) };
+    pub static PENDING_DELETE: RefCell<Option<ClipEntry>> = const { RefCell::new(None) };
+    pub static IMAGE_POPOVER_GEN: RefCell<u64> = const { RefCell::new(0) };
+    /// Ids of entries marked for `copy_marked`, in the order they were marked
+    pub static MARKED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Wait this long after the selection settles before decoding a large image
+const IMAGE_POPOVER_DEBOUNCE_MS: u64 = 250;
+
+/// Footer hints, shown with whatever key each action is bound to
+const STATUS_HINTS: &[(Action, &str)] = &[(Action::Select, "select"), (Action::Delete, "delete")];
+/// In vim mode: the fixed normal-mode keys, then the bound actions
+const VIM_HINTS: &[(&str, &str)] = &[("i", "insert"), ("j/k", "nav"), ("dd", "delete")];
+const VIM_STATUS_HINTS: &[(Action, &str)] = &[(Action::Select, "select")];
+
+/// Copy `entry` and hide per `close_on_select`. With `auto_paste` the
+/// window always hides and the entry is pasted into the app underneath.
+fn choose_entry(entry: &ClipEntry, window: &ApplicationWindow) {
+    let (notify, notify_ms, close, paste) = CONFIG.with(|c| {
+        let c = c.borrow();
+        (
+            c.notify_on_copy,
+            c.notify_timeout_ms,
+            c.close_on_select || common::cli::is_oneshot(),
+            c.auto_paste
+                .then(|| (c.paste_command.clone(), c.paste_delay_ms)),
+        )
+    });
+    select_entry(entry, notify, notify_ms);
+    if let Some((command, delay_ms)) = paste {
+        window.set_visible(false);
+        paste_into_focused(&command, delay_ms, notify_ms);
+    } else if close {
+        window.set_visible(false);
+    }
+}
+
+/// Mark or unmark the selected entry and say how many are marked
+fn toggle_mark(view: &EntryView, entries: &[ClipEntry], status: &Label) {
+    let (Some(entry), Some(item)) = (view.selected_entry(entries), view.selected()) else {
+        return;
+    };
+    let count = MARKED.with(|m| {
+        let mut marked = m.borrow_mut();
+        match marked.iter().position(|id| *id == entry.id) {
+            Some(pos) => {
+                marked.remove(pos);
+                item.remove_css_class("clip-row-marked");
+            }
+            None => {
+                marked.push(entry.id.clone());
+                item.add_css_class("clip-row-marked");
+            }
+        }
+        marked.len()
+    });
+    if count == 0 {
+        restore_status(view, status);
+    } else {
+        status.set_text(&trf("{} marked", &[&count]));
+    }
+}
+
+/// Unmark everything, e.g. after a copy or when the rows are rebuilt
+fn clear_marks(view: &EntryView) {
+    MARKED.with(|m| m.borrow_mut().clear());
+    for item in view.all_items() {
+        item.remove_css_class("clip-row-marked");
+    }
+}
+
+/// Copy the marked entries (or the selected one if none are marked) as one
+/// text item joined by `join_separator`, then clear the marks
+fn copy_marked(view: &EntryView, entries: &[ClipEntry], status: &Label) -> bool {
+    let marked = MARKED.with(|m| m.borrow().clone());
+    let chosen: Vec<ClipEntry> = if marked.is_empty() {
+        view.selected_entry(entries).into_iter().collect()
+    } else {
+        marked
+            .iter()
+            .filter_map(|id| entries.iter().find(|e| e.id == *id).cloned())
+            .collect()
+    };
+    let (separator, notify, notify_ms) = CONFIG.with(|c| {
+        let c = c.borrow();
+        (
+            c.join_separator.clone(),
+            c.notify_on_copy,
+            c.notify_timeout_ms,
+        )
+    });
+    let (copied, skipped) = copy_joined(&chosen, &separator, notify, notify_ms);
+    clear_marks(view);
+    match (copied, skipped) {
+        (0, 0) => restore_status(view, status),
+        (0, _) => status.set_text(tr("Nothing to copy: marked entries aren't text")),
+        (n, 0) => status.set_text(&trf("Copied {} entries", &[&n])),
+        (n, s) => status.set_text(&trf("Copied {} entries, skipped {} non-text", &[&n, &s])),
+    }
+    copied > 0 && skipped == 0
+}
+
+fn restore_status(view: &EntryView, status: &Label) {
+    status.set_text(&trf("{} items", &[&view.len()]));
+}
+
+/// Delete now, or park the entry and ask first when confirm_delete is set
+fn delete_or_confirm(entry: ClipEntry, status: &Label) {
+    let cfg = CONFIG.with(|c| c.borrow().clone());
+    if cfg.confirm_delete {
+        PENDING_DELETE.with(|p| *p.borrow_mut() = Some(entry));
+        status.set_text(tr("Delete? y/n"));
+    } else {
+        delete_keep_position(&entry, &cfg);
+    }
+}
+
+/// Delete `entry`, then reselect the row at the same (clamped) index so
+/// deleting several entries in a row doesn't jump back to the top
+fn delete_keep_position(entry: &ClipEntry, cfg: &Config) {
+    let index = WIDGETS.with(|w| w.borrow().as_ref().and_then(|wg| wg.view.selected_index()));
+    delete_entry(entry);
+    refresh_entries(cfg.max_items, cfg.max_age_days);
+
+    let Some(index) = index else {
+        return;
+    };
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            if wg.view.select(index.min(wg.view.len() - 1)) {
+                wg.view.scroll_to_selected(
+                    &wg.scroll,
+                    cfg.base.animation_duration,
+                    cfg.base.animation_easing,
+                );
+            }
+        }
+    });
+}
+
+/// Drop a pending delete confirmation, e.g. when the selection moves or the window hides
+fn cancel_pending_delete() {
+    if PENDING_DELETE.with(|p| p.borrow_mut().take()).is_some() {
+        WIDGETS.with(|w| {
+            if let Some(ref wg) = *w.borrow() {
+                restore_status(&wg.view, &wg.status);
+            }
+        });
+    }
+}
+
+const SLIDE_OFFSET: f64 = 24.0;
+
+/// Widgets hidden while the window is collapsed to the search bar
+fn collapsible(wg: &AppWidgets, cfg: &Config) -> Vec<gtk4::Widget> {
+    let mut w: Vec<gtk4::Widget> = vec![
+        wg.scroll.clone().upcast(),
+        wg.section_label.clone().upcast(),
+        wg.status_bar.clone().upcast(),
+    ];
+    if cfg.show_preview {
+        w.push(wg.preview_scroll.clone().upcast());
+    }
+    w
+}
+
+/// `expand_on_open`: start expanded whenever there is history to show
+fn has_history(wg: &AppWidgets, cfg: &Config) -> bool {
+    cfg.expand_on_open && wg.view.len() > 0
+}
+
+fn expand(cfg: &Config) {
+    if !cfg.collapsed {
+        return;
+    }
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            anim::expand(
+                &wg.container,
+                &collapsible(wg, cfg),
+                cfg.search_height,
+                cfg.base.height,
+                cfg.base.animation_duration,
+                cfg.base.animation_easing,
+            );
+        }
+    });
+}
+
+fn collapse(cfg: &Config) {
+    if !cfg.collapsed {
+        return;
+    }
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            anim::collapse(
+                &wg.container,
+                &collapsible(wg, cfg),
+                cfg.search_height,
+                cfg.base.height,
+                cfg.base.animation_duration,
+                cfg.base.animation_easing,
+            );
+        }
+    });
+}
+
+/// Fade and/or slide the container in when the window is shown
+fn animate_open(container: &GtkBox, cfg: &Config) {
+    let gen = OPEN_ANIMATION_GEN.with(|g| {
+        let mut gen = g.borrow_mut();
+        *gen = gen.wrapping_add(1);
+        *gen
+    });
+
+    container.set_opacity(1.0);
+    container.set_margin_top(0);
+    let duration_ms = anim::duration(cfg.base.animation_duration);
+    if cfg.open_animation == OpenAnimation::None || duration_ms == 0 {
+        return;
+    }
+
+    let kind = cfg.open_animation;
+    let easing = cfg.base.animation_easing;
+    let steps = 20;
+    let step_ms = (duration_ms / steps).max(1);
+
+    container.set_opacity(0.0);
+    if kind == OpenAnimation::Slide {
+        container.set_margin_top(SLIDE_OFFSET as i32);
+    }
+
+    let container = container.clone();
+    let step = Rc::new(std::cell::Cell::new(0u64));
+    glib::timeout_add_local(std::time::Duration::from_millis(step_ms), move || {
+        if OPEN_ANIMATION_GEN.with(|g| *g.borrow()) != gen {
+            return glib::ControlFlow::Break;
+        }
+
+        let s = step.get() + 1;
+        step.set(s);
+        let eased = easing.apply(s as f64 / steps as f64);
+
+        container.set_opacity(eased.clamp(0.0, 1.0));
+        if kind == OpenAnimation::Slide {
+            container.set_margin_top((SLIDE_OFFSET * (1.0 - eased)).max(0.0) as i32);
+        }
+
+        if s >= steps {
+            container.set_opacity(1.0);
+            container.set_margin_top(0);
+            glib::ControlFlow::Break
+        } else {
+            glib::ControlFlow::Continue
+        }
+    });
+}
+
+/// Spinner plus "previews done/total" in the status bar while thumbnails
+/// are generated; back to the item count once they're all in
+fn show_thumb_progress(done: usize, total: usize) {
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            if done < total {
+                wg.spinner.set_visible(true);
+                wg.spinner.start();
+                wg.status.set_text(&trf(
+                    "{} items · previews {}/{}",
+                    &[&wg.view.len(), &done, &total],
+                ));
+            } else {
+                wg.spinner.stop();
+                wg.spinner.set_visible(false);
+                restore_status(&wg.view, &wg.status);
+            }
+        }
+    });
 }
 
 /// Start polling for thumbnail results
@@ -64,21 +363,20 @@ fn start_thumbnail_polling() {
                             for result in &new_results {
                                 if let Some(ref path) = result.path {
                                     update_entry_thumbnail(&mut ents, &result.id, path.clone());
-                                    update_row_thumbnail(&wg.listbox, &result.id, path);
+                                    update_row_thumbnail(&wg.view, &result.id, path);
                                 }
                             }
                         }
                     });
                 }
 
-                // Check if we might have more results coming
-                if let Ok(r) = results.lock() {
-                    // Continue polling if there might be more
-                    // Stop after 10 seconds of no new results or if all done
-                    r.len() < 1000 // Arbitrary large number - keep polling
-                } else {
-                    false
+                // One result arrives per thumbnail, failed or not
+                let done = THUMB_POLL_COUNT.with(|c| *c.borrow());
+                let total = THUMB_TOTAL.with(|t| *t.borrow());
+                if !new_results.is_empty() {
+                    show_thumb_progress(done, total);
                 }
+                done < total && results.lock().is_ok()
             } else {
                 false
             }
@@ -95,10 +393,284 @@ fn start_thumbnail_polling() {
     });
 }
 
-/// Refresh entries - called on toggle
-fn refresh_entries(max_items: usize) {
+/// Show the selected entry's full contents in the preview pane
+fn update_preview() {
+    let (show, highlight) = CONFIG.with(|c| {
+        let c = c.borrow();
+        (c.show_preview, c.highlight_code)
+    });
+    if !show {
+        return;
+    }
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            let buffer = wg.preview.buffer();
+            let entry = wg.view.selected_entry(&wg.entries.borrow());
+            match entry {
+                // Don't decode large blobs on every selection; cliphist's
+                // summary says enough
+                Some(e)
+                    if e.kind.is_binary()
+                        && e.preview.trim_start().starts_with("[[ binary data") =>
+                {
+                    buffer.set_text(e.preview.trim())
+                }
+                Some(e) if e.masked => buffer.set_text(MASK),
+                Some(e) => match decode_preview(&e).map(|(d, size)| (prefix_text(d, size), size)) {
+                    Some((Ok(text), _)) => set_preview_text(&buffer, &text, highlight),
+                    Some((Err(data), size)) => buffer.set_text(&hex_dump(&data, size)),
+                    None if e.kind.is_binary() => buffer.set_text(&printable(&e.preview)),
+                    None => set_preview_text(&buffer, &e.preview, highlight),
+                },
+                None => buffer.set_text(""),
+            }
+        }
+    });
+}
+
+type MenuAction = Box<dyn Fn()>;
+
+/// Popover listing what can be done with the selected entry, opened from
+/// the `actions_menu` key or a right-click. Items depend on the entry type.
+fn show_actions_menu(window: &ApplicationWindow) {
+    let Some((entry, row, list, status)) = WIDGETS.with(|w| {
+        let w = w.borrow();
+        let wg = w.as_ref()?;
+        let row = wg.view.selected()?;
+        let entry = wg.view.selected_entry(&wg.entries.borrow())?;
+        Some((entry, row, wg.view.widget(), wg.status.clone()))
+    }) else {
+        return;
+    };
+    hide_image_popover();
+
+    let (notify, notify_ms, close, paste_command, paste_delay_ms) = CONFIG.with(|c| {
+        let c = c.borrow();
+        (
+            c.notify_on_copy,
+            c.notify_timeout_ms,
+            c.close_on_select || common::cli::is_oneshot(),
+            c.paste_command.clone(),
+            c.paste_delay_ms,
+        )
+    });
+
+    let mut items: Vec<(&str, MenuAction)> = Vec::new();
+    let (e, win) = (entry.clone(), window.clone());
+    items.push(("Copy", Box::new(move || choose_entry(&e, &win))));
+    let (e, win) = (entry.clone(), window.clone());
+    items.push((
+        "Copy and paste",
+        Box::new(move || {
+            select_entry(&e, notify, notify_ms);
+            win.set_visible(false);
+            paste_into_focused(&paste_command, paste_delay_ms, notify_ms);
+        }),
+    ));
+    if entry.is_image() {
+        let (e, win) = (entry.clone(), window.clone());
+        items.push((
+            "Copy as file path",
+            Box::new(move || {
+                copy_image_path(&e, notify, notify_ms);
+                if close {
+                    win.set_visible(false);
+                }
+            }),
+        ));
+    }
+    items.push((
+        "Delete",
+        Box::new(move || delete_or_confirm(entry.clone(), &status)),
+    ));
+
+    let popover = Popover::new();
+    popover.add_css_class("clip-actions-menu");
+    popover.set_has_arrow(false);
+    popover.set_position(gtk4::PositionType::Bottom);
+    let menu = GtkBox::new(Orientation::Vertical, 0);
+    for (label, action) in items {
+        let text = Label::new(Some(tr(label)));
+        text.set_xalign(0.0);
+        let button = gtk4::Button::new();
+        button.set_child(Some(&text));
+        button.add_css_class("flat");
+        let p = popover.clone();
+        button.connect_clicked(move |_| {
+            p.popdown();
+            action();
+        });
+        menu.append(&button);
+    }
+    popover.set_child(Some(&menu));
+    popover.set_parent(&list);
+    if let Some(bounds) = row.compute_bounds(&list) {
+        popover.set_pointing_to(Some(&gdk4::Rectangle::new(
+            bounds.x() as i32,
+            bounds.y() as i32,
+            bounds.width() as i32,
+            bounds.height() as i32,
+        )));
+    }
+    // Built per use; drop it once closed
+    popover.connect_closed(|p| {
+        let p = p.clone();
+        glib::idle_add_local_once(move || p.unparent());
+    });
+    popover.popup();
+    if let Some(first) = menu.first_child() {
+        first.grab_focus();
+    }
+}
+
+fn hide_image_popover() {
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            wg.image_popover.popdown();
+        }
+    });
+}
+
+fn show_image_popover(path: &Path) {
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            let Some(row) = wg.view.selected() else {
+                return;
+            };
+            let Some(bounds) = row.compute_bounds(&wg.view.widget()) else {
+                return;
+            };
+            wg.image_picture.set_filename(Some(path));
+            wg.image_popover.set_pointing_to(Some(&gdk4::Rectangle::new(
+                bounds.x() as i32,
+                bounds.y() as i32,
+                bounds.width() as i32,
+                bounds.height() as i32,
+            )));
+            wg.image_popover.popup();
+        }
+    });
+}
+
+/// Show a larger decode of the selected image entry once navigation pauses.
+/// Each call supersedes the previous one, so fast scrolling decodes nothing.
+fn schedule_image_popover() {
+    let gen = IMAGE_POPOVER_GEN.with(|g| {
+        let mut gen = g.borrow_mut();
+        *gen = gen.wrapping_add(1);
+        *gen
+    });
+    let is_current = move || IMAGE_POPOVER_GEN.with(|g| *g.borrow() == gen);
+    hide_image_popover();
+
+    let (enabled, size) = CONFIG.with(|c| {
+        let c = c.borrow();
+        (c.image_popover, c.image_popover_size)
+    });
+    if !enabled || magick_command().is_none() {
+        return;
+    }
+
+    glib::timeout_add_local_once(
+        std::time::Duration::from_millis(IMAGE_POPOVER_DEBOUNCE_MS),
+        move || {
+            if !is_current() {
+                return;
+            }
+            let entry = WIDGETS.with(|w| {
+                let w = w.borrow();
+                let wg = w.as_ref()?;
+                let entry = wg.view.selected_entry(&wg.entries.borrow());
+                entry
+            });
+            let Some(entry) = entry.filter(|e| e.is_image()) else {
+                return;
+            };
+
+            let slot = large_thumbnail_background(&entry, size);
+            glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+                if !is_current() {
+                    return glib::ControlFlow::Break;
+                }
+                let done = slot.lock().ok().and_then(|mut s| s.take());
+                match done {
+                    None => glib::ControlFlow::Continue,
+                    Some(path) => {
+                        if let Some(path) = path {
+                            show_image_popover(&path);
+                        }
+                        glib::ControlFlow::Break
+                    }
+                }
+            });
+        },
+    );
+}
+
+/// The history as the list shows it: markers and masking applied, no
+/// thumbnails generated yet
+fn load_history(max_items: usize, max_age_days: u64) -> Vec<ClipEntry> {
+    let mut entries = fetch_entries_fast(max_items, max_age_days);
+    CONFIG.with(|c| {
+        let c = c.borrow();
+        apply_binary_marker(&mut entries, &c.binary_marker);
+        if c.mask_passwords {
+            apply_masking(&mut entries, &c.mask_regex);
+        }
+    });
+    entries
+}
+
+fn refresh_entries(max_items: usize, max_age_days: u64) {
     // Fast synchronous load first (no thumbnail generation)
-    let entries = fetch_entries_fast(max_items);
+    show_entries(load_history(max_items, max_age_days));
+}
+
+/// Refetch while the window is open (`refresh`, `auto_refresh_ms`). The
+/// query stays and the selected entry stays selected if it's still listed.
+/// With `only_if_changed`, nothing is rebuilt unless the history changed.
+fn refresh_in_place(only_if_changed: bool) {
+    let (max_items, max_age_days, duration, easing) = CONFIG.with(|c| {
+        let c = c.borrow();
+        (
+            c.max_items,
+            c.max_age_days,
+            c.base.animation_duration,
+            c.base.animation_easing,
+        )
+    });
+    let entries = load_history(max_items, max_age_days);
+    let Some(selected) = WIDGETS.with(|w| {
+        let w = w.borrow();
+        let wg = w.as_ref()?;
+        let current = wg.entries.borrow();
+        let same = current.len() == entries.len()
+            && current.iter().zip(&entries).all(|(a, b)| a.id == b.id);
+        if only_if_changed && same {
+            return None;
+        }
+        let selected = wg.view.selected_entry(&current).map(|e| e.id);
+        Some(selected)
+    }) else {
+        return;
+    };
+
+    show_entries(entries);
+
+    let Some(id) = selected else {
+        return;
+    };
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            if wg.view.index_of(&id).is_some_and(|i| wg.view.select(i)) {
+                wg.view.scroll_to_selected(&wg.scroll, duration, easing);
+            }
+        }
+    });
+}
+
+/// Show freshly loaded `entries` and start their thumbnails
+fn show_entries(entries: Vec<ClipEntry>) {
     let entries_for_thumbs = entries.clone();
 
     WIDGETS.with(|w| {
@@ -107,17 +679,32 @@ fn refresh_entries(max_items: usize) {
             *ents = entries;
 
             let query = wg.search.text().to_string();
-            let n = populate_list(&wg.listbox, &ents, &query);
-            wg.status.set_text(&format!("{} items", n));
+            let (initial, smartcase) = CONFIG.with(|c| {
+                let c = c.borrow();
+                (c.base.initial_selection, c.base.smartcase)
+            });
+            // Rebuild rather than reuse rows, so ages and thumbnails are current
+            wg.view.clear();
+            MARKED.with(|m| m.borrow_mut().clear());
+            let n = populate_list(&wg.view, &ents, &query, initial, smartcase);
+            wg.status.set_text(&trf("{} items", &[&n]));
         }
     });
 
     // Check if any entries need thumbnails
-    let needs_thumbs = entries_for_thumbs
-        .iter()
-        .any(|e| e.is_image && e.thumb_path.is_none());
+    let needs_thumbs = magick_command().is_some()
+        && entries_for_thumbs
+            .iter()
+            .any(|e| e.is_image() && e.thumb_path.is_none());
 
     if needs_thumbs {
+        let total = entries_for_thumbs
+            .iter()
+            .filter(|e| e.is_image() && e.thumb_path.is_none())
+            .count();
+        THUMB_TOTAL.with(|t| *t.borrow_mut() = total);
+        show_thumb_progress(0, total);
+
         // Start background thumbnail generation
         let results = generate_thumbnails_background(entries_for_thumbs);
 
@@ -139,27 +726,47 @@ pub fn activate(app: &Application) {
     }
 
     if let Some(win) = app.active_window() {
-        if win.is_visible() {
+        if win.is_visible() && !win.is_active() {
+            // Open but unfocused: take focus back instead of hiding
+            win.present();
+            focus_input(cfg.vim_mode);
+        } else if win.is_visible() {
             win.set_visible(false);
         } else {
             if cfg.base.anchor == Anchor::Cursor {
-                update_cursor_position(&win);
+                update_cursor_position(&win, &cfg.base, APP_NAME);
             }
 
             if cfg.vim_mode {
                 set_vim_mode(VimMode::Normal);
             }
 
+            let (query, selected) = session::saved_search(cfg.base.remember_search);
+
             // Refresh entries (fast + async thumbnails)
-            refresh_entries(cfg.max_items);
+            refresh_entries(cfg.max_items, cfg.max_age_days);
 
             WIDGETS.with(|w| {
                 if let Some(ref wg) = *w.borrow() {
-                    wg.search.set_text("");
+                    wg.search.set_text(&query);
+                    wg.preview_scroll.set_visible(cfg.show_preview);
+                    if cfg.collapsed {
+                        anim::reset_collapsed(
+                            &wg.container,
+                            &collapsible(wg, &cfg),
+                            cfg.base.width,
+                            cfg.search_height,
+                        );
+                    }
+                    if !query.is_empty() || has_history(wg, &cfg) {
+                        expand(&cfg);
+                    }
+                    wg.view.restore_selection(selected);
+                    animate_open(&wg.container, &cfg);
 
                     if cfg.vim_mode {
                         update_mode_display(&wg.mode_label, VimMode::Normal);
-                        wg.listbox.grab_focus();
+                        wg.view.widget().grab_focus();
                     } else {
                         wg.search.grab_focus();
                     }
@@ -171,38 +778,47 @@ pub fn activate(app: &Application) {
         return;
     }
 
-    let css_content = if let Ok(theme) = std::env::var("GUI_THEME_OVERRIDE") {
-        common::paths::get_theme_css(&theme)
-            .unwrap_or_else(|| load_css(APP_NAME, &cfg.base.theme, default_css()))
-    } else if !cfg.base.theme.contains('/') && !cfg.base.theme.ends_with(".css") {
-        common::paths::get_theme_css(&cfg.base.theme).unwrap_or_else(|| default_css().to_string())
-    } else {
-        load_css(APP_NAME, &cfg.base.theme, default_css())
+    common::css::apply_background(cfg.base.opaque_background.as_deref());
+    let css_source = match std::env::var("GUI_THEME_OVERRIDE")
+        .ok()
+        .and_then(|t| common::paths::get_theme_css(&t))
+    {
+        Some(css) => CssSource::Builtin(css),
+        None => load_css(APP_NAME, &cfg.base.theme, default_css()),
     };
 
-    let provider = CssProvider::new();
-    provider.load_from_data(&css_content);
+    let provider = css_provider(APP_NAME, &css_source);
     gtk4::style_context_add_provider_for_display(
         &gdk4::Display::default().expect("no display"),
         &provider,
         gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
     );
+    common::css::apply_wallpaper_colors(APP_NAME, cfg.base.follow_wallpaper);
 
     let entries: Rc<RefCell<Vec<ClipEntry>>> = Rc::new(RefCell::new(Vec::new()));
 
+    let initial_height = if cfg.collapsed {
+        cfg.search_height
+    } else {
+        cfg.base.height
+    };
+
     let window = ApplicationWindow::builder()
         .application(app)
         .default_width(cfg.base.width)
-        .default_height(cfg.base.height)
+        .default_height(initial_height)
         .resizable(false)
         .build();
 
     apply_layer_shell(&window, &cfg.base, APP_NAME);
-    window.set_default_size(cfg.base.width, cfg.base.height);
+    window.set_default_size(cfg.base.width, initial_height);
 
     let container = GtkBox::new(Orientation::Vertical, 0);
     container.add_css_class("clip-container");
-    container.set_size_request(cfg.base.width, cfg.base.height);
+    if cfg.collapsed {
+        container.add_css_class("collapsed");
+    }
+    container.set_size_request(cfg.base.width, initial_height);
 
     // header
     let header = GtkBox::new(Orientation::Vertical, 0);
@@ -211,7 +827,7 @@ pub fn activate(app: &Application) {
     let search_row = GtkBox::new(Orientation::Horizontal, 8);
     search_row.add_css_class("clip-search-row");
     let search = Entry::new();
-    search.set_placeholder_text(Some("Search clipboard history..."));
+    search.set_placeholder_text(Some(tr(&cfg.search_placeholder)));
     search.add_css_class("clip-search");
     search.set_hexpand(true);
     search_row.append(&search);
@@ -221,15 +837,16 @@ pub fn activate(app: &Application) {
     let esc_badge = Label::new(Some("esc"));
     esc_badge.add_css_class("clip-esc-badge");
     hint_box.append(&esc_badge);
-    let hint_text = Label::new(Some("to close"));
+    let hint_text = Label::new(Some(tr("to close")));
     hint_text.add_css_class("clip-hint-text");
     hint_box.append(&hint_text);
     search_row.append(&hint_box);
     header.append(&search_row);
 
-    let recent_label = Label::new(Some("Recent"));
+    let recent_label = Label::new(Some(tr(&cfg.section_label)));
     recent_label.set_xalign(0.0);
     recent_label.add_css_class("clip-section-label");
+    recent_label.set_visible(!cfg.collapsed);
     header.append(&recent_label);
     container.append(&header);
 
@@ -238,16 +855,53 @@ pub fn activate(app: &Application) {
     scroll.set_vexpand(true);
     scroll.set_hscrollbar_policy(gtk4::PolicyType::Never);
     scroll.set_vscrollbar_policy(gtk4::PolicyType::Automatic);
-    let listbox = ListBox::new();
-    listbox.add_css_class("clip-list");
-    listbox.set_selection_mode(gtk4::SelectionMode::Single);
-    scroll.set_child(Some(&listbox));
+    scroll.set_visible(!cfg.collapsed);
+    let view = EntryView::new(cfg.layout);
+    scroll.set_child(Some(&view.widget()));
     container.append(&scroll);
     let scroll_k = scroll.clone();
 
+    // preview pane
+    let preview_scroll = ScrolledWindow::new();
+    preview_scroll.add_css_class("clip-preview");
+    preview_scroll.set_hscrollbar_policy(gtk4::PolicyType::Never);
+    preview_scroll.set_min_content_height(140);
+    preview_scroll.set_max_content_height(140);
+    let preview = TextView::new();
+    preview.set_editable(false);
+    preview.set_cursor_visible(false);
+    preview.set_monospace(true);
+    preview.set_wrap_mode(gtk4::WrapMode::WordChar);
+    preview.add_css_class("clip-preview-text");
+    preview_scroll.set_child(Some(&preview));
+    preview_scroll.set_visible(cfg.show_preview && !cfg.collapsed);
+    container.append(&preview_scroll);
+
+    // Deferred so the entries borrow held during populate_list is released first
+    view.connect_selection_changed(|| {
+        glib::idle_add_local_once(|| {
+            cancel_pending_delete();
+            update_preview();
+            schedule_image_popover();
+        });
+    });
+
+    // larger image preview, anchored to the selected row
+    let image_picture = Picture::new();
+    image_picture.set_can_shrink(true);
+    image_picture.set_size_request(cfg.image_popover_size as i32, cfg.image_popover_size as i32);
+    let image_popover = Popover::new();
+    image_popover.add_css_class("clip-image-popover");
+    image_popover.set_child(Some(&image_picture));
+    image_popover.set_position(gtk4::PositionType::Right);
+    image_popover.set_autohide(false);
+    image_popover.set_has_arrow(false);
+    image_popover.set_parent(&view.widget());
+
     // status bar
     let status_bar = GtkBox::new(Orientation::Horizontal, 0);
     status_bar.add_css_class("clip-status-bar");
+    status_bar.set_visible(!cfg.collapsed);
 
     let mode_label = Label::new(Some(""));
     mode_label.add_css_class("vim-mode-indicator");
@@ -260,7 +914,12 @@ pub fn activate(app: &Application) {
     }
     status_bar.append(&mode_label);
 
-    let status = Label::new(Some("Loading..."));
+    let spinner = Spinner::new();
+    spinner.add_css_class("clip-spinner");
+    spinner.set_visible(false);
+    status_bar.append(&spinner);
+
+    let status = Label::new(Some(tr("Loading...")));
     status.add_css_class("clip-status-left");
     status.set_halign(Align::Start);
     status.set_hexpand(true);
@@ -269,33 +928,24 @@ pub fn activate(app: &Application) {
     let hints = GtkBox::new(Orientation::Horizontal, 12);
     hints.set_halign(Align::End);
 
-    if cfg.vim_mode {
-        for (k, h) in [
-            ("i", "insert"),
-            ("j/k", "nav"),
-            ("dd", "delete"),
-            ("Enter", "select"),
-        ] {
-            let b = GtkBox::new(Orientation::Horizontal, 0);
-            let kl = Label::new(Some(k));
-            kl.add_css_class("clip-status-key");
-            b.append(&kl);
-            let hl = Label::new(Some(h));
-            hl.add_css_class("clip-status-hint");
-            b.append(&hl);
-            hints.append(&b);
-        }
+    let hint_keys = if cfg.vim_mode {
+        VIM_HINTS
+            .iter()
+            .map(|&(k, h)| (k.to_string(), h))
+            .chain(status_hints(&cfg.base.keybinds, VIM_STATUS_HINTS))
+            .collect()
     } else {
-        for (k, h) in [("Enter", "select"), ("Del", "delete")] {
-            let b = GtkBox::new(Orientation::Horizontal, 0);
-            let kl = Label::new(Some(k));
-            kl.add_css_class("clip-status-key");
-            b.append(&kl);
-            let hl = Label::new(Some(h));
-            hl.add_css_class("clip-status-hint");
-            b.append(&hl);
-            hints.append(&b);
-        }
+        status_hints(&cfg.base.keybinds, STATUS_HINTS)
+    };
+    for (k, h) in hint_keys {
+        let b = GtkBox::new(Orientation::Horizontal, 0);
+        let kl = Label::new(Some(&k));
+        kl.add_css_class("clip-status-key");
+        b.append(&kl);
+        let hl = Label::new(Some(tr(h)));
+        hl.add_css_class("clip-status-hint");
+        b.append(&hl);
+        hints.append(&b);
     }
     status_bar.append(&hints);
     container.append(&status_bar);
@@ -303,29 +953,116 @@ pub fn activate(app: &Application) {
 
     // search handler
     let entries_f = entries.clone();
-    let listbox_f = listbox.clone();
+    let view_f = view.clone();
     let status_f = status.clone();
     search.connect_changed(move |s| {
         let q = s.text().to_string();
+        let cfg = CONFIG.with(|c| c.borrow().clone());
+
+        // Expand before populating so the list is visible when rows land
+        if !q.is_empty() && !is_expanded() {
+            expand(&cfg);
+        } else if q.is_empty() && is_expanded() && !cfg.expand_on_open {
+            collapse(&cfg);
+        }
+
         let ents = entries_f.borrow();
-        let n = populate_list(&listbox_f, &ents, &q);
-        status_f.set_text(&format!("{} items", n));
+        let n = populate_list(
+            &view_f,
+            &ents,
+            &q,
+            cfg.base.initial_selection,
+            cfg.base.smartcase,
+        );
+        status_f.set_text(&trf("{} items", &[&n]));
     });
 
     // keybinds
     let key_ctrl = EventControllerKey::new();
     key_ctrl.set_propagation_phase(gtk4::PropagationPhase::Capture);
     let ek = entries.clone();
-    let lk = listbox.clone();
+    let lk = view.clone();
     let wk = window.clone();
     let sk = search.clone();
     let mode_k = mode_label.clone();
+    let status_k = status.clone();
 
     key_ctrl.connect_key_pressed(move |_, key, _, mods| {
         let vim_enabled = CONFIG.with(|c| c.borrow().vim_mode);
-        let close_on_select = CONFIG.with(|c| c.borrow().close_on_select);
-        let notify = CONFIG.with(|c| c.borrow().notify_on_copy);
-        let max_items = CONFIG.with(|c| c.borrow().max_items);
+        let (nav_opts, escape_clears) = CONFIG.with(|c| {
+            let c = c.borrow();
+            (NavOptions::from_config(&c.base), c.base.escape_clears_first)
+        });
+        let close_on_select =
+            CONFIG.with(|c| c.borrow().close_on_select) || common::cli::is_oneshot();
+        let (notify, notify_ms) = CONFIG.with(|c| {
+            let c = c.borrow();
+            (c.notify_on_copy, c.notify_timeout_ms)
+        });
+
+        // Tab in the search field completes the query instead of moving
+        // (`tab_complete`); in the list it still navigates
+        let plain = !mods.intersects(
+            gdk4::ModifierType::CONTROL_MASK
+                | gdk4::ModifierType::SHIFT_MASK
+                | gdk4::ModifierType::ALT_MASK
+                | gdk4::ModifierType::SUPER_MASK,
+        );
+        if key == gdk4::Key::Tab
+            && plain
+            && CONFIG.with(|c| c.borrow().tab_complete)
+            && common::keys::entry_has_focus(&wk, &sk)
+        {
+            let smartcase = CONFIG.with(|c| c.borrow().base.smartcase);
+            if let Some(completed) = complete_query(&ek.borrow(), &sk.text(), smartcase) {
+                sk.set_text(&completed);
+                sk.set_position(-1);
+            }
+            return glib::Propagation::Stop;
+        }
+
+        // A pending "Delete? y/n" swallows the next key
+        if let Some(e) = PENDING_DELETE.with(|p| p.borrow_mut().take()) {
+            if matches!(common::keys::key_to_char(key), Some('y' | 'Y')) {
+                let cfg = CONFIG.with(|c| c.borrow().clone());
+                delete_keep_position(&e, &cfg);
+            } else {
+                restore_status(&lk, &status_k);
+            }
+            return glib::Propagation::Stop;
+        }
+
+        // Works in both vim and normal mode; Ctrl+Shift+C by default
+        let action = CONFIG.with(|c| match_action(&c.borrow().base.keybinds, key, mods));
+        if action == Some(Action::ActionsMenu) {
+            show_actions_menu(&wk);
+            return glib::Propagation::Stop;
+        }
+        if action == Some(Action::ToggleMark) {
+            toggle_mark(&lk, &ek.borrow(), &status_k);
+            return glib::Propagation::Stop;
+        }
+        if action == Some(Action::CopyMarked) {
+            let done = copy_marked(&lk, &ek.borrow(), &status_k);
+            // Stay open when something was skipped so the status can be read
+            if done && close_on_select {
+                wk.set_visible(false);
+            }
+            return glib::Propagation::Stop;
+        }
+        if action == Some(Action::Refresh) {
+            refresh_in_place(false);
+            return glib::Propagation::Stop;
+        }
+        if action == Some(Action::CopyPath) {
+            if let Some(e) = lk.selected_entry(&ek.borrow()) {
+                copy_image_path(&e, notify, notify_ms);
+                if close_on_select {
+                    wk.set_visible(false);
+                }
+            }
+            return glib::Propagation::Stop;
+        }
 
         if vim_enabled {
             let current_mode = get_vim_mode();
@@ -333,91 +1070,29 @@ pub fn activate(app: &Application) {
             match current_mode {
                 VimMode::Normal => {
                     if let Some(action) = handle_vim_normal_key(key, mods, true) {
+                        if let Some(nav) = Nav::from_vim(action) {
+                            lk.navigate(&scroll_k, nav, &nav_opts);
+                            return glib::Propagation::Stop;
+                        }
                         match action {
                             VimAction::Close => {
                                 wk.set_visible(false);
                             }
                             VimAction::Select => {
-                                if let Some(row) = lk.selected_row() {
-                                    let ents = ek.borrow();
-                                    if let Some(e) =
-                                        get_filtered_entry(&ents, &sk.text(), row.index() as usize)
-                                    {
-                                        select_entry(&e, notify);
-                                        if close_on_select {
-                                            wk.set_visible(false);
-                                        }
-                                    }
+                                if let Some(e) = lk.selected_entry(&ek.borrow()) {
+                                    choose_entry(&e, &wk);
                                 }
                             }
                             VimAction::Delete => {
-                                if let Some(row) = lk.selected_row() {
-                                    let ents = ek.borrow();
-                                    if let Some(e) =
-                                        get_filtered_entry(&ents, &sk.text(), row.index() as usize)
-                                    {
-                                        delete_entry(&e);
-                                    }
-                                    drop(ents);
-                                    refresh_entries(max_items);
+                                if let Some(e) = lk.selected_entry(&ek.borrow()) {
+                                    delete_or_confirm(e, &status_k);
                                 }
                             }
                             VimAction::EnterInsert => {
                                 set_vim_mode(VimMode::Insert);
                                 update_mode_display(&mode_k, VimMode::Insert);
                                 sk.grab_focus();
-                            }
-                            VimAction::Down => {
-                                if let Some(r) = lk.selected_row() {
-                                    if let Some(n) = lk.row_at_index(r.index() + 1) {
-                                        lk.select_row(Some(&n));
-                                        common::css::scroll_to_selected(&lk, &scroll_k);
-                                    }
-                                }
-                            }
-                            VimAction::Up => {
-                                if let Some(r) = lk.selected_row() {
-                                    if r.index() > 0 {
-                                        if let Some(p) = lk.row_at_index(r.index() - 1) {
-                                            lk.select_row(Some(&p));
-                                            common::css::scroll_to_selected(&lk, &scroll_k);
-                                        }
-                                    }
-                                }
-                            }
-                            VimAction::Top => {
-                                if let Some(r) = lk.row_at_index(0) {
-                                    lk.select_row(Some(&r));
-                                    common::css::scroll_to_selected(&lk, &scroll_k);
-                                }
-                            }
-                            VimAction::Bottom => {
-                                let n = lk.observe_children().n_items();
-                                if n > 0 {
-                                    if let Some(r) = lk.row_at_index(n as i32 - 1) {
-                                        lk.select_row(Some(&r));
-                                        common::css::scroll_to_selected(&lk, &scroll_k);
-                                    }
-                                }
-                            }
-                            VimAction::HalfPageDown => {
-                                if let Some(r) = lk.selected_row() {
-                                    let t = (r.index() + 10)
-                                        .min(lk.observe_children().n_items() as i32 - 1);
-                                    if let Some(nr) = lk.row_at_index(t) {
-                                        lk.select_row(Some(&nr));
-                                        common::css::scroll_to_selected(&lk, &scroll_k);
-                                    }
-                                }
-                            }
-                            VimAction::HalfPageUp => {
-                                if let Some(r) = lk.selected_row() {
-                                    let t = (r.index() - 10).max(0);
-                                    if let Some(nr) = lk.row_at_index(t) {
-                                        lk.select_row(Some(&nr));
-                                        common::css::scroll_to_selected(&lk, &scroll_k);
-                                    }
-                                }
+                                expand(&CONFIG.with(|c| c.borrow().clone()));
                             }
                             _ => {}
                         }
@@ -430,20 +1105,16 @@ pub fn activate(app: &Application) {
                         if action == VimAction::ExitInsert {
                             set_vim_mode(VimMode::Normal);
                             update_mode_display(&mode_k, VimMode::Normal);
-                            lk.grab_focus();
+                            lk.widget().grab_focus();
+
+                            if sk.text().is_empty() {
+                                collapse(&CONFIG.with(|c| c.borrow().clone()));
+                            }
                         }
                     }
                     if key == gdk4::Key::Return {
-                        if let Some(row) = lk.selected_row() {
-                            let ents = ek.borrow();
-                            if let Some(e) =
-                                get_filtered_entry(&ents, &sk.text(), row.index() as usize)
-                            {
-                                select_entry(&e, notify);
-                                if close_on_select {
-                                    wk.set_visible(false);
-                                }
-                            }
+                        if let Some(e) = lk.selected_entry(&ek.borrow()) {
+                            choose_entry(&e, &wk);
                         }
                         return glib::Propagation::Stop;
                     }
@@ -452,136 +1123,175 @@ pub fn activate(app: &Application) {
                 }
             }
         } else {
+            // Left/Right step between grid cards, unless they'd move the
+            // cursor through a typed query
+            let sideways = match key {
+                gdk4::Key::Left | gdk4::Key::KP_Left => Some(Nav::Left),
+                gdk4::Key::Right | gdk4::Key::KP_Right => Some(Nav::Right),
+                _ => None,
+            };
+            if let Some(nav) = sideways.filter(|_| {
+                lk.is_grid()
+                    && plain
+                    && (sk.text().is_empty() || !common::keys::entry_has_focus(&wk, &sk))
+            }) {
+                lk.navigate(&scroll_k, nav, &nav_opts);
+                return glib::Propagation::Stop;
+            }
+            // Cursor keys edit the query while the search entry has focus
+            if common::keys::is_text_editing_key(key, mods)
+                && common::keys::entry_has_focus(&wk, &sk)
+            {
+                return glib::Propagation::Proceed;
+            }
             let action = CONFIG.with(|c| match_action(&c.borrow().base.keybinds, key, mods));
 
             if let Some(action) = action {
+                if let Some(nav) = Nav::from_action(&action) {
+                    lk.navigate(&scroll_k, nav, &nav_opts);
+                    return glib::Propagation::Stop;
+                }
                 match action {
                     Action::Close => {
-                        wk.set_visible(false);
+                        // Optionally a first Escape only empties the query
+                        if escape_clears && !sk.text().is_empty() {
+                            sk.set_text("");
+                        } else {
+                            wk.set_visible(false);
+                        }
                     }
                     Action::Select => {
-                        if let Some(row) = lk.selected_row() {
-                            let ents = ek.borrow();
-                            if let Some(e) =
-                                get_filtered_entry(&ents, &sk.text(), row.index() as usize)
-                            {
-                                select_entry(&e, notify);
-                                if close_on_select {
-                                    wk.set_visible(false);
-                                }
-                            }
+                        if let Some(e) = lk.selected_entry(&ek.borrow()) {
+                            choose_entry(&e, &wk);
                         }
                     }
+                    Action::SelectToggleTerminal => {} // Launcher only
+                    Action::ActionsMenu => {}          // Handled before the vim split
+                    Action::Next
+                    | Action::Prev
+                    | Action::PageDown
+                    | Action::PageUp
+                    | Action::First
+                    | Action::Last => {} // Handled by navigate above
+                    Action::CopyPath
+                    | Action::ToggleMark
+                    | Action::CopyMarked
+                    | Action::Refresh => {} // Handled above
                     Action::Delete => {
-                        if let Some(row) = lk.selected_row() {
-                            let ents = ek.borrow();
-                            if let Some(e) =
-                                get_filtered_entry(&ents, &sk.text(), row.index() as usize)
-                            {
-                                delete_entry(&e);
-                            }
-                            drop(ents);
-                            refresh_entries(max_items);
+                        if let Some(e) = lk.selected_entry(&ek.borrow()) {
+                            delete_or_confirm(e, &status_k);
                         }
                     }
                     Action::ClearSearch => {
                         sk.set_text("");
                     }
-                    Action::Next => {
-                        if let Some(r) = lk.selected_row() {
-                            if let Some(n) = lk.row_at_index(r.index() + 1) {
-                                lk.select_row(Some(&n));
-                                common::css::scroll_to_selected(&lk, &scroll_k);
-                            }
-                        }
-                    }
-                    Action::Prev => {
-                        if let Some(r) = lk.selected_row() {
-                            if r.index() > 0 {
-                                if let Some(p) = lk.row_at_index(r.index() - 1) {
-                                    lk.select_row(Some(&p));
-                                    common::css::scroll_to_selected(&lk, &scroll_k);
-                                }
-                            }
-                        }
-                    }
-                    Action::PageDown => {
-                        if let Some(r) = lk.selected_row() {
-                            let t =
-                                (r.index() + 10).min(lk.observe_children().n_items() as i32 - 1);
-                            if let Some(nr) = lk.row_at_index(t) {
-                                lk.select_row(Some(&nr));
-                                common::css::scroll_to_selected(&lk, &scroll_k);
-                            }
-                        }
-                    }
-                    Action::PageUp => {
-                        if let Some(r) = lk.selected_row() {
-                            let t = (r.index() - 10).max(0);
-                            if let Some(nr) = lk.row_at_index(t) {
-                                lk.select_row(Some(&nr));
-                                common::css::scroll_to_selected(&lk, &scroll_k);
-                            }
-                        }
-                    }
-                    Action::First => {
-                        if let Some(r) = lk.row_at_index(0) {
-                            lk.select_row(Some(&r));
-                            common::css::scroll_to_selected(&lk, &scroll_k);
-                        }
-                    }
-                    Action::Last => {
-                        let n = lk.observe_children().n_items();
-                        if n > 0 {
-                            if let Some(r) = lk.row_at_index(n as i32 - 1) {
-                                lk.select_row(Some(&r));
-                                common::css::scroll_to_selected(&lk, &scroll_k);
-                            }
-                        }
-                    }
                 }
                 return glib::Propagation::Stop;
             }
         }
         glib::Propagation::Proceed
     });
+    if common::theme_preview::is_active() {
+        common::theme_preview::attach(window.upcast_ref(), APP_NAME, &status, &cfg.base.theme);
+    }
     window.add_controller(key_ctrl);
+    let search_v = search.clone();
+    let view_v = view.clone();
+    window.connect_visible_notify(move |w| {
+        cancel_pending_delete();
+        if !w.is_visible() {
+            hide_image_popover();
+            session::save_search(&search_v.text(), view_v.selected_index());
+            if common::cli::is_oneshot() {
+                if let Some(app) = w.application() {
+                    app.quit();
+                }
+            }
+        }
+    });
+
+    // right-click for the actions menu
+    let right_click = gtk4::GestureClick::new();
+    right_click.set_button(gdk4::BUTTON_SECONDARY);
+    let lr = view.clone();
+    let wr = window.clone();
+    right_click.connect_pressed(move |_, _, x, y| {
+        if let Some(index) = lr.index_at(x, y) {
+            lr.select(index);
+            show_actions_menu(&wr);
+        }
+    });
+    view.widget().add_controller(right_click);
 
     // click to select
     let ec = entries.clone();
     let wc = window.clone();
-    let sc = search.clone();
-    let cfg_c = cfg.clone();
-    listbox.connect_row_activated(move |_, row| {
+    let vc = view.clone();
+    view.connect_activated(move |index| {
         let ents = ec.borrow();
-        if let Some(e) = get_filtered_entry(&ents, &sc.text(), row.index() as usize) {
-            select_entry(&e, cfg_c.notify_on_copy);
-            if cfg_c.close_on_select {
-                wc.set_visible(false);
-            }
+        if let Some(e) = vc.entry(index, &ents) {
+            choose_entry(&e, &wc);
         }
     });
 
     WIDGETS.with(|w| {
         *w.borrow_mut() = Some(AppWidgets {
             search: search.clone(),
-            listbox: listbox.clone(),
+            view: view.clone(),
+            scroll: scroll.clone(),
+            section_label: recent_label.clone(),
+            status_bar: status_bar.clone(),
             status: status.clone(),
+            spinner: spinner.clone(),
             mode_label: mode_label.clone(),
+            preview: preview.clone(),
+            preview_scroll: preview_scroll.clone(),
+            image_popover: image_popover.clone(),
+            image_picture: image_picture.clone(),
+            container: container.clone(),
             entries: entries.clone(),
         });
     });
 
     // Initial fast load
-    refresh_entries(cfg.max_items);
+    refresh_entries(cfg.max_items, cfg.max_age_days);
+    if cfg.live_refresh {
+        let win = window.clone();
+        crate::watch::watch_store(move || {
+            if win.is_visible() {
+                refresh_in_place(true);
+            }
+        });
+    }
+    if cfg.auto_refresh_ms > 0 {
+        let win = window.clone();
+        glib::timeout_add_local(
+            std::time::Duration::from_millis(cfg.auto_refresh_ms),
+            move || {
+                if win.is_visible() {
+                    refresh_in_place(true);
+                }
+                glib::ControlFlow::Continue
+            },
+        );
+    }
+    if cfg.expand_on_open && view.len() > 0 {
+        expand(&cfg);
+    }
 
+    animate_open(&container, &cfg);
     window.present();
 
     if cfg.vim_mode {
-        listbox.grab_focus();
+        view.widget().grab_focus();
     } else {
         search.grab_focus();
     }
+    if let Some(query) = take_query_request(APP_NAME) {
+        show_requested_query(&query, cfg.vim_mode);
+    }
+
+    cfg.base.report_config_problems(APP_NAME);
 
     log(
         APP_NAME,
@@ -592,6 +1302,33 @@ pub fn activate(app: &Application) {
     );
 }
 
+/// Focus the list in vim mode, the search entry otherwise
+fn focus_input(vim_mode: bool) {
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            if vim_mode {
+                wg.view.widget().grab_focus();
+            } else {
+                wg.search.grab_focus();
+            }
+        }
+    });
+}
+
+/// Put a `--query` in the search box, with the cursor after it rather than
+/// the text selected, so typing adds to it
+fn show_requested_query(query: &str, vim_mode: bool) {
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            wg.search.set_text(query);
+            if !vim_mode {
+                wg.search.grab_focus_without_selecting();
+                wg.search.set_position(-1);
+            }
+        }
+    });
+}
+
 pub fn setup_signals(app: &Application) {
     glib::unix_signal_add_local(libc::SIGUSR1, {
         let app = app.clone();
@@ -599,28 +1336,63 @@ pub fn setup_signals(app: &Application) {
             let cfg = Config::load();
             CONFIG.with(|c| *c.borrow_mut() = cfg.clone());
 
+            let request = take_window_request(APP_NAME);
+            let requested = take_query_request(APP_NAME);
             if let Some(win) = app.active_window() {
-                if win.is_visible() {
+                let toggle = request == WindowRequest::Toggle;
+                if request == WindowRequest::Close
+                    || (toggle && win.is_visible() && win.is_active())
+                {
                     win.set_visible(false);
+                } else if win.is_visible() {
+                    // `open`, or a toggle while open but unfocused: take
+                    // focus back instead of hiding
+                    win.present();
+                    focus_input(cfg.vim_mode);
+                    if let Some(query) = &requested {
+                        show_requested_query(query, cfg.vim_mode);
+                    }
                 } else {
                     if cfg.base.anchor == Anchor::Cursor {
-                        update_cursor_position(&win);
+                        update_cursor_position(&win, &cfg.base, APP_NAME);
                     }
 
                     if cfg.vim_mode {
                         set_vim_mode(VimMode::Normal);
                     }
 
+                    let (query, selected) = match &requested {
+                        Some(query) => (query.clone(), None),
+                        None => session::saved_search(cfg.base.remember_search),
+                    };
+
                     // Async refresh
-                    refresh_entries(cfg.max_items);
+                    refresh_entries(cfg.max_items, cfg.max_age_days);
 
                     WIDGETS.with(|w| {
                         if let Some(ref wg) = *w.borrow() {
-                            wg.search.set_text("");
+                            wg.search.set_text(&query);
+                            wg.preview_scroll.set_visible(cfg.show_preview);
+                            if cfg.collapsed {
+                                anim::reset_collapsed(
+                                    &wg.container,
+                                    &collapsible(wg, &cfg),
+                                    cfg.base.width,
+                                    cfg.search_height,
+                                );
+                            }
+                            if !query.is_empty() || has_history(wg, &cfg) {
+                                expand(&cfg);
+                            }
+                            wg.view.restore_selection(selected);
+                            animate_open(&wg.container, &cfg);
 
                             if cfg.vim_mode {
                                 update_mode_display(&wg.mode_label, VimMode::Normal);
-                                wg.listbox.grab_focus();
+                                wg.view.widget().grab_focus();
+                            } else if requested.is_some() {
+                                wg.search.grab_focus_without_selecting();
+                                wg.search.set_position(-1);
                             } else {
                                 wg.search.grab_focus();
                             }
@@ -636,16 +1408,33 @@ pub fn setup_signals(app: &Application) {
 
     glib::unix_signal_add_local(libc::SIGUSR2, {
         move || {
+            // `--restart-if-unresponsive` checking we're alive. Signals
+            // coalesce, so a theme request may have arrived with it.
+            let pinged = common::cli::answer_ping(APP_NAME);
+            // `--theme <name>` against a running daemon: switch in place
+            if let Some(theme) = common::cli::take_theme_request(APP_NAME) {
+                match common::paths::get_theme_css(&theme) {
+                    Some(css) => {
+                        common::css::apply_override_css(APP_NAME, &CssSource::Builtin(css));
+                        log(APP_NAME, &format!("switched to theme {}", theme));
+                    }
+                    None => log(APP_NAME, &format!("unknown theme requested: {}", theme)),
+                }
+                return glib::ControlFlow::Continue;
+            }
+            if pinged {
+                return glib::ControlFlow::Continue;
+            }
+
             let cfg = Config::load();
             CONFIG.with(|c| *c.borrow_mut() = cfg.clone());
 
-            let provider = CssProvider::new();
-            provider.load_from_data(&load_css(APP_NAME, &cfg.base.theme, default_css()));
-            gtk4::style_context_add_provider_for_display(
-                &gdk4::Display::default().expect("no display"),
-                &provider,
-                gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+            common::css::apply_background(cfg.base.opaque_background.as_deref());
+            common::css::apply_override_css(
+                APP_NAME,
+                &load_css(APP_NAME, &cfg.base.theme, default_css()),
             );
+            common::css::apply_wallpaper_colors(APP_NAME, cfg.base.follow_wallpaper);
             log(APP_NAME, "config + css reloaded");
             glib::ControlFlow::Continue
         }
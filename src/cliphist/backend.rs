@@ -0,0 +1,380 @@
+use crate::config::APP_NAME;
+use crate::entries::LINE_BREAK;
+use common::json::escape;
+use common::logging::log;
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// A clipboard history store. Entries travel as `id<TAB>preview` lines,
+/// cliphist's `list` format, so the rest of the app stays backend-agnostic.
+/// A preview may mark line breaks with `LINE_BREAK`.
+pub trait HistoryBackend: Sync {
+    /// Every entry, newest first; None if the store can't be read
+    fn list(&self) -> Option<String>;
+    /// Full contents of the entry on `raw_line`
+    fn decode(&self, raw_line: &str) -> Option<Vec<u8>>;
+    /// The first `max_bytes` of the entry on `raw_line` and its full size,
+    /// for looks that shouldn't hold a huge entry in memory
+    fn decode_prefix(&self, raw_line: &str, max_bytes: usize) -> Option<(Vec<u8>, usize)> {
+        let mut data = self.decode(raw_line)?;
+        let size = data.len();
+        data.truncate(max_bytes);
+        Some((data, size))
+    }
+    fn delete(&self, raw_line: &str);
+    /// Delete several entries in one go
+    fn delete_many(&self, raw_lines: &[String]) {
+        for line in raw_lines {
+            self.delete(line);
+        }
+    }
+    /// Remove the whole history
+    fn wipe(&self);
+    /// The file the history is stored in, for `live_refresh` to watch
+    fn store_path(&self) -> PathBuf;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackendKind {
+    Cliphist,
+    Clipman,
+}
+
+pub fn parse_backend(s: &str) -> Option<BackendKind> {
+    match s.to_lowercase().as_str() {
+        "cliphist" => Some(BackendKind::Cliphist),
+        "clipman" => Some(BackendKind::Clipman),
+        _ => None,
+    }
+}
+
+static KIND: AtomicU8 = AtomicU8::new(0);
+
+/// Pick the backend `backend()` returns (`[behavior] backend`)
+pub fn set_backend(kind: BackendKind) {
+    KIND.store(kind as u8, Ordering::Relaxed);
+}
+
+pub fn backend() -> &'static dyn HistoryBackend {
+    if KIND.load(Ordering::Relaxed) == BackendKind::Clipman as u8 {
+        &Clipman
+    } else {
+        &Cliphist
+    }
+}
+
+/// Run `program args`, feeding `input` on stdin; stdout if it succeeded
+fn pipe(program: &str, args: &[&str], input: &[u8]) -> Option<Vec<u8>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    if let Some(mut si) = child.stdin.take() {
+        let _ = si.write_all(input);
+    }
+    let out = child.wait_with_output().ok()?;
+    out.status.success().then_some(out.stdout)
+}
+
+/// `pipe`, keeping only the first `max_bytes` of stdout; the rest is read
+/// and counted so the size comes out right
+fn pipe_prefix(
+    program: &str,
+    args: &[&str],
+    input: &[u8],
+    max_bytes: usize,
+) -> Option<(Vec<u8>, usize)> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    if let Some(mut si) = child.stdin.take() {
+        let _ = si.write_all(input);
+    }
+    let mut stdout = child.stdout.take()?;
+    let mut data = Vec::new();
+    let mut size = (&mut stdout)
+        .take(max_bytes as u64)
+        .read_to_end(&mut data)
+        .ok()?;
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        match stdout.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => size += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(_) => return None,
+        }
+    }
+    child.wait().ok()?.success().then_some((data, size))
+}
+
+pub struct Cliphist;
+
+impl HistoryBackend for Cliphist {
+    fn list(&self) -> Option<String> {
+        let out = Command::new("cliphist")
+            .arg("list")
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        Some(String::from_utf8_lossy(&out.stdout).to_string())
+    }
+
+    fn decode(&self, raw_line: &str) -> Option<Vec<u8>> {
+        pipe("cliphist", &["decode"], raw_line.as_bytes())
+    }
+
+    fn decode_prefix(&self, raw_line: &str, max_bytes: usize) -> Option<(Vec<u8>, usize)> {
+        pipe_prefix("cliphist", &["decode"], raw_line.as_bytes(), max_bytes)
+    }
+
+    fn delete(&self, raw_line: &str) {
+        let _ = pipe("cliphist", &["delete"], raw_line.as_bytes());
+    }
+
+    /// `cliphist delete` takes one entry per stdin line
+    fn delete_many(&self, raw_lines: &[String]) {
+        let _ = pipe("cliphist", &["delete"], raw_lines.join("\n").as_bytes());
+    }
+
+    fn wipe(&self) {
+        let _ = Command::new("cliphist").arg("wipe").status();
+    }
+
+    /// cliphist's default database, or `CLIPHIST_DB_PATH`
+    fn store_path(&self) -> PathBuf {
+        if let Some(path) = std::env::var_os("CLIPHIST_DB_PATH").filter(|p| !p.is_empty()) {
+            return PathBuf::from(path);
+        }
+        std::env::var("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                PathBuf::from(std::env::var("HOME").unwrap_or("/tmp".into())).join(".cache")
+            })
+            .join("cliphist/db")
+    }
+}
+
+/// clipman keeps its history as a JSON array of strings, oldest first.
+/// Text only; ids are content hashes so they stay put as entries are added.
+pub struct Clipman;
+
+fn clipman_path() -> PathBuf {
+    std::env::var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            PathBuf::from(std::env::var("HOME").unwrap_or("/tmp".into())).join(".local/share")
+        })
+        .join("clipman.json")
+}
+
+/// Characters of text a clipman preview keeps
+const PREVIEW_CHARS: usize = 200;
+/// Line breaks a clipman preview keeps, so huge logs stay cheap to list
+const PREVIEW_LINES: usize = 999;
+
+/// A one-line preview of `text`: its first `PREVIEW_CHARS` characters with
+/// other whitespace as spaces, and its line breaks (even past that) as
+/// `LINE_BREAK`, so the list can show the text's shape
+fn clipman_preview(text: &str) -> String {
+    let mut out = String::new();
+    let (mut chars, mut breaks) = (0, 0);
+    for c in text.trim().chars() {
+        if c == '\n' {
+            if breaks == PREVIEW_LINES {
+                break;
+            }
+            breaks += 1;
+            out.push(LINE_BREAK);
+        } else if chars < PREVIEW_CHARS {
+            chars += 1;
+            out.push(if c.is_whitespace() { ' ' } else { c });
+        }
+    }
+    out
+}
+
+/// FNV-1a, as hex: stable across runs, unlike `DefaultHasher`
+fn content_id(text: &str) -> String {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in text.bytes() {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", h)
+}
+
+/// Parse a JSON array of strings; None on anything else
+fn parse_string_array(json: &str) -> Option<Vec<String>> {
+    let mut chars = json.trim().chars().peekable();
+    if chars.next()? != '[' {
+        return None;
+    }
+    let mut items = Vec::new();
+    loop {
+        match chars.find(|c| !c.is_whitespace() && *c != ',')? {
+            ']' => return Some(items),
+            '"' => {}
+            _ => return None,
+        }
+        let mut s = String::new();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => match chars.next()? {
+                    'n' => s.push('\n'),
+                    't' => s.push('\t'),
+                    'r' => s.push('\r'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'u' => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        let mut code = u32::from_str_radix(&hex, 16).ok()?;
+                        // Surrogate pair; a high half without its `\uDCxx`
+                        // partner becomes U+FFFD and leaves what follows alone
+                        if (0xd800..0xdc00).contains(&code) {
+                            let mut ahead = chars.clone();
+                            let low = match (ahead.next(), ahead.next()) {
+                                (Some('\\'), Some('u')) => {
+                                    let hex: String = ahead.by_ref().take(4).collect();
+                                    u32::from_str_radix(&hex, 16)
+                                        .ok()
+                                        .filter(|l| (0xdc00..0xe000).contains(l))
+                                }
+                                _ => None,
+                            };
+                            code = match low {
+                                Some(low) => {
+                                    chars = ahead;
+                                    0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+                                }
+                                None => 0xfffd,
+                            };
+                        }
+                        s.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    other => s.push(other),
+                },
+                c => s.push(c),
+            }
+        }
+        items.push(s);
+    }
+}
+
+fn write_string_array(items: &[String]) -> String {
+    let quoted: Vec<String> = items.iter().map(|s| format!("\"{}\"", escape(s))).collect();
+    format!("[{}]", quoted.join(","))
+}
+
+impl Clipman {
+    fn read(&self) -> Option<Vec<String>> {
+        parse_string_array(&std::fs::read_to_string(clipman_path()).ok()?)
+    }
+
+    /// Write next to the history and rename over it, so clipman (or a
+    /// crash) never sees a half-written file
+    fn write(&self, items: &[String]) {
+        let path = clipman_path();
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        let result = std::fs::write(&tmp, write_string_array(items))
+            .and_then(|_| std::fs::rename(&tmp, &path));
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&tmp);
+            log(APP_NAME, &format!("failed to write clipman history: {}", e));
+        }
+    }
+
+    fn find(&self, raw_line: &str) -> Option<String> {
+        let id = raw_line.split('\t').next()?;
+        self.read()?.into_iter().find(|t| content_id(t) == id)
+    }
+}
+
+impl HistoryBackend for Clipman {
+    fn list(&self) -> Option<String> {
+        let items = self.read()?;
+        let lines: Vec<String> = items
+            .iter()
+            .rev()
+            .map(|t| format!("{}\t{}", content_id(t), clipman_preview(t)))
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    fn decode(&self, raw_line: &str) -> Option<Vec<u8>> {
+        self.find(raw_line).map(String::into_bytes)
+    }
+
+    fn delete(&self, raw_line: &str) {
+        let Some(id) = raw_line.split('\t').next() else {
+            return;
+        };
+        if let Some(mut items) = self.read() {
+            items.retain(|t| content_id(t) != id);
+            self.write(&items);
+        }
+    }
+
+    fn delete_many(&self, raw_lines: &[String]) {
+        let ids: HashSet<&str> = raw_lines
+            .iter()
+            .filter_map(|l| l.split('\t').next())
+            .collect();
+        if let Some(mut items) = self.read() {
+            items.retain(|t| !ids.contains(content_id(t).as_str()));
+            self.write(&items);
+        }
+    }
+
+    fn wipe(&self) {
+        self.write(&[]);
+    }
+
+    fn store_path(&self) -> PathBuf {
+        clipman_path()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one(json: &str) -> String {
+        parse_string_array(json).unwrap().remove(0)
+    }
+
+    #[test]
+    fn surrogate_pair() {
+        assert_eq!(parse_one(r#"["\ud83d\ude00"]"#), "\u{1f600}");
+    }
+
+    #[test]
+    fn lone_high_surrogate_keeps_following_text() {
+        assert_eq!(parse_one(r#"["\ud83dab"]"#), "\u{fffd}ab");
+        assert_eq!(parse_one(r#"["\ud83d\n"]"#), "\u{fffd}\n");
+        assert_eq!(parse_one(r#"["\ud83dA"]"#), "\u{fffd}A");
+        assert_eq!(parse_one(r#"["\ud83d"]"#), "\u{fffd}");
+        assert_eq!(parse_one(r#"["\ud83d\u0041"]"#), "\u{fffd}A");
+    }
+
+    #[test]
+    fn round_trip() {
+        let items = vec![
+            "a \"quoted\"\nline".to_string(),
+            "\u{1f600}\t\u{1}".to_string(),
+        ];
+        assert_eq!(parse_string_array(&write_string_array(&items)), Some(items));
+    }
+}
@@ -1,12 +1,19 @@
+use crate::backend::{parse_backend, set_backend, BackendKind};
+use crate::entries::{parse_image_copy_format, set_image_copy_format, ImageCopyFormat};
 use common::{
-    config::{parse_bool, parse_config_file},
+    config::{parse_bool, parse_config_with_includes},
+    json::Json,
     logging::log,
     paths::config_dir,
     ConfigBase,
 };
+use std::path::Path;
 
 pub const APP_NAME: &str = "cliphist-gui";
 
+/// Shortest `auto_refresh_ms` that is honoured
+const MIN_AUTO_REFRESH_MS: u64 = 500;
+
 pub fn default_config() -> &'static str {
     include_str!("config.default")
 }
@@ -14,58 +21,442 @@ pub fn default_css() -> &'static str {
     include_str!("style.css")
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OpenAnimation {
+    #[default]
+    None,
+    Fade,
+    Slide,
+}
+
+pub fn parse_open_animation(s: &str) -> OpenAnimation {
+    match s.to_lowercase().as_str() {
+        "fade" => OpenAnimation::Fade,
+        "slide" => OpenAnimation::Slide,
+        _ => OpenAnimation::None,
+    }
+}
+
+/// How entries are laid out (`[style] layout`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Layout {
+    #[default]
+    List,
+    /// Wrapping cards, for image-heavy histories
+    Grid,
+}
+
+pub fn parse_layout(s: &str) -> Layout {
+    match s.to_lowercase().as_str() {
+        "grid" => Layout::Grid,
+        _ => Layout::List,
+    }
+}
+
+/// `\n` and `\t` in a config value as the characters they name, `\\` as a
+/// backslash
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Row and card badge wording (`[ui] badge_*`). An empty label leaves that
+/// badge out; `lines` fills `{}` with the line count.
+#[derive(Clone, Debug)]
+pub struct BadgeLabels {
+    pub text: String,
+    pub url: String,
+    pub image: String,
+    pub binary: String,
+    pub lines: String,
+}
+
+impl Default for BadgeLabels {
+    fn default() -> Self {
+        Self {
+            text: "TEXT".to_string(),
+            url: "URL".to_string(),
+            image: "IMAGE".to_string(),
+            binary: "BINARY".to_string(),
+            lines: "{} LINES".to_string(),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub base: ConfigBase,
     pub max_items: usize,
+    pub max_age_days: u64,
     pub close_on_select: bool,
     pub notify_on_copy: bool,
+    pub notify_timeout_ms: u32,
+    pub auto_paste: bool,
+    pub paste_command: String,
+    pub paste_delay_ms: u64,
+    pub confirm_delete: bool,
     pub vim_mode: bool,
+    pub tab_complete: bool,
+    pub join_separator: String,
+    pub auto_refresh_ms: u64,
+    pub live_refresh: bool,
+    pub show_preview: bool,
+    pub show_tooltips: bool,
+    pub highlight_code: bool,
+    pub image_popover: bool,
+    pub image_popover_size: u32,
+    pub mask_passwords: bool,
+    pub mask_regex: String,
+    pub binary_marker: String,
+    pub backend: BackendKind,
+    pub image_copy_format: ImageCopyFormat,
+    pub open_animation: OpenAnimation,
+    pub layout: Layout,
+    pub show_badges: bool,
+    pub collapsed: bool,
+    pub expand_on_open: bool,
+    pub search_height: i32,
+    pub search_placeholder: String,
+    pub section_label: String,
+    pub badges: BadgeLabels,
 }
 
+/// launch-gui's own keys, as `(section, key)`. The shared file may hold them,
+/// so they aren't reported as unknown here.
+const LAUNCHER_KEYS: &[(&str, &str)] = &[
+    ("window", "search_height"),
+    ("behavior", "calc_copy_format"),
+    ("behavior", "calc_format"),
+    ("behavior", "calc_scale"),
+    ("behavior", "calc_strip_zeros"),
+    ("behavior", "calculator"),
+    ("behavior", "file_search"),
+    ("behavior", "launch_via"),
+    ("behavior", "recent_count"),
+    ("behavior", "show_recent"),
+    ("behavior", "ssh_hosts"),
+    ("behavior", "terminal"),
+    ("behavior", "terminal_exec_arg"),
+    ("behavior", "terminal_template"),
+    ("behavior", "vim_mode"),
+    ("search", "exact"),
+    ("search", "prefix"),
+    ("search", "contains"),
+    ("search", "consecutive"),
+    ("search", "word_start"),
+    ("search", "frequency"),
+    ("ui", "search_placeholder"),
+    ("ui", "section_label"),
+    ("ui", "recent_label"),
+    ("ui", "all_apps_label"),
+];
+
 impl Config {
     pub fn default() -> Self {
         Self {
             base: ConfigBase::new(APP_NAME, 580, 520),
             max_items: 0,
+            max_age_days: 0,
             close_on_select: true,
             notify_on_copy: false,
+            notify_timeout_ms: 2000,
+            auto_paste: false,
+            paste_command: "wtype -M ctrl v -m ctrl".to_string(),
+            paste_delay_ms: 150,
+            confirm_delete: false,
             vim_mode: false,
+            tab_complete: false,
+            join_separator: "\n".to_string(),
+            auto_refresh_ms: 0,
+            live_refresh: false,
+            show_preview: false,
+            show_tooltips: true,
+            highlight_code: false,
+            image_popover: false,
+            image_popover_size: 360,
+            mask_passwords: false,
+            mask_regex: String::new(),
+            binary_marker: String::new(),
+            backend: BackendKind::Cliphist,
+            image_copy_format: ImageCopyFormat::Png,
+            open_animation: OpenAnimation::None,
+            layout: Layout::List,
+            show_badges: true,
+            collapsed: false,
+            expand_on_open: false,
+            search_height: 70,
+            search_placeholder: "Search clipboard history...".to_string(),
+            section_label: "Recent".to_string(),
+            badges: BadgeLabels::default(),
         }
     }
 
     pub fn load() -> Self {
         let path = config_dir(APP_NAME).join("config");
-        if !path.exists() {
-            return Self::default();
-        }
-
-        match std::fs::read_to_string(&path) {
-            Ok(c) => {
-                log(APP_NAME, &format!("loaded config from {}", path.display()));
-                Self::parse(&c)
-            }
+        // A missing file is just an empty one: defaults plus the shared config
+        let content = match std::fs::read_to_string(&path) {
+            Ok(c) => Some(c),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
             Err(e) => {
                 log(APP_NAME, &format!("config read error: {}", e));
-                Self::default()
+                None
             }
+        };
+        let cfg = Self::parse(content.as_deref().unwrap_or(""), &path);
+        cfg.base.apply_logging();
+        common::css::set_notify_errors(cfg.base.notify_css_errors);
+        common::css::set_density(cfg.base.density);
+        common::anim::set_animations(cfg.base.animations);
+        set_backend(cfg.backend);
+        set_image_copy_format(cfg.image_copy_format);
+        crate::ui::set_tooltips(cfg.show_tooltips);
+        crate::ui::set_badges(cfg.show_badges.then(|| cfg.badges.clone()));
+        if content.is_some() {
+            log(APP_NAME, &format!("loaded config from {}", path.display()));
         }
+        cfg
     }
 
-    pub fn parse(content: &str) -> Self {
+    pub fn parse(content: &str, path: &Path) -> Self {
         let mut cfg = Self::default();
-        for (section, key, val) in parse_config_file(content) {
-            cfg.base.parse_section(APP_NAME, &section, &key, &val);
-            if section == "behavior" {
-                match key.as_str() {
-                    "max_items" => cfg.max_items = val.parse().unwrap_or(0),
-                    "close_on_select" => cfg.close_on_select = parse_bool(&val, true),
-                    "notify_on_copy" => cfg.notify_on_copy = parse_bool(&val, false),
-                    "vim_mode" => cfg.vim_mode = parse_bool(&val, false),
-                    _ => {}
-                }
+        for (section, key, val) in cfg.base.apply_shared() {
+            let other_app = LAUNCHER_KEYS.contains(&(section.as_str(), key.as_str()));
+            if !cfg.parse_key(&section, &key, val) && !other_app {
+                cfg.base.unknown_shared_key(&section, &key);
+            }
+        }
+        let (entries, include_errors) = parse_config_with_includes(content, path);
+        cfg.base.include_errors.extend(include_errors);
+        for (section, key, val) in entries {
+            if !cfg.base.parse_section(&section, &key, &val) && !cfg.parse_key(&section, &key, val)
+            {
+                cfg.base.unknown_key(&section, &key);
             }
         }
         cfg
     }
+
+    /// Every effective setting, defaults included, for `--dump-config`
+    pub fn to_json(&self) -> Json {
+        let mut json = self.base.to_json();
+        for (section, key, value) in [
+            (
+                "window",
+                "open_animation",
+                Json::variant(&self.open_animation),
+            ),
+            ("window", "collapsed", Json::Bool(self.collapsed)),
+            ("window", "expand_on_open", Json::Bool(self.expand_on_open)),
+            ("window", "search_height", Json::num(self.search_height)),
+            ("style", "layout", Json::variant(&self.layout)),
+            ("style", "show_badges", Json::Bool(self.show_badges)),
+            ("behavior", "max_items", Json::num(self.max_items)),
+            ("behavior", "max_age_days", Json::num(self.max_age_days)),
+            (
+                "behavior",
+                "close_on_select",
+                Json::Bool(self.close_on_select),
+            ),
+            (
+                "behavior",
+                "notify_on_copy",
+                Json::Bool(self.notify_on_copy),
+            ),
+            (
+                "behavior",
+                "notify_timeout_ms",
+                Json::num(self.notify_timeout_ms),
+            ),
+            ("behavior", "auto_paste", Json::Bool(self.auto_paste)),
+            ("behavior", "paste_command", Json::str(&self.paste_command)),
+            ("behavior", "paste_delay_ms", Json::num(self.paste_delay_ms)),
+            (
+                "behavior",
+                "confirm_delete",
+                Json::Bool(self.confirm_delete),
+            ),
+            ("behavior", "vim_mode", Json::Bool(self.vim_mode)),
+            ("behavior", "tab_complete", Json::Bool(self.tab_complete)),
+            (
+                "behavior",
+                "join_separator",
+                Json::str(&self.join_separator),
+            ),
+            (
+                "behavior",
+                "auto_refresh_ms",
+                Json::num(self.auto_refresh_ms),
+            ),
+            ("behavior", "live_refresh", Json::Bool(self.live_refresh)),
+            ("behavior", "show_preview", Json::Bool(self.show_preview)),
+            ("behavior", "show_tooltips", Json::Bool(self.show_tooltips)),
+            (
+                "behavior",
+                "highlight_code",
+                Json::Bool(self.highlight_code),
+            ),
+            ("behavior", "image_popover", Json::Bool(self.image_popover)),
+            (
+                "behavior",
+                "image_popover_size",
+                Json::num(self.image_popover_size),
+            ),
+            (
+                "behavior",
+                "mask_passwords",
+                Json::Bool(self.mask_passwords),
+            ),
+            ("behavior", "mask_regex", Json::str(&self.mask_regex)),
+            ("behavior", "binary_marker", Json::str(&self.binary_marker)),
+            ("behavior", "backend", Json::variant(&self.backend)),
+            (
+                "behavior",
+                "image_copy_format",
+                Json::variant(&self.image_copy_format),
+            ),
+            (
+                "ui",
+                "search_placeholder",
+                Json::str(&self.search_placeholder),
+            ),
+            ("ui", "section_label", Json::str(&self.section_label)),
+            ("ui", "badge_text", Json::str(&self.badges.text)),
+            ("ui", "badge_url", Json::str(&self.badges.url)),
+            ("ui", "badge_image", Json::str(&self.badges.image)),
+            ("ui", "badge_binary", Json::str(&self.badges.binary)),
+            ("ui", "badge_lines", Json::str(&self.badges.lines)),
+        ] {
+            json.set(section, key, value);
+        }
+        json
+    }
+
+    /// Record a `mask_regex`/`binary_marker` the matcher can't use, so
+    /// `--check-config` and the startup notice report it
+    fn check_pattern(&mut self, section: &str, key: &str, val: &str) {
+        if let Err(e) = crate::mask::Pattern::parse(val) {
+            self.base
+                .bad_values
+                .push(format!("[{}] {} = {}: {}", section, key, val, e));
+        }
+    }
+
+    /// cliphist-only keys; false if `key` isn't one
+    fn parse_key(&mut self, section: &str, key: &str, val: String) -> bool {
+        match (section, key) {
+            ("window", "open_animation") => self.open_animation = parse_open_animation(&val),
+            ("window", "collapsed") => self.collapsed = parse_bool(&val, false),
+            ("window", "expand_on_open") => self.expand_on_open = parse_bool(&val, false),
+            ("style", "layout") => self.layout = parse_layout(&val),
+            ("style", "show_badges") => self.show_badges = parse_bool(&val, true),
+            ("window", "search_height") => self.search_height = val.parse().unwrap_or(70),
+            ("behavior", "max_items") => self.max_items = val.parse().unwrap_or(0),
+            ("behavior", "max_age_days") => self.max_age_days = val.parse().unwrap_or(0),
+            ("behavior", "close_on_select") => self.close_on_select = parse_bool(&val, true),
+            ("behavior", "notify_on_copy") => self.notify_on_copy = parse_bool(&val, false),
+            ("behavior", "notify_timeout_ms") => {
+                self.notify_timeout_ms = val.parse().unwrap_or(2000)
+            }
+            ("behavior", "auto_paste") => self.auto_paste = parse_bool(&val, false),
+            ("behavior", "paste_command") => self.paste_command = self.base.expand(&val),
+            ("behavior", "paste_delay_ms") => self.paste_delay_ms = val.parse().unwrap_or(150),
+            ("behavior", "confirm_delete") => self.confirm_delete = parse_bool(&val, false),
+            ("behavior", "vim_mode") => self.vim_mode = parse_bool(&val, false),
+            ("behavior", "tab_complete") => self.tab_complete = parse_bool(&val, false),
+            ("behavior", "join_separator") => self.join_separator = unescape(&val),
+            ("behavior", "live_refresh") => self.live_refresh = parse_bool(&val, false),
+            ("behavior", "auto_refresh_ms") => {
+                // Each refresh runs the backend, so keep it from spinning
+                self.auto_refresh_ms = match val.parse().unwrap_or(0) {
+                    0 => 0,
+                    ms => ms.max(MIN_AUTO_REFRESH_MS),
+                }
+            }
+            ("behavior", "show_preview") => self.show_preview = parse_bool(&val, false),
+            ("behavior", "show_tooltips") => self.show_tooltips = parse_bool(&val, true),
+            ("behavior", "highlight_code") => self.highlight_code = parse_bool(&val, false),
+            ("behavior", "image_popover") => self.image_popover = parse_bool(&val, false),
+            ("behavior", "image_popover_size") => {
+                self.image_popover_size = val.parse::<u32>().unwrap_or(360).clamp(64, 2048)
+            }
+            ("behavior", "mask_passwords") => self.mask_passwords = parse_bool(&val, false),
+            ("behavior", "mask_regex") => {
+                self.check_pattern(section, key, &val);
+                self.mask_regex = val
+            }
+            ("behavior", "binary_marker") => {
+                self.check_pattern(section, key, &val);
+                self.binary_marker = val
+            }
+            ("ui", "search_placeholder") => self.search_placeholder = val,
+            ("ui", "section_label") => self.section_label = val,
+            ("ui", "badge_text") => self.badges.text = val,
+            ("ui", "badge_url") => self.badges.url = val,
+            ("ui", "badge_image") => self.badges.image = val,
+            ("ui", "badge_binary") => self.badges.binary = val,
+            ("ui", "badge_lines") => self.badges.lines = val,
+            ("behavior", "backend") => match parse_backend(&val) {
+                Some(b) => self.backend = b,
+                None => log(
+                    APP_NAME,
+                    &format!("unknown backend '{}', using cliphist", val),
+                ),
+            },
+            ("behavior", "image_copy_format") => match parse_image_copy_format(&val) {
+                Some(f) => self.image_copy_format = f,
+                None => log(
+                    APP_NAME,
+                    &format!("unknown image_copy_format '{}', using png", val),
+                ),
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::config::parse_config_file;
+
+    #[test]
+    fn launcher_keys_in_the_shared_file_are_not_reported() {
+        let mut cfg = Config::default();
+        let default = include_str!("../launcher/config.default");
+        for (section, key, val) in parse_config_file(default) {
+            if cfg.base.parse_section(&section, &key, &val) || cfg.parse_key(&section, &key, val) {
+                continue;
+            }
+            assert!(
+                LAUNCHER_KEYS.contains(&(section.as_str(), key.as_str())),
+                "[{}] {} missing from LAUNCHER_KEYS",
+                section,
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn unusable_patterns_are_reported() {
+        let mut cfg = Config::default();
+        assert!(cfg.parse_key("behavior", "mask_regex", "^sk-\\w+$".to_string()));
+        assert!(cfg.base.bad_values.is_empty());
+        assert!(cfg.parse_key("behavior", "binary_marker", "png|jpe?g".to_string()));
+        assert_eq!(cfg.base.bad_values.len(), 1);
+        assert!(cfg.base.bad_values[0].starts_with("[behavior] binary_marker = png|jpe?g:"));
+    }
 }
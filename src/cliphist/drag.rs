@@ -0,0 +1,104 @@
+use crate::entries::{decode_entry, image_extension, image_subtype, ClipEntry, ContentKind};
+use gdk4::subclass::prelude::*;
+use gtk4::prelude::*;
+use gtk4::{DragSource, WidgetPaintable};
+
+/// A content provider that decodes its entry only when a drop target
+/// actually asks for the data, so large entries cost nothing to drag over
+/// places that won't take them
+mod imp {
+    use super::*;
+    use std::cell::RefCell;
+    use std::future::Future;
+    use std::pin::Pin;
+
+    #[derive(Default)]
+    pub struct EntryContent {
+        pub raw_line: RefCell<String>,
+        pub mime_types: RefCell<Vec<String>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for EntryContent {
+        const NAME: &'static str = "CliphistEntryContent";
+        type Type = super::EntryContent;
+        type ParentType = gdk4::ContentProvider;
+    }
+
+    impl ObjectImpl for EntryContent {}
+
+    impl ContentProviderImpl for EntryContent {
+        fn formats(&self) -> gdk4::ContentFormats {
+            let mime_types = self.mime_types.borrow();
+            let mime_types: Vec<&str> = mime_types.iter().map(String::as_str).collect();
+            gdk4::ContentFormats::new(&mime_types)
+        }
+
+        fn write_mime_type_future(
+            &self,
+            _mime_type: &str,
+            stream: &gio::OutputStream,
+            io_priority: glib::Priority,
+        ) -> Pin<Box<dyn Future<Output = Result<(), glib::Error>> + 'static>> {
+            let raw_line = self.raw_line.borrow().clone();
+            let stream = stream.clone();
+            Box::pin(async move {
+                let data = gio::spawn_blocking(move || decode_entry(&raw_line))
+                    .await
+                    .ok()
+                    .flatten()
+                    .ok_or_else(|| {
+                        glib::Error::new(gio::IOErrorEnum::Failed, "could not decode entry")
+                    })?;
+                stream
+                    .write_all_future(data, io_priority)
+                    .await
+                    .map_err(|(_, e)| e)?;
+                Ok(())
+            })
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct EntryContent(ObjectSubclass<imp::EntryContent>)
+        @extends gdk4::ContentProvider;
+}
+
+impl EntryContent {
+    fn new(entry: &ClipEntry) -> Self {
+        let content: Self = glib::Object::new();
+        content.imp().raw_line.replace(entry.raw_line.clone());
+        content.imp().mime_types.replace(mime_types(entry));
+        content
+    }
+}
+
+/// What a dragged entry is offered as
+fn mime_types(entry: &ClipEntry) -> Vec<String> {
+    match entry.kind {
+        ContentKind::Image => {
+            let ext = image_extension(&entry.preview);
+            vec![format!("image/{}", image_subtype(&ext))]
+        }
+        ContentKind::OtherBinary => vec!["application/octet-stream".to_string()],
+        ContentKind::Text | ContentKind::Url => vec![
+            "text/plain;charset=utf-8".to_string(),
+            "text/plain".to_string(),
+        ],
+    }
+}
+
+/// Let `widget` (a row or card) be dragged out of the window as `entry`'s content
+pub fn attach_drag_source(widget: &impl IsA<gtk4::Widget>, entry: &ClipEntry) {
+    let source = DragSource::new();
+    source.set_actions(gdk4::DragAction::COPY);
+    let entry = entry.clone();
+    source.connect_prepare(move |_, _, _| Some(EntryContent::new(&entry).upcast()));
+    // Drag a snapshot of the item itself rather than GTK's generic icon
+    source.connect_drag_begin(|source, _| {
+        let paintable = WidgetPaintable::new(source.widget().as_ref());
+        source.set_icon(Some(&paintable), 0, 0);
+    });
+    widget.add_controller(source);
+}
@@ -1,23 +1,238 @@
+use crate::backend::backend;
 use crate::config::APP_NAME;
 use common::css::char_truncate;
+use common::i18n::{tr, trf};
 use common::logging::log;
-use std::io::{BufRead, BufReader, Write};
+use common::paths::in_path;
+use common::search::ignore_case;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 
 const THUMB_SIZE: u32 = 64;
 
+/// Stands in for a line break in a one-line preview. cliphist flattens its
+/// previews, so only backends that write this get multi-line rows.
+pub const LINE_BREAK: char = '\u{2028}';
+/// Non-empty lines of a multi-line preview kept for display
+const MAX_PREVIEW_LINES: usize = 5;
+/// Bytes of an entry the preview pane decodes; copying still takes it all
+pub const PREVIEW_BYTES: usize = 64 * 1024;
+/// Entries this big get their size in the subtitle
+const LARGE_ENTRY_BYTES: usize = 1024 * 1024;
+
+static MAGICK: OnceLock<Option<&'static str>> = OnceLock::new();
+
 #[derive(Clone, Debug)]
 #[allow(dead_code)]
 pub struct ClipEntry {
     pub raw_line: String,
     pub id: String,
     pub preview: String,
-    pub is_image: bool,
+    pub kind: ContentKind,
     pub thumb_path: Option<PathBuf>,
+    pub first_seen: u64,
+    pub masked: bool,
+    /// Lines in the text; 1 when the preview doesn't say
+    pub line_count: usize,
+    /// The first non-empty lines of a multi-line text, for display
+    pub lines: Vec<String>,
+    /// Full size in bytes of a large entry, once a preview has measured it
+    pub large_size: Option<usize>,
+}
+
+impl ClipEntry {
+    pub fn is_image(&self) -> bool {
+        self.kind == ContentKind::Image
+    }
+}
+
+/// What an entry holds, judged from its cliphist preview
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentKind {
+    Text,
+    Url,
+    Image,
+    /// Binary data that isn't a decodable image (PDFs, archives, ...)
+    OtherBinary,
+}
+
+impl ContentKind {
+    /// Badge text for the row
+    pub fn label(self) -> &'static str {
+        match self {
+            ContentKind::Text => "TEXT",
+            ContentKind::Url => "URL",
+            ContentKind::Image => "IMAGE",
+            ContentKind::OtherBinary => "BINARY",
+        }
+    }
+
+    pub fn is_binary(self) -> bool {
+        matches!(self, ContentKind::Image | ContentKind::OtherBinary)
+    }
+}
+
+const IMAGE_FORMATS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "webp"];
+
+/// What an image entry is put on the clipboard as (`[behavior] image_copy_format`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageCopyFormat {
+    /// Png, converting other formats
+    Png,
+    /// As stored, under its own type
+    Original,
+    Jpeg,
+}
+
+pub fn parse_image_copy_format(s: &str) -> Option<ImageCopyFormat> {
+    match s.to_lowercase().as_str() {
+        "png" => Some(ImageCopyFormat::Png),
+        "original" => Some(ImageCopyFormat::Original),
+        "jpeg" | "jpg" => Some(ImageCopyFormat::Jpeg),
+        _ => None,
+    }
+}
+
+static IMAGE_COPY_FORMAT: AtomicU8 = AtomicU8::new(ImageCopyFormat::Png as u8);
+
+pub fn set_image_copy_format(format: ImageCopyFormat) {
+    IMAGE_COPY_FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+fn image_copy_format() -> ImageCopyFormat {
+    match IMAGE_COPY_FORMAT.load(Ordering::Relaxed) {
+        f if f == ImageCopyFormat::Original as u8 => ImageCopyFormat::Original,
+        f if f == ImageCopyFormat::Jpeg as u8 => ImageCopyFormat::Jpeg,
+        _ => ImageCopyFormat::Png,
+    }
+}
+
+/// Mime subtype for an image extension as `image_extension` gives it
+pub fn image_subtype(ext: &str) -> &str {
+    if ext == "jpg" {
+        "jpeg"
+    } else {
+        ext
+    }
+}
+
+/// `data` run through ImageMagick into `subtype` (`png`, `jpeg`); None
+/// without ImageMagick or when it fails
+fn convert_image(data: &[u8], subtype: &str) -> Option<Vec<u8>> {
+    let magick = magick_command()?;
+    let out = Command::new(magick)
+        .args(["-", &format!("{}:-", subtype)])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .and_then(|mut child| {
+            if let Some(mut si) = child.stdin.take() {
+                let _ = si.write_all(data);
+            }
+            child.wait_with_output()
+        })
+        .ok()?;
+    (out.status.success() && !out.stdout.is_empty()).then_some(out.stdout)
+}
+
+/// An image entry's bytes and mime type as `image_copy_format` asks.
+/// Falls back to the stored bytes under their own type if converting fails.
+fn image_for_copy(entry: &ClipEntry, data: Vec<u8>) -> (Vec<u8>, String) {
+    let original = image_subtype(&image_extension(&entry.preview)).to_string();
+    let wanted = match image_copy_format() {
+        ImageCopyFormat::Original => return (data, format!("image/{}", original)),
+        ImageCopyFormat::Png => "png",
+        ImageCopyFormat::Jpeg => "jpeg",
+    };
+    if wanted == original {
+        return (data, format!("image/{}", original));
+    }
+    match convert_image(&data, wanted) {
+        Some(converted) => (converted, format!("image/{}", wanted)),
+        None => {
+            log(
+                APP_NAME,
+                &format!(
+                    "could not convert {} to {}, copying it as {}",
+                    entry.id, wanted, original
+                ),
+            );
+            (data, format!("image/{}", original))
+        }
+    }
+}
+
+/// Whether a binary preview names an image format or `WxH` size
+fn names_image(preview: &str) -> bool {
+    preview.split_whitespace().any(|p| {
+        IMAGE_FORMATS.contains(&p.to_lowercase().as_str())
+            || (p.contains('x') && p.chars().all(|c| c.is_ascii_digit() || c == 'x'))
+    })
+}
+
+/// cliphist previews binary data as `[[ binary data 12 KiB png 64x64 ]]`,
+/// naming the format and size only for images it could decode. A preview
+/// with NULs or replacement characters is binary that slipped through as text.
+pub fn classify(preview: &str) -> ContentKind {
+    let t = preview.trim();
+    if t.starts_with("[[ binary data") {
+        return if names_image(t) {
+            ContentKind::Image
+        } else {
+            ContentKind::OtherBinary
+        };
+    }
+    if t.contains(['\0', '\u{fffd}']) {
+        ContentKind::OtherBinary
+    } else if t.starts_with("http://") || t.starts_with("https://") {
+        ContentKind::Url
+    } else {
+        ContentKind::Text
+    }
+}
+
+/// `preview` with replacement characters and control codes (left by lossy
+/// decoding of non-UTF-8 data) shown as `.`, so rows never look garbled
+pub fn printable(preview: &str) -> String {
+    preview
+        .chars()
+        .map(|c| {
+            if c == '\u{fffd}' || (c.is_control() && c != '\n' && c != '\t') {
+                '.'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Treat text entries whose preview matches `binary_marker` as binary, for
+/// backends or builds whose previews don't look like cliphist's
+pub fn apply_binary_marker(entries: &mut [ClipEntry], binary_marker: &str) {
+    let Some(pattern) = crate::mask::Pattern::parse(binary_marker)
+        .ok()
+        .filter(|_| !binary_marker.is_empty())
+    else {
+        return;
+    };
+    for e in entries
+        .iter_mut()
+        .filter(|e| !e.kind.is_binary() && pattern.is_match(&e.preview))
+    {
+        e.kind = if names_image(&e.preview) {
+            ContentKind::Image
+        } else {
+            ContentKind::OtherBinary
+        };
+    }
 }
 
 /// Thumbnail generation result
@@ -33,94 +248,281 @@ pub fn thumb_cache() -> PathBuf {
     d
 }
 
-/// Fast synchronous fetch - NO thumbnail generation, just parse cliphist output
-/// Returns entries immediately with thumb_path set only if already cached
-pub fn fetch_entries_fast(max_items: usize) -> Vec<ClipEntry> {
-    let output = match Command::new("cliphist")
-        .arg("list")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .output()
-    {
-        Ok(o) => o,
-        Err(_) => return Vec::new(),
-    };
+fn seen_path() -> PathBuf {
+    common::paths::cache_dir(APP_NAME).join("seen")
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let cache = thumb_cache();
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Load the first-seen sidecar: one `id<TAB>unix_secs` per line
+fn load_seen() -> HashMap<String, u64> {
+    std::fs::read_to_string(seen_path())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| {
+            let (id, ts) = l.split_once('\t')?;
+            Some((id.to_string(), ts.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn save_seen(seen: &HashMap<String, u64>) {
+    let content: String = seen
+        .iter()
+        .map(|(id, ts)| format!("{}\t{}\n", id, ts))
+        .collect();
+    let _ = std::fs::write(seen_path(), content);
+}
+
+/// Record first-seen times for new ids and drop ids cliphist no longer has
+fn update_seen(ids: &HashSet<&str>) -> HashMap<String, u64> {
+    let mut seen = load_seen();
+    let before = seen.len();
+    seen.retain(|id, _| ids.contains(id.as_str()));
+    let mut changed = seen.len() != before;
+
+    let now = now_secs();
+    for id in ids {
+        if !seen.contains_key(*id) {
+            seen.insert(id.to_string(), now);
+            changed = true;
+        }
+    }
 
-    let iter = stdout.lines().filter(|l| !l.is_empty());
-    let iter: Box<dyn Iterator<Item = &str>> = if max_items > 0 {
-        Box::new(iter.take(max_items))
+    if changed {
+        save_seen(&seen);
+    }
+    seen
+}
+
+fn sizes_path() -> PathBuf {
+    common::paths::cache_dir(APP_NAME).join("sizes")
+}
+
+/// Load the large-entry sidecar: one `id<TAB>bytes` per line
+fn load_sizes() -> HashMap<String, usize> {
+    std::fs::read_to_string(sizes_path())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| {
+            let (id, size) = l.split_once('\t')?;
+            Some((id.to_string(), size.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn save_sizes(sizes: &HashMap<String, usize>) {
+    let content: String = sizes
+        .iter()
+        .map(|(id, size)| format!("{}\t{}\n", id, size))
+        .collect();
+    let _ = std::fs::write(sizes_path(), content);
+}
+
+/// Known large-entry sizes, without ids the history no longer has
+fn update_sizes(ids: &HashSet<&str>) -> HashMap<String, usize> {
+    let mut sizes = load_sizes();
+    let before = sizes.len();
+    sizes.retain(|id, _| ids.contains(id.as_str()));
+    if sizes.len() != before {
+        save_sizes(&sizes);
+    }
+    sizes
+}
+
+/// Short relative age like "5m ago"; anything under a minute is "new"
+pub fn relative_age(first_seen: u64) -> String {
+    let age = now_secs().saturating_sub(first_seen);
+    match age {
+        0..=59 => "new".to_string(),
+        60..=3599 => format!("{}m ago", age / 60),
+        3600..=86399 => format!("{}h ago", age / 3600),
+        _ => format!("{}d ago", age / 86400),
+    }
+}
+
+fn parse_line(
+    line: &str,
+    cache: &Path,
+    seen: &HashMap<String, u64>,
+    sizes: &HashMap<String, usize>,
+) -> ClipEntry {
+    let raw_line = line.to_string();
+    let (id, preview) = match line.split_once('\t') {
+        Some((i, p)) => (i.trim().to_string(), p.to_string()),
+        None => (line.to_string(), line.to_string()),
+    };
+    // Search and masking see the flat preview; rows get the lines
+    let line_count = preview.split(LINE_BREAK).count();
+    let (preview, lines) = if line_count > 1 {
+        let all: Vec<&str> = preview
+            .split(LINE_BREAK)
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+        let lines = all
+            .iter()
+            .take(MAX_PREVIEW_LINES)
+            .map(|l| l.to_string())
+            .collect();
+        (all.join(" "), lines)
     } else {
-        Box::new(iter)
+        (preview, Vec::new())
     };
+    let kind = classify(&preview);
 
-    iter.map(|line| {
-        let raw_line = line.to_string();
-        let (id, preview) = match line.split_once('\t') {
-            Some((i, p)) => (i.trim().to_string(), p.to_string()),
-            None => (line.to_string(), line.to_string()),
-        };
-        let is_image = preview.contains("[[ binary data");
-
-        // Only check if thumbnail exists - don't generate
-        let thumb_path = if is_image {
-            let path = cache.join(format!("{}.png", id));
-            if path.exists() {
-                Some(path)
-            } else {
-                None
-            }
+    // Only check if thumbnail exists - don't generate
+    let thumb_path = if kind == ContentKind::Image {
+        let path = cache.join(format!("{}.png", id));
+        if path.exists() {
+            Some(path)
         } else {
             None
-        };
+        }
+    } else {
+        None
+    };
+    let first_seen = seen.get(&id).copied().unwrap_or_else(now_secs);
+    let large_size = sizes.get(&id).copied();
+
+    ClipEntry {
+        raw_line,
+        id,
+        preview,
+        kind,
+        thumb_path,
+        first_seen,
+        masked: false,
+        line_count,
+        lines,
+        large_size,
+    }
+}
 
-        ClipEntry {
-            raw_line,
-            id,
-            preview,
-            is_image,
-            thumb_path,
+/// Drop entries first seen more than `max_age_days` ago and delete them
+/// from the history on a background thread, in one batch.
+/// There is no pinning yet, so nothing is exempt.
+fn prune_expired(entries: &mut Vec<ClipEntry>, max_age_days: u64) {
+    let cutoff = now_secs().saturating_sub(max_age_days * 86400);
+    let (expired, kept): (Vec<ClipEntry>, Vec<ClipEntry>) = std::mem::take(entries)
+        .into_iter()
+        .partition(|e| e.first_seen < cutoff);
+    *entries = kept;
+    if expired.is_empty() {
+        return;
+    }
+    log(
+        APP_NAME,
+        &format!(
+            "pruning {} entries older than {} days",
+            expired.len(),
+            max_age_days
+        ),
+    );
+    thread::spawn(move || {
+        let lines: Vec<String> = expired.iter().map(|e| e.raw_line.clone()).collect();
+        backend().delete_many(&lines);
+        for p in expired.iter().filter_map(|e| e.thumb_path.as_ref()) {
+            let _ = std::fs::remove_file(p);
         }
-    })
-    .collect()
+    });
 }
 
-/// Synchronous thumbnail generation - returns true on success
-fn generate_thumbnail_sync(raw_line: &str, out_path: &Path) -> bool {
-    // Decode from cliphist
-    let mut child = match Command::new("cliphist")
-        .arg("decode")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-    {
-        Ok(c) => c,
-        Err(_) => return false,
+/// Fast synchronous fetch - NO thumbnail generation, just parse the backend's list
+/// Returns entries immediately with thumb_path set only if already cached.
+/// `max_age_days > 0` deletes expired entries from the history along the way.
+pub fn fetch_entries_fast(max_items: usize, max_age_days: u64) -> Vec<ClipEntry> {
+    let Some(stdout) = backend().list() else {
+        return Vec::new();
     };
+    let cache = thumb_cache();
 
-    if let Some(mut si) = child.stdin.take() {
-        let _ = si.write_all(raw_line.as_bytes());
-        drop(si);
+    // Track ages against the full history so max_items doesn't prune the sidecar
+    let ids: HashSet<&str> = stdout
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.split_once('\t').map(|(i, _)| i.trim()).unwrap_or(l))
+        .collect();
+    let seen = update_seen(&ids);
+    let sizes = update_sizes(&ids);
+
+    let mut entries: Vec<ClipEntry> = stdout
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|line| parse_line(line, &cache, &seen, &sizes))
+        .collect();
+
+    if max_age_days > 0 {
+        prune_expired(&mut entries, max_age_days);
+    }
+    if max_items > 0 {
+        entries.truncate(max_items);
     }
+    entries
+}
 
-    let out = match child.wait_with_output() {
-        Ok(o) => o,
-        Err(_) => return false,
-    };
+/// ImageMagick command to use for thumbnails, probed once per process.
+/// Falls back to the IM6 `convert` name; None if neither is installed.
+pub fn magick_command() -> Option<&'static str> {
+    *MAGICK.get_or_init(|| {
+        for cmd in ["magick", "convert"] {
+            let found = Command::new(cmd)
+                .arg("-version")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok();
+            if found {
+                return Some(cmd);
+            }
+        }
+        log(
+            APP_NAME,
+            "ImageMagick not found (tried magick, convert) - image thumbnails disabled",
+        );
+        None
+    })
+}
 
-    if !out.status.success() || out.stdout.is_empty() {
-        return false;
+/// Decode an entry's full contents through the history backend
+pub fn decode_entry(raw_line: &str) -> Option<Vec<u8>> {
+    backend().decode(raw_line)
+}
+
+/// The first `PREVIEW_BYTES` of an entry and its full size. Large sizes
+/// are remembered so the entry's row can show them from the next listing.
+pub fn decode_preview(entry: &ClipEntry) -> Option<(Vec<u8>, usize)> {
+    let (data, size) = backend().decode_prefix(&entry.raw_line, PREVIEW_BYTES)?;
+    if size >= LARGE_ENTRY_BYTES && entry.large_size != Some(size) {
+        let mut sizes = load_sizes();
+        sizes.insert(entry.id.clone(), size);
+        save_sizes(&sizes);
     }
+    Some((data, size))
+}
+
+/// Synchronous thumbnail generation - returns true on success
+/// `geometry` is an ImageMagick `-resize` argument
+fn generate_thumbnail_sync(raw_line: &str, out_path: &Path, geometry: &str) -> bool {
+    let Some(magick) = magick_command() else {
+        return false;
+    };
+    let data = match decode_entry(raw_line) {
+        Some(d) if !d.is_empty() => d,
+        _ => return false,
+    };
 
     // Resize with imagemagick
-    let mut m = match Command::new("magick")
+    let mut m = match Command::new(magick)
         .args([
             "png:-",
             "-resize",
-            &format!("{}x{}^", THUMB_SIZE * 2, THUMB_SIZE * 2),
+            geometry,
             &format!("png:{}", out_path.display()),
         ])
         .stdin(Stdio::piped())
@@ -133,7 +535,7 @@ fn generate_thumbnail_sync(raw_line: &str, out_path: &Path) -> bool {
     };
 
     if let Some(mut si) = m.stdin.take() {
-        let _ = si.write_all(&out.stdout);
+        let _ = si.write_all(&data);
         drop(si);
     }
 
@@ -152,7 +554,7 @@ pub fn generate_thumbnails_background(entries: Vec<ClipEntry>) -> Arc<Mutex<Vec<
         // Collect entries that need thumbnails
         let needs_thumb: Vec<_> = entries
             .iter()
-            .filter(|e| e.is_image && e.thumb_path.is_none())
+            .filter(|e| e.is_image() && e.thumb_path.is_none())
             .collect();
 
         if needs_thumb.is_empty() {
@@ -167,7 +569,8 @@ pub fn generate_thumbnails_background(entries: Vec<ClipEntry>) -> Arc<Mutex<Vec<
         for entry in needs_thumb {
             let path = cache.join(format!("{}.png", entry.id));
 
-            let result = if generate_thumbnail_sync(&entry.raw_line, &path) {
+            let geometry = format!("{}x{}^", THUMB_SIZE * 2, THUMB_SIZE * 2);
+            let result = if generate_thumbnail_sync(&entry.raw_line, &path, &geometry) {
                 ThumbnailResult {
                     id: entry.id.clone(),
                     path: Some(path),
@@ -188,6 +591,32 @@ pub fn generate_thumbnails_background(entries: Vec<ClipEntry>) -> Arc<Mutex<Vec<
     results
 }
 
+/// Slot filled by `large_thumbnail_background`: None while decoding,
+/// then Some(path) or Some(None) if the decode failed
+pub type LargeThumbSlot = Arc<Mutex<Option<Option<PathBuf>>>>;
+
+/// Decode an image entry at up to `size` px (aspect kept, never upscaled)
+/// in a background thread. Results are cached next to the small thumbs.
+pub fn large_thumbnail_background(entry: &ClipEntry, size: u32) -> LargeThumbSlot {
+    let slot: LargeThumbSlot = Arc::new(Mutex::new(None));
+    let path = thumb_cache().join(format!("{}-{}.png", entry.id, size));
+    if path.exists() {
+        *slot.lock().unwrap() = Some(Some(path));
+        return slot;
+    }
+
+    let slot_clone = slot.clone();
+    let raw_line = entry.raw_line.clone();
+    thread::spawn(move || {
+        let geometry = format!("{}x{}>", size, size);
+        let done = generate_thumbnail_sync(&raw_line, &path, &geometry).then_some(path);
+        if let Ok(mut s) = slot_clone.lock() {
+            *s = Some(done);
+        }
+    });
+    slot
+}
+
 /// Poll for completed thumbnails - returns new results since last poll
 pub fn poll_thumbnail_results(
     results: &Arc<Mutex<Vec<ThumbnailResult>>>,
@@ -201,78 +630,245 @@ pub fn poll_thumbnail_results(
     Vec::new()
 }
 
-pub fn select_entry(entry: &ClipEntry, notify: bool) {
-    let mut dec = Command::new("cliphist")
-        .arg("decode")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .expect("cliphist decode failed");
+/// Summary line for the copy notification, e.g. "Copied URL (42 bytes)"
+fn copy_summary(entry: &ClipEntry, len: usize) -> String {
+    let size = if len == 1 {
+        tr("1 byte").to_string()
+    } else {
+        trf("{} bytes", &[&len])
+    };
+    match parse_image_meta(&entry.preview).filter(|_| entry.is_image()) {
+        Some(meta) => trf("Copied IMAGE {} ({})", &[&meta, &size]),
+        None => trf("Copied {} ({})", &[&entry.kind.label(), &size]),
+    }
+}
 
-    if let Some(mut si) = dec.stdin.take() {
-        let _ = si.write_all(entry.raw_line.as_bytes());
+/// Put `data` on the clipboard, typed as `mime` if given
+fn wl_copy(data: &[u8], mime: Option<&str>) {
+    let mut cmd = Command::new("wl-copy");
+    if let Some(mime) = mime {
+        cmd.args(["--type", mime]);
+    }
+    let mut wl = cmd.stdin(Stdio::piped()).spawn().expect("wl-copy failed");
+    if let Some(mut si) = wl.stdin.take() {
+        let _ = si.write_all(data);
         drop(si);
     }
+    let _ = wl.wait();
+}
 
-    if let Ok(out) = dec.wait_with_output() {
-        if out.status.success() {
-            let mime = if entry.is_image {
-                "image/png"
-            } else {
-                "text/plain"
-            };
-            let mut wl = Command::new("wl-copy")
-                .args(["--type", mime])
-                .stdin(Stdio::piped())
-                .spawn()
-                .expect("wl-copy failed");
-            if let Some(mut si) = wl.stdin.take() {
-                let _ = si.write_all(&out.stdout);
-                drop(si);
+/// Copy the text entries among `entries` as one item, joined by
+/// `separator`. Images and other binary entries are skipped. Returns how
+/// many were copied and how many skipped.
+pub fn copy_joined(
+    entries: &[ClipEntry],
+    separator: &str,
+    notify: bool,
+    notify_timeout_ms: u32,
+) -> (usize, usize) {
+    let mut parts = Vec::new();
+    let mut skipped = 0;
+    for entry in entries {
+        match decode_entry(&entry.raw_line).filter(|_| !entry.kind.is_binary()) {
+            Some(data) => parts.push(data),
+            None => {
+                log(
+                    APP_NAME,
+                    &format!("not copying {} ({}) as text", entry.id, entry.kind.label()),
+                );
+                skipped += 1;
             }
-            let _ = wl.wait();
+        }
+    }
+    if parts.is_empty() {
+        return (0, skipped);
+    }
+    let joined = parts.join(separator.as_bytes());
+    wl_copy(&joined, Some("text/plain"));
+
+    if notify {
+        crate::notify::send(
+            &trf(
+                "Copied {} entries ({} bytes)",
+                &[&parts.len(), &joined.len()],
+            ),
+            notify_timeout_ms,
+        );
+    }
+    (parts.len(), skipped)
+}
 
-            if notify {
-                let msg = if entry.is_image {
-                    "Image copied".to_string()
-                } else {
-                    format!("Copied: {}", char_truncate(&entry.preview, 50))
-                };
-                let _ = Command::new("notify-send")
-                    .args(["-t", "2000", APP_NAME, &msg])
-                    .spawn();
-            }
+pub fn select_entry(entry: &ClipEntry, notify: bool, notify_timeout_ms: u32) {
+    let Some(data) = decode_entry(&entry.raw_line) else {
+        log(
+            APP_NAME,
+            &format!("cliphist decode failed for {}", entry.id),
+        );
+        return;
+    };
+
+    // wl-copy sniffs the type itself when none is given
+    let (data, mime) = match entry.kind {
+        ContentKind::Image => {
+            let (data, mime) = image_for_copy(entry, data);
+            (data, Some(mime))
         }
+        ContentKind::OtherBinary => (data, None),
+        ContentKind::Text | ContentKind::Url => (data, Some("text/plain".to_string())),
+    };
+    wl_copy(&data, mime.as_deref());
+
+    if notify {
+        let summary = copy_summary(entry, data.len());
+        let msg = if entry.kind.is_binary() {
+            summary
+        } else if entry.masked {
+            format!("{}\n{}", summary, crate::mask::MASK)
+        } else {
+            format!("{}\n{}", summary, char_truncate(&entry.preview, 50))
+        };
+        crate::notify::send(&msg, notify_timeout_ms);
     }
 }
 
-pub fn delete_entry(entry: &ClipEntry) {
-    if let Ok(mut c) = Command::new("cliphist")
-        .arg("delete")
+/// Run `command` (e.g. `wtype -M ctrl v`) after `delay_ms`, once our window
+/// is gone and the previous app has keyboard focus again. Goes through a
+/// detached `sh` so it still fires when `--oneshot` exits right away.
+pub fn paste_into_focused(command: &str, delay_ms: u64, notify_timeout_ms: u32) {
+    let Some(program) = command.split_whitespace().next() else {
+        return;
+    };
+    if !in_path(program) {
+        log(
+            APP_NAME,
+            &format!("auto_paste: {} not found, entry copied only", program),
+        );
+        crate::notify::send(
+            &trf("auto_paste: {} not found, entry copied only", &[&program]),
+            notify_timeout_ms,
+        );
+        return;
+    }
+    let script = format!("sleep {}; {}", delay_ms as f64 / 1000.0, command);
+    if let Err(e) = Command::new("sh")
+        .args(["-c", &script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        log(APP_NAME, &format!("paste command failed: {}", e));
+    }
+}
+
+/// Scratch directory for images copied as paths, removed on exit:
+/// `$XDG_RUNTIME_DIR/<app>`, else `<tmp>/<app>-<uid>`
+fn copied_dir() -> PathBuf {
+    common::paths::runtime_dir()
+        .map(|d| d.join(APP_NAME))
+        .unwrap_or_else(|| {
+            std::env::temp_dir().join(format!("{}-{}", APP_NAME, unsafe { libc::getuid() }))
+        })
+}
+
+/// Create `copied_dir()` for this user alone, refusing one that someone
+/// else (or a symlink) already put there
+fn create_copied_dir() -> std::io::Result<PathBuf> {
+    let dir = copied_dir();
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+    let meta = std::fs::symlink_metadata(&dir)?;
+    if !meta.is_dir() || meta.uid() != unsafe { libc::getuid() } {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is not our directory", dir.display()),
+        ));
+    }
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    Ok(dir)
+}
+
+pub fn cleanup_copied_files() {
+    let _ = std::fs::remove_dir_all(copied_dir());
+}
+
+/// File extension for an image entry, from the format in its preview
+pub fn image_extension(preview: &str) -> String {
+    preview
+        .split_whitespace()
+        .map(|p| p.to_lowercase())
+        .find(|p| ["png", "jpg", "jpeg", "gif", "bmp", "webp"].contains(&p.as_str()))
+        .unwrap_or_else(|| "png".to_string())
+}
+
+/// Write an image entry to a temp file and copy its path as text.
+/// Text entries are copied as usual.
+pub fn copy_image_path(entry: &ClipEntry, notify: bool, notify_timeout_ms: u32) {
+    if !entry.is_image() {
+        select_entry(entry, notify, notify_timeout_ms);
+        return;
+    }
+    let Some(data) = decode_entry(&entry.raw_line) else {
+        log(
+            APP_NAME,
+            &format!("cliphist decode failed for {}", entry.id),
+        );
+        return;
+    };
+
+    let name = format!("{}.{}", entry.id, image_extension(&entry.preview));
+    let written = create_copied_dir().and_then(|dir| {
+        let path = dir.join(&name);
+        std::fs::write(&path, &data).map(|_| path)
+    });
+    let path = match written {
+        Ok(path) => path,
+        Err(e) => {
+            log(
+                APP_NAME,
+                &format!(
+                    "failed to write {}: {}",
+                    copied_dir().join(&name).display(),
+                    e
+                ),
+            );
+            return;
+        }
+    };
+
+    let path_str = path.to_string_lossy().to_string();
+    match Command::new("wl-copy")
+        .args(["--type", "text/plain"])
         .stdin(Stdio::piped())
         .spawn()
     {
-        if let Some(mut si) = c.stdin.take() {
-            let _ = si.write_all(entry.raw_line.as_bytes());
-            drop(si);
+        Ok(mut wl) => {
+            if let Some(mut si) = wl.stdin.take() {
+                let _ = si.write_all(path_str.as_bytes());
+            }
+            let _ = wl.wait();
+        }
+        Err(e) => {
+            log(APP_NAME, &format!("wl-copy failed: {}", e));
+            return;
         }
-        let _ = c.wait();
     }
-    if let Some(ref p) = entry.thumb_path {
-        let _ = std::fs::remove_file(p);
+
+    if notify {
+        crate::notify::send(
+            &trf("Copied image path\n{}", &[&path_str]),
+            notify_timeout_ms,
+        );
     }
 }
 
-pub fn content_type(e: &ClipEntry) -> &'static str {
-    if e.is_image {
-        return "IMAGE";
-    }
-    let p = e.preview.trim();
-    if p.starts_with("http://") || p.starts_with("https://") {
-        "URL"
-    } else {
-        "TEXT"
+pub fn delete_entry(entry: &ClipEntry) {
+    backend().delete(&entry.raw_line);
+    if let Some(ref p) = entry.thumb_path {
+        let _ = std::fs::remove_file(p);
     }
 }
 
@@ -289,7 +885,7 @@ pub fn parse_image_meta(preview: &str) -> Option<String> {
         if p.contains('x') && p.chars().all(|c| c.is_ascii_digit() || c == 'x') {
             dims = Some(p.to_string());
         }
-        if ["png", "jpg", "jpeg", "gif", "bmp", "webp"].contains(&p.to_lowercase().as_str()) {
+        if IMAGE_FORMATS.contains(&p.to_lowercase().as_str()) {
             fmt = Some(p.to_uppercase());
         }
     }
@@ -302,17 +898,49 @@ pub fn parse_image_meta(preview: &str) -> Option<String> {
     }
 }
 
-pub fn get_filtered_entry(entries: &[ClipEntry], query: &str, idx: usize) -> Option<ClipEntry> {
-    let q = query.to_lowercase();
-    let filtered: Vec<&ClipEntry> = if q.is_empty() {
-        entries.iter().collect()
-    } else {
-        entries
-            .iter()
-            .filter(|e| e.preview.to_lowercase().contains(&q))
-            .collect()
+/// `query` extended by whatever every matching text entry has right after
+/// it, for Tab in the search field; with a single match, that's the rest of
+/// its preview. None when there's nothing to add.
+pub fn complete_query(entries: &[ClipEntry], query: &str, smartcase: bool) -> Option<String> {
+    if query.is_empty() {
+        return None;
+    }
+    let ic = ignore_case(query, smartcase);
+    let fold = |c: char| {
+        if ic {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
     };
-    filtered.get(idx).map(|e| (*e).clone())
+    let q: Vec<char> = query.chars().map(fold).collect();
+
+    let mut common: Option<Vec<char>> = None;
+    for e in entries.iter().filter(|e| !e.masked && !e.kind.is_binary()) {
+        let text: Vec<char> = e.preview.chars().collect();
+        let Some(at) = text
+            .windows(q.len())
+            .position(|w| w.iter().map(|&c| fold(c)).eq(q.iter().copied()))
+        else {
+            continue;
+        };
+        let rest = &text[at + q.len()..];
+        let shared = match common {
+            None => rest.to_vec(),
+            Some(c) => c
+                .iter()
+                .zip(rest)
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| *a)
+                .collect(),
+        };
+        if shared.is_empty() {
+            return None;
+        }
+        common = Some(shared);
+    }
+    let ext: String = common?.into_iter().collect();
+    Some(format!("{}{}", query, ext.trim_end()))
 }
 
 /// Update thumbnail path for an entry by ID
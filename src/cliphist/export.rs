@@ -0,0 +1,179 @@
+use crate::entries::{decode_entry, fetch_entries_fast, ClipEntry};
+use common::json::escape;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Text,
+}
+
+pub fn parse_export_format(s: &str) -> Option<ExportFormat> {
+    match s.to_lowercase().as_str() {
+        "json" => Some(ExportFormat::Json),
+        "text" | "txt" => Some(ExportFormat::Text),
+        _ => None,
+    }
+}
+
+/// The path after `--export`: the first argument that isn't a flag or the
+/// value of `--export-format`
+pub fn export_path(args: &[String]) -> Option<&str> {
+    let mut rest = args.iter().skip_while(|a| *a != "--export").skip(1);
+    while let Some(a) = rest.next() {
+        if a == "--export-format" {
+            rest.next();
+        } else if !a.starts_with("--") {
+            return Some(a);
+        }
+    }
+    None
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        out.push(TABLE[(n >> 18) as usize & 63] as char);
+        out.push(TABLE[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 {
+            TABLE[(n >> 6) as usize & 63] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            TABLE[n as usize & 63] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+fn json_record(entry: &ClipEntry, data: &[u8]) -> String {
+    if entry.kind.is_binary() {
+        let kind = if entry.is_image() { "image" } else { "binary" };
+        format!(
+            "{{\"id\":\"{}\",\"type\":\"{}\",\"first_seen\":{},\"data_base64\":\"{}\"}}",
+            escape(&entry.id),
+            kind,
+            entry.first_seen,
+            base64_encode(data)
+        )
+    } else {
+        format!(
+            "{{\"id\":\"{}\",\"type\":\"text\",\"first_seen\":{},\"content\":\"{}\"}}",
+            escape(&entry.id),
+            entry.first_seen,
+            escape(&String::from_utf8_lossy(data))
+        )
+    }
+}
+
+/// One entry per line: `id<TAB>content`, with newlines escaped so each record stays greppable
+fn text_record(entry: &ClipEntry, data: &[u8]) -> String {
+    if entry.kind.is_binary() {
+        format!(
+            "{}\t[{} skipped: {}]",
+            entry.id,
+            entry.kind.label().to_lowercase(),
+            entry.preview.trim()
+        )
+    } else {
+        let text = String::from_utf8_lossy(data)
+            .replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\t', "\\t");
+        format!("{}\t{}", entry.id, text)
+    }
+}
+
+/// Decode every entry in `cliphist list` and write them to `path`.
+/// Returns the number of entries written.
+pub fn export_history(path: &str, format: ExportFormat) -> std::io::Result<usize> {
+    let entries = fetch_entries_fast(0, 0);
+    // The history may hold passwords, so keep the copy private
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // The mode above only applies to new files
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    let mut f = std::io::BufWriter::new(file);
+    let mut count = 0;
+
+    if format == ExportFormat::Json {
+        writeln!(f, "[")?;
+    }
+
+    for entry in &entries {
+        let data = if entry.kind.is_binary() && format == ExportFormat::Text {
+            Vec::new()
+        } else {
+            match decode_entry(&entry.raw_line) {
+                Some(d) => d,
+                None => {
+                    eprintln!("skipping {}: cliphist decode failed", entry.id);
+                    continue;
+                }
+            }
+        };
+
+        match format {
+            ExportFormat::Json => {
+                let sep = if count > 0 { ",\n" } else { "" };
+                write!(f, "{}  {}", sep, json_record(entry, &data))?;
+            }
+            ExportFormat::Text => writeln!(f, "{}", text_record(entry, &data))?,
+        }
+        count += 1;
+    }
+
+    if format == ExportFormat::Json {
+        writeln!(f, "\n]")?;
+    }
+    f.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn export_path_skips_the_format_flag() {
+        let a = args(&["cliphist-gui", "--export", "out.json"]);
+        assert_eq!(export_path(&a), Some("out.json"));
+        let a = args(&[
+            "cliphist-gui",
+            "--export",
+            "--export-format",
+            "text",
+            "out.txt",
+        ]);
+        assert_eq!(export_path(&a), Some("out.txt"));
+        let a = args(&[
+            "cliphist-gui",
+            "--export",
+            "out.txt",
+            "--export-format",
+            "text",
+        ]);
+        assert_eq!(export_path(&a), Some("out.txt"));
+        let a = args(&["cliphist-gui", "--export", "--export-format", "text"]);
+        assert_eq!(export_path(&a), None);
+    }
+}
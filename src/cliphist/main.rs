@@ -1,7 +1,17 @@
 mod app;
+mod backend;
 mod config;
+mod drag;
 mod entries;
+mod export;
+mod mask;
+mod notify;
+mod preview;
+mod selftest;
+mod stats;
 mod ui;
+mod view;
+mod watch;
 
 use gtk4::prelude::*;
 use gtk4::Application;
@@ -9,31 +19,71 @@ use std::process::Command;
 
 use app::{activate, setup_signals};
 use common::cli::{
-    cmd_config, cmd_generate_config, cmd_reload, get_pid, pidfile_path, remove_pid, write_pid,
+    cmd_config, cmd_generate_config, cmd_reload, cmd_version, get_pid, pidfile_path, print_usage,
+    query_arg, remove_pid, request_query, request_window, write_pid, WindowRequest,
 };
 use config::{default_config, default_css, APP_NAME};
+use export::{export_history, export_path, parse_export_format, ExportFormat};
 
-fn print_usage() {
-    eprintln!("{} - clipboard manager\n", APP_NAME);
-    eprintln!("Usage:");
-    eprintln!("  {}                      Start daemon", APP_NAME);
-    eprintln!("  {} toggle               Toggle window", APP_NAME);
-    eprintln!("  {} --theme <name>       Preview theme", APP_NAME);
-    eprintln!("  {} show-themes          List themes", APP_NAME);
-    eprintln!("  {} --config             Show config dir", APP_NAME);
-    eprintln!("  {} --generate-config    Create defaults", APP_NAME);
-    eprintln!("  {} --reload             Restart daemon", APP_NAME);
-    eprintln!("  {} --help               Show help", APP_NAME);
+/// `--help`: arguments and what they do. A leading space marks an option
+/// of the line above.
+const USAGE: &[(&str, &str)] = &[
+    ("", "Start daemon"),
+    ("toggle", "Toggle window"),
+    ("open [text]", "Show window (focus if shown)"),
+    ("--query <text>", "Open with the search filled in"),
+    ("close", "Hide window"),
+    ("--theme <name>", "Preview theme"),
+    ("show-themes", "List themes"),
+    (" [--json]", "(names as a JSON array)"),
+    ("--preview-themes", "Cycle themes live"),
+    ("--config", "Show config dir"),
+    ("--generate-config", "Create defaults"),
+    ("--check-config", "Validate config"),
+    ("--edit-config", "Open config in $EDITOR"),
+    ("--dump-config", "Print effective config as JSON"),
+    ("--reload", "Restart daemon"),
+    ("--restart-if-unresponsive", "Restart daemon if it hangs"),
+    ("--export <path>", "Export history"),
+    (" [--export-format json|text]", "(default: json)"),
+    ("--wipe --yes", "Clear all history (can't be undone)"),
+    ("--stats", "Show history and cache sizes"),
+    ("--clear-cache", "Delete cached thumbnails"),
+    (" [--all]", "(the whole cache dir)"),
+    ("--oneshot", "Show once and exit (no daemon)"),
+    ("--version", "Show version"),
+    ("--help", "Show help"),
+];
+
+/// `--wipe` needs `--yes`, or a "y" typed at a terminal prompt
+fn confirm_wipe(args: &[String]) -> bool {
+    use std::io::{IsTerminal, Write};
+    if args.iter().any(|a| a == "--yes") {
+        return true;
+    }
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    print!("Delete the whole clipboard history? This can't be undone. [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    let _ = std::io::stdin().read_line(&mut answer);
+    matches!(answer.trim(), "y" | "Y" | "yes")
 }
 
 fn main() {
+    common::i18n::init(APP_NAME);
     let args: Vec<String> = std::env::args().collect();
     let pidfile = pidfile_path(APP_NAME);
 
     if args.len() > 1 {
         match args[1].as_str() {
             "--help" | "-h" => {
-                print_usage();
+                print_usage(APP_NAME, "clipboard manager", USAGE);
+                return;
+            }
+            "--version" | "-V" => {
+                cmd_version(APP_NAME, env!("CARGO_PKG_VERSION"), env!("GIT_HASH"));
                 return;
             }
             "--config" => {
@@ -44,31 +94,128 @@ fn main() {
                 cmd_generate_config(APP_NAME, default_css(), default_config());
                 return;
             }
+            "--check-config" => {
+                common::cli::cmd_check_config(APP_NAME, &config::Config::load().base);
+                return;
+            }
+            "--dump-config" => {
+                println!("{}", config::Config::load().to_json().pretty());
+                return;
+            }
+            "--edit-config" | "edit-config" => {
+                common::cli::cmd_edit_config(APP_NAME, default_css(), default_config(), &|_| None);
+                return;
+            }
             "--reload" => {
                 cmd_reload(APP_NAME, &pidfile);
                 return;
             }
-            "toggle" | "open" => {
-                if let Some(pid) = get_pid(&pidfile) {
-                    unsafe { libc::kill(pid, libc::SIGUSR1) };
-                } else {
-                    eprintln!("Daemon not running");
+            "--restart-if-unresponsive" => {
+                common::cli::cmd_restart_if_unresponsive(APP_NAME, &pidfile);
+                return;
+            }
+            "--export" => {
+                let Some(path) = export_path(&args) else {
+                    eprintln!(
+                        "Usage: {} --export <path> [--export-format json|text]",
+                        APP_NAME
+                    );
+                    std::process::exit(1);
+                };
+                let format = match args.iter().position(|a| a == "--export-format") {
+                    Some(i) => match args.get(i + 1).and_then(|f| parse_export_format(f)) {
+                        Some(f) => f,
+                        None => {
+                            eprintln!("--export-format must be json or text");
+                            std::process::exit(1);
+                        }
+                    },
+                    None => ExportFormat::Json,
+                };
+                config::Config::load(); // Selects [behavior] backend
+                match export_history(path, format) {
+                    Ok(n) => println!("Exported {} entries to {}", n, path),
+                    Err(e) => {
+                        eprintln!("Export failed: {}", e);
+                        std::process::exit(1);
+                    }
                 }
                 return;
             }
-            "close" => {
-                if let Some(pid) = get_pid(&pidfile) {
-                    unsafe { libc::kill(pid, libc::SIGTERM) };
+            "--wipe" => {
+                if !confirm_wipe(&args) {
+                    eprintln!("History left untouched; pass --yes to wipe without asking");
+                    std::process::exit(1);
                 }
+                config::Config::load();
+                backend::backend().wipe();
+                println!("Clipboard history cleared");
+                return;
+            }
+            "--stats" => {
+                stats::print_stats(&config::Config::load());
                 return;
             }
-            "show-themes" | "--themes" => {
-                println!("Available themes:");
-                for (name, _) in common::paths::builtin_themes() {
-                    println!("  {}", name);
+            // Hidden: timings for performance work
+            "--selftest" => {
+                selftest::run(&config::Config::load());
+                return;
+            }
+            "--clear-cache" => {
+                stats::clear_cache(args.iter().any(|a| a == "--all"));
+                return;
+            }
+            // Opens a running daemon's window, else falls through to startup
+            "--query" => {
+                let Some(query) = query_arg(&args) else {
+                    eprintln!("Usage: {} --query <text>", APP_NAME);
+                    std::process::exit(1);
+                };
+                if let Some(pid) = get_pid(&pidfile) {
+                    if let Err(e) = request_query(APP_NAME, &query)
+                        .and_then(|_| request_window(APP_NAME, pid, WindowRequest::Open))
+                    {
+                        eprintln!("Failed to send --query: {}", e);
+                        std::process::exit(1);
+                    }
+                    return;
+                }
+            }
+            // Falls through to startup, skipping the pidfile
+            "--oneshot" => common::cli::set_oneshot(true),
+            // Oneshot window with theme cycling on top
+            "--preview-themes" => {
+                common::cli::set_oneshot(true);
+                common::theme_preview::set_active(true);
+            }
+            "toggle" | "open" | "close" => {
+                let request = match args[1].as_str() {
+                    "open" => WindowRequest::Open,
+                    "close" => WindowRequest::Close,
+                    _ => WindowRequest::Toggle,
+                };
+                let query = query_arg(&args).filter(|_| request != WindowRequest::Close);
+                match get_pid(&pidfile) {
+                    Some(pid) => {
+                        let sent = match query {
+                            Some(q) => request_query(APP_NAME, &q),
+                            None => Ok(()),
+                        };
+                        if let Err(e) = sent.and_then(|_| request_window(APP_NAME, pid, request)) {
+                            eprintln!("Failed to send {}: {}", args[1], e);
+                            std::process::exit(1);
+                        }
+                    }
+                    // Nothing to close
+                    None if request == WindowRequest::Close => {}
+                    None => eprintln!("Daemon not running"),
                 }
                 return;
             }
+            "show-themes" | "--themes" | "--list-themes" => {
+                common::cli::cmd_list_themes(args.iter().any(|a| a == "--json"));
+                return;
+            }
             "-T" | "--theme" => {
                 if args.len() < 3 {
                     eprintln!("Usage: {} --theme <name>", APP_NAME);
@@ -80,9 +227,14 @@ fn main() {
                     return;
                 }
                 if let Some(pid) = get_pid(&pidfile) {
-                    unsafe { libc::kill(pid, libc::SIGTERM) };
-                    std::thread::sleep(std::time::Duration::from_millis(100));
-                    let _ = std::fs::remove_file(&pidfile);
+                    match common::cli::request_theme(APP_NAME, pid, theme) {
+                        Ok(()) => println!("Switched to theme: {}", theme),
+                        Err(e) => {
+                            eprintln!("Failed to switch theme: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                    return;
                 }
                 let exe = std::env::current_exe().expect("cannot find self");
                 let _ = Command::new(&exe)
@@ -95,18 +247,27 @@ fn main() {
             }
             other => {
                 eprintln!("Unknown option: {}", other);
-                print_usage();
+                print_usage(APP_NAME, "clipboard manager", USAGE);
                 std::process::exit(1);
             }
         }
     }
 
-    if let Some(pid) = get_pid(&pidfile) {
-        unsafe { libc::kill(pid, libc::SIGUSR1) };
-        return;
+    let oneshot = common::cli::is_oneshot();
+    if !oneshot {
+        if let Some(pid) = get_pid(&pidfile) {
+            unsafe { libc::kill(pid, libc::SIGUSR1) };
+            return;
+        }
+        write_pid(&pidfile);
+        // Leftovers from a daemon that was killed before it could clean up
+        entries::cleanup_copied_files();
     }
-
-    write_pid(&pidfile);
+    // `--query`, also under `--oneshot`, for the window about to open
+    if let Some(query) = query_arg(&args) {
+        let _ = request_query(APP_NAME, &query);
+    }
+    notify::init();
 
     let app = Application::builder()
         .application_id("com.vib1240n.cliphist-gui")
@@ -119,5 +280,9 @@ fn main() {
     });
 
     app.run_with_args::<String>(&[]);
-    remove_pid(&pidfile);
+    // A oneshot run leaves copied image paths for the clipboard to point at
+    if !oneshot {
+        entries::cleanup_copied_files();
+        remove_pid(&pidfile);
+    }
 }
@@ -0,0 +1,323 @@
+use crate::entries::ClipEntry;
+
+pub const MASK: &str = "••••••";
+
+/// Single token, long, and high-entropy enough to look like a generated secret
+pub fn looks_like_secret(preview: &str) -> bool {
+    let t = preview.trim();
+    let len = t.chars().count();
+    if !(12..=128).contains(&len) || t.chars().any(char::is_whitespace) {
+        return false;
+    }
+    if t.contains("://") || t.starts_with('/') || t.starts_with('~') {
+        return false;
+    }
+
+    let classes = [
+        t.chars().any(|c| c.is_ascii_lowercase()),
+        t.chars().any(|c| c.is_ascii_uppercase()),
+        t.chars().any(|c| c.is_ascii_digit()),
+        t.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|&&b| b)
+    .count();
+
+    classes >= 3 && shannon_entropy(t) >= 3.5
+}
+
+/// Bits per character
+fn shannon_entropy(s: &str) -> f64 {
+    let mut counts: std::collections::HashMap<char, f64> = std::collections::HashMap::new();
+    let mut total = 0.0;
+    for c in s.chars() {
+        *counts.entry(c).or_insert(0.0) += 1.0;
+        total += 1.0;
+    }
+    counts
+        .values()
+        .map(|&n| {
+            let p = n / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Mark entries whose preview should be hidden in the list. A bad
+/// `mask_regex` is reported by the config, so here it just doesn't match.
+pub fn apply_masking(entries: &mut [ClipEntry], mask_regex: &str) {
+    let pattern = if mask_regex.is_empty() {
+        None
+    } else {
+        Pattern::parse(mask_regex).ok()
+    };
+    for e in entries.iter_mut().filter(|e| !e.kind.is_binary()) {
+        e.masked = looks_like_secret(&e.preview)
+            || pattern.as_ref().is_some_and(|p| p.is_match(&e.preview));
+    }
+}
+
+#[derive(Debug)]
+enum Atom {
+    Any,
+    Char(char),
+    Class(Vec<(char, char)>, bool),
+    Digit,
+    Word,
+    Space,
+}
+
+impl Atom {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Atom::Any => true,
+            Atom::Char(x) => *x == c,
+            Atom::Class(ranges, negated) => {
+                ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+            }
+            Atom::Digit => c.is_ascii_digit(),
+            Atom::Word => c.is_alphanumeric() || c == '_',
+            Atom::Space => c.is_whitespace(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Piece {
+    atom: Atom,
+    min: usize,
+    max: usize,
+}
+
+/// Small regex subset for `mask_regex`: literals, `.`, `[...]`/`[^...]`,
+/// `\d \w \s`, the `* + ?` quantifiers, and `^`/`$` anchors. Anything else
+/// (groups, `|`, `{n}`, other letter escapes) is rejected rather than
+/// quietly read as a literal.
+#[derive(Debug)]
+pub struct Pattern {
+    pieces: Vec<Piece>,
+    anchor_start: bool,
+    anchor_end: bool,
+}
+
+impl Pattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let mut chars = pattern.chars().peekable();
+        let anchor_start = chars.peek() == Some(&'^');
+        if anchor_start {
+            chars.next();
+        }
+        let mut pieces = Vec::new();
+        let mut anchor_end = false;
+
+        while let Some(c) = chars.next() {
+            let atom = match c {
+                '$' if chars.peek().is_none() => {
+                    anchor_end = true;
+                    break;
+                }
+                '.' => Atom::Any,
+                '\\' => match chars.next() {
+                    Some('d') => Atom::Digit,
+                    Some('w') => Atom::Word,
+                    Some('s') => Atom::Space,
+                    Some(x) if x.is_ascii_alphanumeric() => {
+                        return Err(format!("unsupported escape '\\{}'", x))
+                    }
+                    Some(x) => Atom::Char(x),
+                    None => return Err("trailing '\\'".to_string()),
+                },
+                '[' => {
+                    let negated = chars.peek() == Some(&'^');
+                    if negated {
+                        chars.next();
+                    }
+                    let unterminated = || "unterminated '['".to_string();
+                    let mut ranges = Vec::new();
+                    loop {
+                        let lo = match chars.next().ok_or_else(unterminated)? {
+                            ']' if ranges.is_empty() => return Err("empty '[]'".to_string()),
+                            ']' => break,
+                            '\\' => chars.next().ok_or_else(unterminated)?,
+                            x => x,
+                        };
+                        if chars.peek() == Some(&'-') {
+                            chars.next();
+                            match chars.next().ok_or_else(unterminated)? {
+                                ']' => {
+                                    ranges.push((lo, lo));
+                                    ranges.push(('-', '-'));
+                                    break;
+                                }
+                                hi if hi < lo => return Err(format!("bad range '{}-{}'", lo, hi)),
+                                hi => ranges.push((lo, hi)),
+                            }
+                        } else {
+                            ranges.push((lo, lo));
+                        }
+                    }
+                    Atom::Class(ranges, negated)
+                }
+                '*' | '+' | '?' => return Err(format!("'{}' has nothing to repeat", c)),
+                '|' | '(' | ')' | '{' | '}' => {
+                    return Err(format!("'{}' is not supported (escape it as '\\{}')", c, c))
+                }
+                x => Atom::Char(x),
+            };
+            let (min, max) = match chars.peek() {
+                Some('*') => (0, usize::MAX),
+                Some('+') => (1, usize::MAX),
+                Some('?') => (0, 1),
+                _ => (1, 1),
+            };
+            if (min, max) != (1, 1) {
+                chars.next();
+            }
+            pieces.push(Piece { atom, min, max });
+        }
+
+        Ok(Self {
+            pieces,
+            anchor_start,
+            anchor_end,
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        // One cell per (piece, position), so a start that already failed
+        // from a given state isn't retried: O(pieces * len^2) at worst
+        let mut memo = vec![None; (self.pieces.len() + 1) * (chars.len() + 1)];
+        if self.anchor_start {
+            return self.match_at(0, 0, &chars, &mut memo);
+        }
+        (0..=chars.len()).any(|pos| self.match_at(0, pos, &chars, &mut memo))
+    }
+
+    fn match_at(&self, piece: usize, pos: usize, text: &[char], memo: &mut [Option<bool>]) -> bool {
+        let cell = piece * (text.len() + 1) + pos;
+        if let Some(known) = memo[cell] {
+            return known;
+        }
+        let result = match self.pieces.get(piece) {
+            None => !self.anchor_end || pos == text.len(),
+            Some(p) => {
+                // Greedy: take as many as allowed, then back off
+                let mut n = 0;
+                while n < p.max && pos + n < text.len() && p.atom.matches(text[pos + n]) {
+                    n += 1;
+                }
+                (p.min..=n)
+                    .rev()
+                    .any(|k| self.match_at(piece + 1, pos + k, text, memo))
+            }
+        };
+        memo[cell] = Some(result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entries::ContentKind;
+
+    fn clip(preview: &str, kind: ContentKind) -> ClipEntry {
+        ClipEntry {
+            raw_line: format!("1\t{}", preview),
+            id: "1".to_string(),
+            preview: preview.to_string(),
+            kind,
+            thumb_path: None,
+            first_seen: 0,
+            masked: false,
+            line_count: 1,
+            lines: Vec::new(),
+            large_size: None,
+        }
+    }
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Pattern::parse(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn generated_tokens_look_like_secrets() {
+        assert!(looks_like_secret("hT8#kq2!Zr9@xW"));
+        assert!(looks_like_secret("  Xk3pQ9zL7mW2vR8n  "));
+    }
+
+    #[test]
+    fn words_paths_and_urls_do_not_look_like_secrets() {
+        assert!(!looks_like_secret("short1A!"));
+        assert!(!looks_like_secret("correct horse battery staple"));
+        assert!(!looks_like_secret("https://example.com/Ab1x"));
+        assert!(!looks_like_secret("/usr/share/Applications1"));
+        assert!(!looks_like_secret("aaaaaaaaaaaaaaaa"));
+        assert!(!looks_like_secret("abababababAB12ab"));
+        assert!(!looks_like_secret(&"aB3$".repeat(40)));
+    }
+
+    #[test]
+    fn literals_match_anywhere_unless_anchored() {
+        assert!(matches("token", "my token here"));
+        assert!(!matches("^token", "my token"));
+        assert!(matches("^token", "token here"));
+        assert!(!matches("token$", "token here"));
+        assert!(matches("^token$", "token"));
+        assert!(matches("", "anything"));
+    }
+
+    #[test]
+    fn classes_escapes_and_quantifiers() {
+        assert!(matches("^sk-[a-zA-Z0-9]+$", "sk-Abc123"));
+        assert!(!matches("^sk-[a-zA-Z0-9]+$", "sk-"));
+        assert!(!matches("^[^0-9]+$", "abc1"));
+        assert!(matches("^[a-]+$", "a-a"));
+        assert!(matches(r"^\d\d?\s\w+$", "7 pin_code"));
+        assert!(matches(r"^a\.b$", "a.b"));
+        assert!(!matches(r"^a\.b$", "axb"));
+        assert!(matches("^colou?r$", "color"));
+        assert!(matches("^a.*b$", "a123b"));
+    }
+
+    #[test]
+    fn unsupported_or_broken_syntax_is_rejected() {
+        for bad in [
+            "a|b", "(ab)", "a{3}", "*a", "a**", "[abc", "[]", "[z-a]", r"ab\", r"\bword",
+        ] {
+            assert!(Pattern::parse(bad).is_err(), "{} should be rejected", bad);
+        }
+        assert!(Pattern::parse(r"a\|b\(c\)").is_ok());
+    }
+
+    #[test]
+    fn stacked_wildcards_do_not_backtrack_exponentially() {
+        let text = "a".repeat(200);
+        assert!(!matches(".*.*.*.*.*.*.*.*x", &text));
+        assert!(!matches("^a*a*a*a*a*a*a*a*b$", &text));
+    }
+
+    #[test]
+    fn masking_skips_binary_entries() {
+        let mut entries = vec![
+            clip("hunter2-password", ContentKind::Text),
+            clip(
+                "[[ binary data 2 KiB png 10x10 ]] password",
+                ContentKind::Image,
+            ),
+            clip("hT8#kq2!Zr9@xW", ContentKind::OtherBinary),
+        ];
+        apply_masking(&mut entries, "password");
+        assert!(entries[0].masked);
+        assert!(!entries[1].masked);
+        assert!(!entries[2].masked);
+    }
+
+    #[test]
+    fn a_bad_mask_regex_masks_nothing_extra() {
+        let mut entries = vec![clip("password (old)", ContentKind::Text)];
+        apply_masking(&mut entries, "(old");
+        assert!(!entries[0].masked);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::config::APP_NAME;
+use common::logging::log;
+use glib::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+
+const NOTIFY_BUS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFY_PATH: &str = "/org/freedesktop/Notifications";
+
+enum Backend {
+    NotifySend,
+    DBus(gio::DBusConnection),
+    Log,
+}
+
+thread_local! {
+    static BACKEND: RefCell<Option<Backend>> = const { RefCell::new(None) };
+}
+
+fn detect() -> Backend {
+    let has_notify_send = Command::new("notify-send")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok();
+    if has_notify_send {
+        return Backend::NotifySend;
+    }
+    match gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE) {
+        Ok(conn) => {
+            log(APP_NAME, "notify-send not found, using DBus notifications");
+            Backend::DBus(conn)
+        }
+        Err(e) => {
+            log(
+                APP_NAME,
+                &format!("no notification backend (session bus: {})", e),
+            );
+            Backend::Log
+        }
+    }
+}
+
+/// Pick a notification backend; call once at daemon startup
+pub fn init() {
+    BACKEND.with(|b| *b.borrow_mut() = Some(detect()));
+}
+
+/// Show a desktop notification without blocking: notify-send if installed,
+/// else a direct org.freedesktop.Notifications call, else just a log line
+pub fn send(body: &str, timeout_ms: u32) {
+    BACKEND.with(|b| {
+        let mut backend = b.borrow_mut();
+        match backend.get_or_insert_with(detect) {
+            Backend::NotifySend => {
+                let _ = Command::new("notify-send")
+                    .args(["-t", &timeout_ms.to_string(), APP_NAME, body])
+                    .spawn();
+            }
+            Backend::DBus(conn) => {
+                let params = (
+                    APP_NAME,
+                    0u32,
+                    "",
+                    APP_NAME,
+                    body,
+                    Vec::<String>::new(),
+                    HashMap::<String, glib::Variant>::new(),
+                    timeout_ms.min(i32::MAX as u32) as i32,
+                )
+                    .to_variant();
+                conn.call(
+                    Some(NOTIFY_BUS_NAME),
+                    NOTIFY_PATH,
+                    NOTIFY_BUS_NAME,
+                    "Notify",
+                    Some(&params),
+                    None,
+                    gio::DBusCallFlags::NONE,
+                    -1,
+                    gio::Cancellable::NONE,
+                    |res| {
+                        if let Err(e) = res {
+                            log(APP_NAME, &format!("DBus notification failed: {}", e));
+                        }
+                    },
+                );
+            }
+            Backend::Log => log(APP_NAME, &format!("notification: {}", body)),
+        }
+    });
+}
@@ -0,0 +1,227 @@
+use crate::stats::human_size;
+use common::i18n::trf;
+use gtk4::prelude::*;
+use gtk4::{TextBuffer, TextTag};
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "def",
+    "default",
+    "do",
+    "done",
+    "elif",
+    "else",
+    "enum",
+    "esac",
+    "except",
+    "export",
+    "false",
+    "fi",
+    "finally",
+    "fn",
+    "for",
+    "from",
+    "func",
+    "function",
+    "if",
+    "impl",
+    "import",
+    "in",
+    "interface",
+    "let",
+    "local",
+    "loop",
+    "match",
+    "mod",
+    "mut",
+    "new",
+    "null",
+    "package",
+    "pub",
+    "return",
+    "self",
+    "static",
+    "struct",
+    "switch",
+    "then",
+    "this",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "use",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+    "None",
+    "True",
+    "False",
+];
+
+/// (tag name, foreground color)
+const TAGS: &[(&str, &str)] = &[
+    ("code-keyword", "#c678dd"),
+    ("code-string", "#98c379"),
+    ("code-comment", "#7f848e"),
+    ("code-number", "#d19a66"),
+];
+
+/// Rows shown by `hex_dump`; the rest is summarised
+const HEX_ROWS: usize = 64;
+
+/// `xxd`-style dump for contents that aren't valid UTF-8. `bytes` may be
+/// a prefix of `total` bytes.
+pub fn hex_dump(bytes: &[u8], total: usize) -> String {
+    let mut out: Vec<String> = bytes
+        .chunks(16)
+        .take(HEX_ROWS)
+        .enumerate()
+        .map(|(i, row)| {
+            let hex: Vec<String> = row.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = row
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("{:08x}  {:<47}  {}", i * 16, hex.join(" "), ascii)
+        })
+        .collect();
+    let shown = HEX_ROWS * 16;
+    let total = total.max(bytes.len());
+    if total > shown {
+        out.push(format!("... {} more bytes", total - shown));
+    }
+    out.join("\n")
+}
+
+/// A decoded prefix of `size` bytes as text, with a note when it was cut
+/// short; the bytes back if they aren't UTF-8
+pub fn prefix_text(mut data: Vec<u8>, size: usize) -> Result<String, Vec<u8>> {
+    let truncated = size > data.len();
+    if truncated {
+        // The cut may have split a character
+        if let Err(err) = std::str::from_utf8(&data) {
+            if err.error_len().is_none() {
+                data.truncate(err.valid_up_to());
+            }
+        }
+    }
+    let mut text = String::from_utf8(data).map_err(|e| e.into_bytes())?;
+    if truncated {
+        let note = trf(
+            "... (large, {}; showing the first {})",
+            &[&human_size(size as u64), &human_size(text.len() as u64)],
+        );
+        text.push_str("\n\n");
+        text.push_str(&note);
+    }
+    Ok(text)
+}
+
+/// Heuristic: a shebang, or enough braces/semicolons relative to the line count
+pub fn looks_like_code(text: &str) -> bool {
+    let t = text.trim_start();
+    if t.starts_with("#!") {
+        return true;
+    }
+    let lines = t.lines().count().max(1);
+    let symbols = t.chars().filter(|c| matches!(c, '{' | '}' | ';')).count();
+    symbols >= 3 && symbols * 2 >= lines
+}
+
+fn ensure_tags(buffer: &TextBuffer) {
+    let table = buffer.tag_table();
+    for (name, color) in TAGS {
+        if table.lookup(name).is_none() {
+            let tag = TextTag::builder().name(*name).foreground(*color).build();
+            table.add(&tag);
+        }
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn apply(buffer: &TextBuffer, tag: &str, start: usize, end: usize) {
+    buffer.apply_tag_by_name(
+        tag,
+        &buffer.iter_at_offset(start as i32),
+        &buffer.iter_at_offset(end as i32),
+    );
+}
+
+/// Set the preview text, highlighting it when enabled and it looks like code
+pub fn set_preview_text(buffer: &TextBuffer, text: &str, highlight: bool) {
+    buffer.set_text(text);
+    if !highlight || !looks_like_code(text) {
+        return;
+    }
+    ensure_tags(buffer);
+
+    let chars: Vec<char> = text.chars().collect();
+    let line_end = |from: usize| {
+        chars[from..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map(|p| from + p)
+            .unwrap_or(chars.len())
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let prev = if i > 0 { chars[i - 1] } else { '\n' };
+
+        if (c == '/' && chars.get(i + 1) == Some(&'/')) || (c == '#' && prev.is_whitespace()) {
+            let end = line_end(i);
+            apply(buffer, "code-comment", i, end);
+            i = end;
+        } else if c == '"' || c == '\'' {
+            let mut j = i + 1;
+            while j < chars.len() && chars[j] != c && chars[j] != '\n' {
+                if chars[j] == '\\' {
+                    j += 1;
+                }
+                j += 1;
+            }
+            let end = (j + 1).min(chars.len());
+            apply(buffer, "code-string", i, end);
+            i = end;
+        } else if c.is_ascii_digit() && !is_ident(prev) {
+            let mut j = i;
+            while j < chars.len() && (chars[j].is_ascii_alphanumeric() || chars[j] == '.') {
+                j += 1;
+            }
+            apply(buffer, "code-number", i, j);
+            i = j;
+        } else if is_ident(c) {
+            let mut j = i;
+            while j < chars.len() && is_ident(chars[j]) {
+                j += 1;
+            }
+            let word: String = chars[i..j].iter().collect();
+            if KEYWORDS.contains(&word.as_str()) {
+                apply(buffer, "code-keyword", i, j);
+            }
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+}
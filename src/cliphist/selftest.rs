@@ -0,0 +1,41 @@
+use crate::config::Config;
+use crate::entries::{apply_binary_marker, fetch_entries_fast};
+use common::search::contains_query;
+use common::selftest::{corpus, report, timed, QUERIES};
+
+/// Synthetic previews each query is matched against
+const CORPUS_SIZE: usize = 10_000;
+
+/// `--selftest`: time listing and searching the history without opening a
+/// window. Nothing is pruned, whatever `max_age_days` says.
+pub fn run(cfg: &Config) {
+    let (mut entries, elapsed) = timed(|| fetch_entries_fast(cfg.max_items, 0));
+    report("fetch_entries_fast", entries.len(), elapsed);
+    let ((), elapsed) = timed(|| apply_binary_marker(&mut entries, &cfg.binary_marker));
+    report("apply_binary_marker", entries.len(), elapsed);
+
+    for query in QUERIES {
+        let (matched, elapsed) = timed(|| {
+            entries
+                .iter()
+                .filter(|e| contains_query(&e.preview, query, cfg.base.smartcase))
+                .count()
+        });
+        report(&format!("search history {:?}", query), matched, elapsed);
+    }
+
+    let previews = corpus(CORPUS_SIZE);
+    for query in QUERIES {
+        let (matched, elapsed) = timed(|| {
+            previews
+                .iter()
+                .filter(|p| contains_query(p, query, cfg.base.smartcase))
+                .count()
+        });
+        report(
+            &format!("search {:?} x{}", query, CORPUS_SIZE),
+            matched,
+            elapsed,
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::config::{Config, APP_NAME};
+use crate::entries::{apply_binary_marker, fetch_entries_fast, thumb_cache, ContentKind};
+use std::path::Path;
+
+/// Total size of the files under `dir`, recursively; 0 if it's missing
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(rd) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    rd.flatten()
+        .map(|e| match e.metadata() {
+            Ok(m) if m.is_dir() => dir_size(&e.path()),
+            Ok(m) => m.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn file_count(dir: &Path) -> usize {
+    std::fs::read_dir(dir).map(|rd| rd.count()).unwrap_or(0)
+}
+
+/// `1536` -> "1.5 KiB"
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// `--stats`: history and on-disk footprint, to help decide when to prune
+pub fn print_stats(cfg: &Config) {
+    let mut entries = fetch_entries_fast(0, 0);
+    apply_binary_marker(&mut entries, &cfg.binary_marker);
+    let count = |kind: ContentKind| entries.iter().filter(|e| e.kind == kind).count();
+
+    let thumbs = thumb_cache();
+    let log = common::logging::log_path(APP_NAME);
+    let log_size = std::fs::metadata(&log).map(|m| m.len()).unwrap_or(0);
+
+    println!("Entries:     {}", entries.len());
+    println!("  text:      {}", count(ContentKind::Text));
+    println!("  urls:      {}", count(ContentKind::Url));
+    println!("  images:    {}", count(ContentKind::Image));
+    println!("  binary:    {}", count(ContentKind::OtherBinary));
+    println!(
+        "Thumbnails:  {} ({} files, {})",
+        human_size(dir_size(&thumbs)),
+        file_count(&thumbs),
+        thumbs.display()
+    );
+    println!("Log:         {} ({})", human_size(log_size), log.display());
+}
+
+/// `--clear-cache`: delete the thumbnails, or with `all` everything in the
+/// cache dir, leaving the dir itself in place and empty. Symlinks are
+/// removed, not followed, so nothing outside the dir is touched.
+pub fn clear_cache(all: bool) {
+    let dir = if all {
+        common::paths::cache_dir(APP_NAME)
+    } else {
+        thumb_cache()
+    };
+    let before = dir_size(&dir);
+    let mut failed = 0;
+    if let Ok(rd) = std::fs::read_dir(&dir) {
+        for entry in rd.flatten() {
+            let path = entry.path();
+            let removed = match entry.file_type() {
+                Ok(t) if t.is_dir() => std::fs::remove_dir_all(&path),
+                _ => std::fs::remove_file(&path),
+            };
+            if let Err(e) = removed {
+                eprintln!("Could not remove {}: {}", path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+    let _ = std::fs::create_dir_all(&dir);
+    println!(
+        "Freed {} ({})",
+        human_size(before.saturating_sub(dir_size(&dir))),
+        dir.display()
+    );
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
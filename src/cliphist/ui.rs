@@ -1,16 +1,143 @@
-use crate::entries::{content_type, parse_image_meta, ClipEntry};
-use common::css::char_truncate;
+use crate::config::BadgeLabels;
+use crate::drag::attach_drag_source;
+use crate::entries::{
+    magick_command, parse_image_meta, printable, relative_age, ClipEntry, ContentKind,
+};
+use crate::mask::MASK;
+use crate::stats::human_size;
+use crate::view::EntryView;
+use common::css::{char_truncate, is_compact, row_icon_size};
+use common::i18n::{tr, trf};
+use common::search::contains_query;
+use common::InitialSelection;
 use gtk4::prelude::*;
-use gtk4::{Align, Box as GtkBox, Label, ListBox, ListBoxRow, Orientation, Picture};
-use std::path::PathBuf;
+use gtk4::{Align, Box as GtkBox, FlowBoxChild, Label, ListBoxRow, Orientation, Picture};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
 
 const MAX_TEXT_PREVIEW: usize = 120;
 const MAX_SUB_PREVIEW: usize = 60;
 
+/// Grid card size; the window width decides how many fit per row
+pub const CARD_WIDTH: i32 = 160;
+pub const CARD_THUMB_HEIGHT: i32 = 100;
+const CARD_TEXT_LINES: i32 = 5;
+
+/// Badge wording for rows built from now on; None hides badges
+static BADGES: RwLock<Option<BadgeLabels>> = RwLock::new(None);
+
+/// `[style] show_badges` (None when off) and the `[ui] badge_*` labels
+pub fn set_badges(badges: Option<BadgeLabels>) {
+    if let Ok(mut b) = BADGES.write() {
+        *b = badges;
+    }
+}
+
+/// Whether rows built from now on get a tooltip (`[behavior] show_tooltips`)
+static TOOLTIPS: AtomicBool = AtomicBool::new(true);
+
+pub fn set_tooltips(on: bool) {
+    TOOLTIPS.store(on, Ordering::Relaxed);
+}
+
+/// Hover text for `entry`: the whole preview (or image details), then its
+/// type, line count, size when known and when it was first seen
+fn tooltip_text(entry: &ClipEntry) -> String {
+    let body = if entry.is_image() {
+        parse_image_meta(&entry.preview).unwrap_or_else(|| binary_meta(&entry.preview))
+    } else if entry.kind == ContentKind::OtherBinary {
+        binary_meta(&entry.preview)
+    } else if entry.masked {
+        MASK.to_string()
+    } else if !entry.lines.is_empty() {
+        entry.lines.join("\n")
+    } else {
+        entry.preview.trim().to_string()
+    };
+    let mut meta = vec![tr(match entry.kind {
+        ContentKind::Text => "Text",
+        ContentKind::Url => "Link",
+        ContentKind::Image => "Image",
+        ContentKind::OtherBinary => "Binary data",
+    })
+    .to_string()];
+    if entry.line_count > 1 && !entry.kind.is_binary() {
+        meta.push(trf("{} lines", &[&entry.line_count]));
+    }
+    if let Some(size) = entry.large_size {
+        meta.push(human_size(size as u64));
+    }
+    let seen = glib::DateTime::from_unix_local(entry.first_seen as i64)
+        .and_then(|t| t.format("%Y-%m-%d %H:%M"))
+        .map(|t| format!("{} ({})", t, relative_age(entry.first_seen)))
+        .unwrap_or_else(|_| relative_age(entry.first_seen));
+    meta.push(trf("first seen {}", &[&seen]));
+    format!("{}\n{}", body, meta.join(" · "))
+}
+
+fn set_tooltip(widget: &impl IsA<gtk4::Widget>, entry: &ClipEntry) {
+    if TOOLTIPS.load(Ordering::Relaxed) {
+        widget.set_tooltip_text(Some(&tooltip_text(entry)));
+    }
+}
+
+/// Fill a `thumb_container` for `entry`: the cached thumbnail, a loading or
+/// no-ImageMagick placeholder for images, else a T (or BIN) icon
+fn fill_thumb(container: &GtkBox, entry: &ClipEntry, width: i32, height: i32) {
+    if let Some(ref path) = entry.thumb_path {
+        container.append(&thumb_frame(path, width, height));
+    } else if entry.is_image() && magick_command().is_none() {
+        // No ImageMagick - thumbnails will never arrive
+        container.append(&icon_box("IMG", width, height, "clip-thumb-unavailable"));
+    } else if entry.is_image() {
+        // Loading indicator until the thumbnail is generated
+        container.append(&icon_box("...", width, height, "clip-thumb-loading"));
+    } else if entry.kind == ContentKind::OtherBinary {
+        container.append(&icon_box("BIN", width, height, "clip-binary-icon"));
+    } else {
+        container.append(&icon_box("T", width, height, ""));
+    }
+}
+
+fn thumb_frame(path: &Path, width: i32, height: i32) -> gtk4::Frame {
+    let pic = Picture::for_filename(path.to_str().unwrap_or(""));
+    pic.set_size_request(width, height);
+    pic.add_css_class("clip-thumb");
+    let frame = gtk4::Frame::new(None);
+    frame.set_child(Some(&pic));
+    frame.add_css_class("clip-thumb-frame");
+    frame.set_size_request(width, height);
+    frame
+}
+
+/// Text-icon placeholder, with an extra CSS class unless `class` is empty
+fn icon_box(text: &str, width: i32, height: i32, class: &str) -> GtkBox {
+    let ib = GtkBox::new(Orientation::Vertical, 0);
+    ib.set_size_request(width, height);
+    ib.set_valign(Align::Center);
+    ib.set_halign(Align::Center);
+    ib.add_css_class("clip-text-icon");
+    if !class.is_empty() {
+        ib.add_css_class(class);
+    }
+    let lbl = Label::new(Some(text));
+    lbl.add_css_class("clip-text-icon-label");
+    lbl.set_valign(Align::Center);
+    lbl.set_halign(Align::Center);
+    lbl.set_vexpand(true);
+    ib.append(&lbl);
+    ib
+}
+
 /// Build a row - uses placeholder for missing thumbnails
 pub fn build_row(entry: &ClipEntry) -> ListBoxRow {
     let row = ListBoxRow::new();
     row.set_focusable(false);
+    if is_compact() {
+        row.add_css_class("clip-row-compact");
+    }
+    let size = row_icon_size();
 
     // Store the entry ID as widget name for later thumbnail updates
     row.set_widget_name(&entry.id);
@@ -20,52 +147,13 @@ pub fn build_row(entry: &ClipEntry) -> ListBoxRow {
 
     // Thumbnail/icon container
     let thumb_container = GtkBox::new(Orientation::Vertical, 0);
-    thumb_container.set_size_request(48, 48);
+    thumb_container.set_size_request(size, size);
     thumb_container.set_valign(Align::Center);
     thumb_container.set_halign(Align::Center);
     // Mark container for easy lookup
     thumb_container.set_widget_name("thumb_container");
 
-    if let Some(ref path) = entry.thumb_path {
-        // Has cached thumbnail - show it
-        let pic = Picture::for_filename(path.to_str().unwrap_or(""));
-        pic.set_size_request(48, 48);
-        pic.add_css_class("clip-thumb");
-        let frame = gtk4::Frame::new(None);
-        frame.set_child(Some(&pic));
-        frame.add_css_class("clip-thumb-frame");
-        frame.set_size_request(48, 48);
-        thumb_container.append(&frame);
-    } else if entry.is_image {
-        // Image without thumbnail - show loading placeholder
-        let ib = GtkBox::new(Orientation::Vertical, 0);
-        ib.set_size_request(48, 48);
-        ib.set_valign(Align::Center);
-        ib.set_halign(Align::Center);
-        ib.add_css_class("clip-text-icon");
-        ib.add_css_class("clip-thumb-loading");
-        let lbl = Label::new(Some("...")); // Loading indicator
-        lbl.add_css_class("clip-text-icon-label");
-        lbl.set_valign(Align::Center);
-        lbl.set_halign(Align::Center);
-        lbl.set_vexpand(true);
-        ib.append(&lbl);
-        thumb_container.append(&ib);
-    } else {
-        // Text entry - show T icon
-        let ib = GtkBox::new(Orientation::Vertical, 0);
-        ib.set_size_request(48, 48);
-        ib.set_valign(Align::Center);
-        ib.set_halign(Align::Center);
-        ib.add_css_class("clip-text-icon");
-        let lbl = Label::new(Some("T"));
-        lbl.add_css_class("clip-text-icon-label");
-        lbl.set_valign(Align::Center);
-        lbl.set_halign(Align::Center);
-        lbl.set_vexpand(true);
-        ib.append(&lbl);
-        thumb_container.append(&ib);
-    }
+    fill_thumb(&thumb_container, entry, size, size);
 
     hbox.append(&thumb_container);
 
@@ -73,22 +161,30 @@ pub fn build_row(entry: &ClipEntry) -> ListBoxRow {
     content.set_hexpand(true);
     content.set_valign(Align::Center);
 
-    let ctype = content_type(entry);
-    let title_text = if entry.is_image {
-        "Image".to_string()
-    } else {
-        char_truncate(&entry.preview, MAX_TEXT_PREVIEW)
-    };
-
-    let title = Label::new(Some(&title_text));
+    let title = Label::new(Some(&title_text(entry)));
     title.set_xalign(0.0);
     title.set_ellipsize(gtk4::pango::EllipsizeMode::End);
     title.set_max_width_chars(45);
     title.add_css_class("clip-title");
     content.append(&title);
 
-    let sub_text = if entry.is_image {
+    let sub_text = if is_compact() {
+        String::new()
+    } else if entry.is_image() {
         parse_image_meta(&entry.preview).unwrap_or_default()
+    } else if entry.kind == ContentKind::OtherBinary {
+        binary_meta(&entry.preview)
+    } else if entry.masked {
+        String::new()
+    } else if let Some(size) = entry.large_size {
+        trf("(large, {})", &[&human_size(size as u64)])
+    } else if !entry.lines.is_empty() {
+        // The second line, not a repeat of the title
+        entry
+            .lines
+            .get(1)
+            .map(|l| char_truncate(l, MAX_SUB_PREVIEW))
+            .unwrap_or_default()
     } else {
         char_truncate(&entry.preview, MAX_SUB_PREVIEW)
     };
@@ -104,73 +200,197 @@ pub fn build_row(entry: &ClipEntry) -> ListBoxRow {
 
     hbox.append(&content);
 
-    let right = GtkBox::new(Orientation::Vertical, 2);
-    right.set_valign(Align::Start);
+    // Compact rows put the badge and age side by side
+    let right = if is_compact() {
+        GtkBox::new(Orientation::Horizontal, 6)
+    } else {
+        GtkBox::new(Orientation::Vertical, 2)
+    };
+    right.set_valign(if is_compact() {
+        Align::Center
+    } else {
+        Align::Start
+    });
     right.set_halign(Align::End);
-    right.set_margin_top(2);
-    let badge = Label::new(Some(ctype));
-    badge.set_halign(Align::End);
-    badge.add_css_class("clip-badge");
-    right.append(&badge);
+    if !is_compact() {
+        right.set_margin_top(2);
+    }
+    if let Some(text) = badge_text(entry) {
+        let badge = Label::new(Some(&text));
+        badge.set_halign(Align::End);
+        badge.add_css_class("clip-badge");
+        right.append(&badge);
+    }
+    let time = Label::new(Some(&relative_age(entry.first_seen)));
+    time.set_halign(Align::End);
+    time.add_css_class("clip-time");
+    right.append(&time);
     hbox.append(&right);
 
     row.set_child(Some(&hbox));
+    set_tooltip(&row, entry);
+    attach_drag_source(&row, entry);
     row
 }
 
-/// Update a row's thumbnail after async generation
-pub fn update_row_thumbnail(listbox: &ListBox, id: &str, path: &PathBuf) {
-    // Find the row by ID
-    let mut idx = 0;
-    while let Some(row) = listbox.row_at_index(idx) {
-        if row.widget_name() == id {
-            // Found the row - update its thumbnail
-            if let Some(hbox) = row.child().and_then(|c| c.downcast::<GtkBox>().ok()) {
-                if let Some(container) = hbox.first_child() {
-                    if let Ok(container) = container.downcast::<GtkBox>() {
-                        if container.widget_name() == "thumb_container" {
-                            // Remove old content
-                            while let Some(child) = container.first_child() {
-                                container.remove(&child);
-                            }
-
-                            // Add new thumbnail
-                            let pic = Picture::for_filename(path.to_str().unwrap_or(""));
-                            pic.set_size_request(48, 48);
-                            pic.add_css_class("clip-thumb");
-                            let frame = gtk4::Frame::new(None);
-                            frame.set_child(Some(&pic));
-                            frame.add_css_class("clip-thumb-frame");
-                            frame.set_size_request(48, 48);
-                            container.append(&frame);
-                        }
-                    }
-                }
-            }
-            break;
-        }
-        idx += 1;
+fn title_text(entry: &ClipEntry) -> String {
+    if entry.is_image() {
+        "Image".to_string()
+    } else if entry.kind == ContentKind::OtherBinary {
+        "Binary data".to_string()
+    } else if entry.masked {
+        MASK.to_string()
+    } else if let Some(first) = entry.lines.first() {
+        char_truncate(first, MAX_TEXT_PREVIEW)
+    } else {
+        char_truncate(&entry.preview, MAX_TEXT_PREVIEW)
     }
 }
 
-pub fn populate_list(listbox: &ListBox, entries: &[ClipEntry], query: &str) -> usize {
-    while let Some(row) = listbox.row_at_index(0) {
-        listbox.remove(&row);
+/// The badge for `entry`, or None when badges are off or its label is empty
+fn badge_text(entry: &ClipEntry) -> Option<String> {
+    let badges = BADGES.read().ok()?;
+    let labels = badges.as_ref()?;
+    let text = if entry.line_count > 1 && !entry.kind.is_binary() {
+        trf(&labels.lines, &[&entry.line_count])
+    } else {
+        let label = match entry.kind {
+            ContentKind::Text => &labels.text,
+            ContentKind::Url => &labels.url,
+            ContentKind::Image => &labels.image,
+            ContentKind::OtherBinary => &labels.binary,
+        };
+        tr(label).to_string()
+    };
+    if text.is_empty() {
+        None
+    } else if entry.is_image() && entry.thumb_path.is_none() && magick_command().is_none() {
+        Some(format!("{} {}", text, tr("(no preview)")))
+    } else {
+        Some(text)
     }
+}
 
-    let q = query.to_lowercase();
-    let mut count = 0;
+/// Build a grid card (`layout = grid`): the thumbnail, or the text itself
+/// for text entries, over a title line with the badge and age
+pub fn build_card(entry: &ClipEntry) -> FlowBoxChild {
+    let child = FlowBoxChild::new();
+    child.set_focusable(false);
+    child.set_widget_name(&entry.id);
 
-    for e in entries {
-        if q.is_empty() || e.preview.to_lowercase().contains(&q) {
-            listbox.append(&build_row(e));
-            count += 1;
-        }
+    let card = GtkBox::new(Orientation::Vertical, 6);
+    card.add_css_class("clip-card");
+    card.set_size_request(CARD_WIDTH, -1);
+
+    // First child, as in rows, so update_row_thumbnail finds it
+    let thumb_container = GtkBox::new(Orientation::Vertical, 0);
+    thumb_container.set_widget_name("thumb_container");
+    thumb_container.set_size_request(CARD_WIDTH, CARD_THUMB_HEIGHT);
+    if entry.kind == ContentKind::Text || entry.kind == ContentKind::Url || entry.masked {
+        let text = if entry.masked {
+            MASK.to_string()
+        } else if !entry.lines.is_empty() {
+            entry.lines.join("\n")
+        } else {
+            entry.preview.trim().to_string()
+        };
+        let body = Label::new(Some(&text));
+        body.set_xalign(0.0);
+        body.set_yalign(0.0);
+        body.set_wrap(true);
+        body.set_wrap_mode(gtk4::pango::WrapMode::WordChar);
+        body.set_lines(CARD_TEXT_LINES);
+        body.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+        body.set_max_width_chars(1);
+        body.set_hexpand(true);
+        body.set_vexpand(true);
+        body.add_css_class("clip-title");
+        body.add_css_class("clip-card-text");
+        thumb_container.append(&body);
+    } else {
+        fill_thumb(&thumb_container, entry, CARD_WIDTH, CARD_THUMB_HEIGHT);
     }
+    card.append(&thumb_container);
 
-    if let Some(first) = listbox.row_at_index(0) {
-        listbox.select_row(Some(&first));
+    let footer = GtkBox::new(Orientation::Horizontal, 6);
+    let title_text = if entry.is_image() {
+        parse_image_meta(&entry.preview).unwrap_or_else(|| title_text(entry))
+    } else if entry.kind == ContentKind::OtherBinary {
+        binary_meta(&entry.preview)
+    } else {
+        relative_age(entry.first_seen)
+    };
+    let title = Label::new(Some(&title_text));
+    title.set_xalign(0.0);
+    title.set_hexpand(true);
+    title.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+    title.set_max_width_chars(1);
+    title.add_css_class("clip-subtitle");
+    footer.append(&title);
+    if let Some(text) = badge_text(entry) {
+        let badge = Label::new(Some(&text));
+        badge.add_css_class("clip-badge");
+        footer.append(&badge);
+    }
+    card.append(&footer);
+
+    child.set_child(Some(&card));
+    set_tooltip(&child, entry);
+    attach_drag_source(&child, entry);
+    child
+}
+
+/// What's inside cliphist's `[[ binary data ... ]]`, e.g. "12 KiB", or the
+/// cleaned-up text of a preview that wasn't valid UTF-8
+fn binary_meta(preview: &str) -> String {
+    let t = preview.trim();
+    match t.strip_prefix("[[ binary data") {
+        Some(meta) => meta.trim_end_matches("]]").trim().to_string(),
+        // Text that isn't valid UTF-8
+        None => format!(
+            "not UTF-8 · {}",
+            char_truncate(&printable(t), MAX_SUB_PREVIEW)
+        ),
     }
+}
+
+/// Update a row's (or card's) thumbnail after async generation
+pub fn update_row_thumbnail(view: &EntryView, id: &str, path: &Path) {
+    let Some(item) = view
+        .all_items()
+        .into_iter()
+        .find(|item| item.widget_name() == id)
+    else {
+        return;
+    };
+    // item -> row box -> thumb_container
+    let Some(container) = item
+        .first_child()
+        .and_then(|b| b.first_child())
+        .and_then(|c| c.downcast::<GtkBox>().ok())
+        .filter(|c| c.widget_name() == "thumb_container")
+    else {
+        return;
+    };
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    let (width, height) = view.thumb_size();
+    container.append(&thumb_frame(path, width, height));
+}
+
+pub fn populate_list(
+    view: &EntryView,
+    entries: &[ClipEntry],
+    query: &str,
+    initial: InitialSelection,
+    smartcase: bool,
+) -> usize {
+    let count = view.show(entries, |e| {
+        query.is_empty() || contains_query(&e.preview, query, smartcase)
+    });
+
+    view.apply_initial_selection(initial, query);
 
     count
 }
@@ -0,0 +1,412 @@
+use crate::config::Layout;
+use crate::entries::ClipEntry;
+use crate::ui::{build_card, build_row, CARD_THUMB_HEIGHT, CARD_WIDTH};
+use common::config::{Easing, InitialSelection};
+use common::css::{row_icon_size, scroll_to_widget};
+use common::nav::{grid_target, nav_target, rows_per_page, Nav, NavOptions};
+use gtk4::prelude::*;
+use gtk4::{FlowBox, ListBox, ScrolledWindow, Widget};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Clone)]
+enum ViewWidget {
+    List(ListBox),
+    Grid(FlowBox),
+}
+
+/// Which built items the current query shows. Rows are built once per
+/// entry list and filtered in place, so typing doesn't rebuild them.
+#[derive(Default)]
+struct FilterState {
+    /// Id and mask state of each built item, to tell when the list changed
+    built: Vec<(String, bool)>,
+    /// Raw indices of the items that pass the filter, in order
+    visible: Vec<i32>,
+}
+
+impl FilterState {
+    fn entry(&self, index: i32, entries: &[ClipEntry]) -> Option<ClipEntry> {
+        let raw = *self.visible.get(usize::try_from(index).ok()?)? as usize;
+        let (id, _) = self.built.get(raw)?;
+        entries
+            .get(raw)
+            .filter(|e| e.id == *id)
+            .or_else(|| entries.iter().find(|e| e.id == *id))
+            .cloned()
+    }
+}
+
+/// The entry list: rows in a `ListBox`, or cards in a `FlowBox` under
+/// `layout = grid`. Items are addressed by their index among the visible
+/// ones, matching `get_filtered_entry`.
+#[derive(Clone)]
+pub struct EntryView {
+    widget: ViewWidget,
+    state: Rc<RefCell<FilterState>>,
+}
+
+impl EntryView {
+    pub fn new(layout: Layout) -> Self {
+        let state = Rc::new(RefCell::new(FilterState::default()));
+        let widget = match layout {
+            Layout::List => {
+                let listbox = ListBox::new();
+                listbox.add_css_class("clip-list");
+                listbox.set_selection_mode(gtk4::SelectionMode::Single);
+                let st = state.clone();
+                listbox.set_filter_func(move |row| {
+                    st.borrow().visible.binary_search(&row.index()).is_ok()
+                });
+                ViewWidget::List(listbox)
+            }
+            Layout::Grid => {
+                let flowbox = FlowBox::new();
+                flowbox.add_css_class("clip-grid");
+                flowbox.set_selection_mode(gtk4::SelectionMode::Single);
+                flowbox.set_activate_on_single_click(true);
+                flowbox.set_homogeneous(true);
+                flowbox.set_valign(gtk4::Align::Start);
+                flowbox.set_min_children_per_line(1);
+                flowbox.set_max_children_per_line(12);
+                let st = state.clone();
+                flowbox.set_filter_func(move |child| {
+                    st.borrow().visible.binary_search(&child.index()).is_ok()
+                });
+                ViewWidget::Grid(flowbox)
+            }
+        };
+        EntryView { widget, state }
+    }
+
+    pub fn is_grid(&self) -> bool {
+        matches!(self.widget, ViewWidget::Grid(_))
+    }
+
+    pub fn widget(&self) -> Widget {
+        match &self.widget {
+            ViewWidget::List(l) => l.clone().upcast(),
+            ViewWidget::Grid(g) => g.clone().upcast(),
+        }
+    }
+
+    /// Number of visible items
+    pub fn len(&self) -> i32 {
+        self.state.borrow().visible.len() as i32
+    }
+
+    /// Built item at `raw` index, visible or not
+    fn raw_item(&self, raw: i32) -> Option<Widget> {
+        match &self.widget {
+            ViewWidget::List(l) => l.row_at_index(raw).map(|r| r.upcast()),
+            ViewWidget::Grid(g) => g.child_at_index(raw).map(|c| c.upcast()),
+        }
+    }
+
+    /// The entry the visible item at `index` was built from, looked up by id
+    /// in `entries` (the list last passed to `show`), so it is always the
+    /// entry the row shows rather than one recomputed from the query
+    pub fn entry(&self, index: i32, entries: &[ClipEntry]) -> Option<ClipEntry> {
+        self.state.borrow().entry(index, entries)
+    }
+
+    /// The entry behind the selected item
+    pub fn selected_entry(&self, entries: &[ClipEntry]) -> Option<ClipEntry> {
+        self.entry(self.selected_index()?, entries)
+    }
+
+    /// Visible index of the item built from the entry with `id`
+    pub fn index_of(&self, id: &str) -> Option<i32> {
+        let state = self.state.borrow();
+        state
+            .visible
+            .iter()
+            .position(|&raw| state.built.get(raw as usize).is_some_and(|(b, _)| b == id))
+            .map(|i| i as i32)
+    }
+
+    /// Every built item, including ones the query hides
+    pub fn all_items(&self) -> Vec<Widget> {
+        (0..self.state.borrow().built.len() as i32)
+            .filter_map(|i| self.raw_item(i))
+            .collect()
+    }
+
+    /// Visible index of the item at `raw`, if it is visible
+    fn to_visible(&self, raw: i32) -> Option<i32> {
+        self.state
+            .borrow()
+            .visible
+            .binary_search(&raw)
+            .ok()
+            .map(|i| i as i32)
+    }
+
+    /// The visible item at `index`
+    pub fn item(&self, index: i32) -> Option<Widget> {
+        let raw = *self
+            .state
+            .borrow()
+            .visible
+            .get(usize::try_from(index).ok()?)?;
+        self.raw_item(raw)
+    }
+
+    pub fn selected(&self) -> Option<Widget> {
+        match &self.widget {
+            ViewWidget::List(l) => l.selected_row().map(|r| r.upcast()),
+            ViewWidget::Grid(g) => g.selected_children().first().map(|c| c.clone().upcast()),
+        }
+    }
+
+    pub fn selected_index(&self) -> Option<i32> {
+        let raw = match &self.widget {
+            ViewWidget::List(l) => l.selected_row().map(|r| r.index()),
+            ViewWidget::Grid(g) => g.selected_children().first().map(|c| c.index()),
+        }?;
+        self.to_visible(raw)
+    }
+
+    /// Select the visible item at `index`; false if there is none
+    pub fn select(&self, index: i32) -> bool {
+        let Some(item) = self.item(index) else {
+            return false;
+        };
+        match &self.widget {
+            ViewWidget::List(l) => l.select_row(item.downcast_ref::<gtk4::ListBoxRow>()),
+            ViewWidget::Grid(g) => {
+                if let Some(child) = item.downcast_ref::<gtk4::FlowBoxChild>() {
+                    g.select_child(child);
+                }
+            }
+        }
+        true
+    }
+
+    pub fn unselect(&self) {
+        match &self.widget {
+            ViewWidget::List(l) => l.select_row(None::<&gtk4::ListBoxRow>),
+            ViewWidget::Grid(g) => g.unselect_all(),
+        }
+    }
+
+    /// Drop every built item, so the next `show` rebuilds them
+    pub fn clear(&self) {
+        match &self.widget {
+            ViewWidget::List(l) => {
+                while let Some(row) = l.row_at_index(0) {
+                    l.remove(&row);
+                }
+            }
+            ViewWidget::Grid(g) => {
+                while let Some(child) = g.child_at_index(0) {
+                    g.remove(&child);
+                }
+            }
+        }
+        *self.state.borrow_mut() = FilterState::default();
+    }
+
+    /// Show the entries `keep` accepts. Items are only built when `entries`
+    /// differs from what was shown last; otherwise the existing ones are
+    /// filtered. Returns how many are visible.
+    pub fn show(&self, entries: &[ClipEntry], keep: impl Fn(&ClipEntry) -> bool) -> usize {
+        let same = {
+            let state = self.state.borrow();
+            state.built.len() == entries.len()
+                && state
+                    .built
+                    .iter()
+                    .zip(entries)
+                    .all(|((id, masked), e)| *id == e.id && *masked == e.masked)
+        };
+        if !same {
+            self.clear();
+            for e in entries {
+                match &self.widget {
+                    ViewWidget::List(l) => l.append(&build_row(e)),
+                    ViewWidget::Grid(g) => g.insert(&build_card(e), -1),
+                }
+            }
+            self.state.borrow_mut().built =
+                entries.iter().map(|e| (e.id.clone(), e.masked)).collect();
+        }
+
+        let visible: Vec<i32> = (0..entries.len() as i32)
+            .filter(|&i| keep(&entries[i as usize]))
+            .collect();
+        let count = visible.len();
+        self.state.borrow_mut().visible = visible;
+        match &self.widget {
+            ViewWidget::List(l) => l.invalidate_filter(),
+            ViewWidget::Grid(g) => g.invalidate_filter(),
+        }
+        count
+    }
+
+    /// Visible index of the item under a point in the view's coordinates
+    pub fn index_at(&self, x: f64, y: f64) -> Option<i32> {
+        let raw = match &self.widget {
+            ViewWidget::List(l) => l.row_at_y(y as i32).map(|r| r.index()),
+            ViewWidget::Grid(g) => g.child_at_pos(x as i32, y as i32).map(|c| c.index()),
+        }?;
+        self.to_visible(raw)
+    }
+
+    /// Size new thumbnails are drawn at
+    pub fn thumb_size(&self) -> (i32, i32) {
+        match self.widget {
+            ViewWidget::List(_) => (row_icon_size(), row_icon_size()),
+            ViewWidget::Grid(_) => (CARD_WIDTH, CARD_THUMB_HEIGHT),
+        }
+    }
+
+    /// Cards per grid row, counted from the first row as laid out; 1 for lists
+    fn columns(&self) -> i32 {
+        if !self.is_grid() {
+            return 1;
+        }
+        let Some(first_y) = self.item(0).map(|c| c.allocation().y()) else {
+            return 1;
+        };
+        let mut cols = 1;
+        while self
+            .item(cols)
+            .is_some_and(|c| c.allocation().y() == first_y)
+        {
+            cols += 1;
+        }
+        cols
+    }
+
+    /// Connect `f` to selection changes
+    pub fn connect_selection_changed<F: Fn() + 'static>(&self, f: F) {
+        match &self.widget {
+            ViewWidget::List(l) => {
+                l.connect_row_selected(move |_, _| f());
+            }
+            ViewWidget::Grid(g) => {
+                g.connect_selected_children_changed(move |_| f());
+            }
+        }
+    }
+
+    /// Connect `f` to clicks (or Enter from GTK's own handling) on an item,
+    /// called with its visible index
+    pub fn connect_activated<F: Fn(i32) + 'static>(&self, f: F) {
+        let view = self.clone();
+        match &self.widget {
+            ViewWidget::List(l) => {
+                l.connect_row_activated(move |_, row| {
+                    if let Some(i) = view.to_visible(row.index()) {
+                        f(i);
+                    }
+                });
+            }
+            ViewWidget::Grid(g) => {
+                g.connect_child_activated(move |_, child| {
+                    if let Some(i) = view.to_visible(child.index()) {
+                        f(i);
+                    }
+                });
+            }
+        }
+    }
+
+    pub fn scroll_to_selected(&self, scroll: &ScrolledWindow, duration_ms: u64, easing: Easing) {
+        if let Some(item) = self.selected() {
+            scroll_to_widget(&item, scroll, duration_ms, easing);
+        }
+    }
+
+    /// Move the selection and scroll to it, in two dimensions for grids.
+    /// With nothing selected, any move except Last lands on the first item.
+    pub fn navigate(&self, scroll: &ScrolledWindow, nav: Nav, opts: &NavOptions) {
+        let (current, nav) = match self.selected_index() {
+            Some(i) => (i, nav),
+            None if nav == Nav::Last => (0, Nav::Last),
+            None => (0, Nav::First),
+        };
+        let n = self.len();
+        let page = match nav {
+            Nav::PageDown | Nav::PageUp | Nav::HalfPageDown | Nav::HalfPageUp => {
+                let height = self
+                    .selected()
+                    .or_else(|| self.item(0))
+                    .map(|w| w.height())
+                    .unwrap_or(0);
+                rows_per_page(height, scroll, opts.page_step)
+            }
+            _ => 0,
+        };
+        let target = match self.widget {
+            ViewWidget::List(_) => nav_target(current, n, nav, page, opts.wrap),
+            ViewWidget::Grid(_) => grid_target(current, n, self.columns(), nav, page, opts.wrap),
+        };
+        if target.is_some_and(|t| self.select(t)) {
+            self.scroll_to_selected(scroll, opts.duration_ms, opts.easing);
+        }
+    }
+
+    /// Reselect a remembered item if the view still has it
+    pub fn restore_selection(&self, selected: Option<i32>) {
+        if let Some(i) = selected {
+            self.select(i);
+        }
+    }
+
+    /// Select the starting item for a freshly filtered view. `Last` reuses the
+    /// one selected when the window last hid, but only for an unfiltered list.
+    pub fn apply_initial_selection(&self, initial: InitialSelection, query: &str) {
+        let index = match initial {
+            InitialSelection::None => {
+                self.unselect();
+                return;
+            }
+            InitialSelection::Last if query.is_empty() => common::session::last_selected()
+                .filter(|&i| i < self.len())
+                .unwrap_or(0),
+            _ => 0,
+        };
+        self.select(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entries::ContentKind;
+
+    fn clip(id: &str) -> ClipEntry {
+        ClipEntry {
+            raw_line: format!("{}\t{}", id, id),
+            id: id.to_string(),
+            preview: id.to_string(),
+            kind: ContentKind::Text,
+            thumb_path: None,
+            first_seen: 0,
+            masked: false,
+            line_count: 1,
+            lines: Vec::new(),
+            large_size: None,
+        }
+    }
+
+    #[test]
+    fn entry_is_the_one_the_row_was_built_from() {
+        let state = FilterState {
+            built: ["1", "2", "3"]
+                .iter()
+                .map(|id| (id.to_string(), false))
+                .collect(),
+            visible: vec![0, 2],
+        };
+        let entries = vec![clip("1"), clip("2"), clip("3")];
+        assert_eq!(state.entry(1, &entries).map(|e| e.id), Some("3".into()));
+        // A new entry arrived on top before the rows were rebuilt
+        let shifted = vec![clip("4"), clip("1"), clip("2"), clip("3")];
+        assert_eq!(state.entry(0, &shifted).map(|e| e.id), Some("1".into()));
+        assert_eq!(state.entry(1, &shifted).map(|e| e.id), Some("3".into()));
+        assert!(state.entry(2, &shifted).is_none());
+    }
+}
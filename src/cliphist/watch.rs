@@ -0,0 +1,57 @@
+use crate::backend::backend;
+use crate::config::APP_NAME;
+use common::logging::log;
+use gio::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+/// How long to wait for a burst of writes to settle before refetching
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+thread_local! {
+    /// Kept alive for as long as the daemon runs; dropping it stops the watch
+    static MONITOR: RefCell<Option<gio::FileMonitor>> = const { RefCell::new(None) };
+}
+
+/// Call `on_change` whenever the backend's store changes (`live_refresh`),
+/// at most once per `DEBOUNCE`. The watch is on the path rather than the
+/// open file, so it survives the store being replaced or recreated.
+pub fn watch_store(on_change: impl Fn() + 'static) {
+    let path = backend().store_path();
+    let monitor = match gio::File::for_path(&path)
+        .monitor_file(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+    {
+        Ok(m) => m,
+        Err(e) => {
+            log(
+                APP_NAME,
+                &format!("live_refresh: can't watch {}: {}", path.display(), e),
+            );
+            return;
+        }
+    };
+    log(
+        APP_NAME,
+        &format!("live_refresh: watching {}", path.display()),
+    );
+
+    let pending = Rc::new(Cell::new(false));
+    let on_change = Rc::new(on_change);
+    monitor.connect_changed(move |_, _, _, event| {
+        use gio::FileMonitorEvent as E;
+        if !matches!(
+            event,
+            E::Changed | E::ChangesDoneHint | E::Created | E::Deleted | E::MovedIn | E::Renamed
+        ) || pending.replace(true)
+        {
+            return;
+        }
+        let (pending, on_change) = (pending.clone(), on_change.clone());
+        glib::timeout_add_local_once(DEBOUNCE, move || {
+            pending.set(false);
+            on_change();
+        });
+    });
+    MONITOR.with(|m| *m.borrow_mut() = Some(monitor));
+}
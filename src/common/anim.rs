@@ -0,0 +1,192 @@
+use crate::config::Easing;
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Widget};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ANIMATIONS: AtomicBool = AtomicBool::new(true);
+
+thread_local! {
+    pub static EXPANDED: RefCell<bool> = const { RefCell::new(false) };
+    pub static ANIMATION_GEN: RefCell<u64> = const { RefCell::new(0) };
+}
+
+/// Master switch for every animation (`[behavior] animations`)
+pub fn set_animations(on: bool) {
+    ANIMATIONS.store(on, Ordering::Relaxed);
+}
+
+/// Whether anything may animate: off when the config says so or GTK's
+/// `gtk-enable-animations` (the desktop's reduced-motion setting) is false
+pub fn animations_enabled() -> bool {
+    ANIMATIONS.load(Ordering::Relaxed)
+        && gtk4::Settings::default().is_none_or(|s| s.is_gtk_enable_animations())
+}
+
+/// `duration_ms`, or 0 (instant) while animations are disabled
+pub fn duration(duration_ms: u64) -> u64 {
+    if animations_enabled() {
+        duration_ms
+    } else {
+        0
+    }
+}
+
+pub fn set_expanded(expanded: bool) {
+    EXPANDED.with(|e| *e.borrow_mut() = expanded);
+}
+
+pub fn is_expanded() -> bool {
+    EXPANDED.with(|e| *e.borrow())
+}
+
+fn next_animation_gen() -> u64 {
+    ANIMATION_GEN.with(|g| {
+        let mut gen = g.borrow_mut();
+        *gen = gen.wrapping_add(1);
+        *gen
+    })
+}
+
+fn current_animation_gen() -> u64 {
+    ANIMATION_GEN.with(|g| *g.borrow())
+}
+
+/// Animate height transition
+/// `hideable` widgets are shown before expanding and hidden once collapsed
+pub fn animate_height(
+    container: &GtkBox,
+    hideable: &[Widget],
+    from_height: i32,
+    to_height: i32,
+    duration_ms: u64,
+    easing: Easing,
+    expanding: bool,
+) {
+    // Get a new generation for this animation - any previous animation callbacks
+    // will see their generation is stale and stop
+    let gen = next_animation_gen();
+
+    let duration_ms = duration(duration_ms);
+    let steps = 20;
+    let step_ms = duration_ms / steps;
+
+    // Update CSS classes immediately
+    if expanding {
+        container.remove_css_class("collapsed");
+        container.add_css_class("expanded");
+        for w in hideable {
+            w.set_visible(true);
+        }
+    } else {
+        container.remove_css_class("expanded");
+        container.add_css_class("collapsed");
+    }
+
+    let container = container.clone();
+    let hideable = hideable.to_vec();
+    let step = Rc::new(std::cell::Cell::new(0u64));
+    let step_clone = step.clone();
+
+    let width = container.width();
+
+    if duration_ms == 0 {
+        container.set_size_request(width, to_height);
+        if !expanding {
+            for w in &hideable {
+                w.set_visible(false);
+            }
+        }
+        return;
+    }
+
+    glib::timeout_add_local(std::time::Duration::from_millis(step_ms), move || {
+        // Check if this animation is still current - if not, stop it
+        if current_animation_gen() != gen {
+            return glib::ControlFlow::Break;
+        }
+
+        let s = step_clone.get() + 1;
+        step_clone.set(s);
+
+        let t = s as f64 / steps as f64;
+        let eased = easing.apply(t);
+        let current = from_height as f64 + (to_height - from_height) as f64 * eased;
+
+        container.set_size_request(width, current as i32);
+
+        if s >= steps {
+            container.set_size_request(width, to_height);
+
+            // Hide elements after collapse animation completes
+            // Only do this if we're still the current animation
+            if !expanding && current_animation_gen() == gen {
+                for w in &hideable {
+                    w.set_visible(false);
+                }
+            }
+
+            glib::ControlFlow::Break
+        } else {
+            glib::ControlFlow::Continue
+        }
+    });
+}
+
+/// Expand from `collapsed_height` to `height` unless already expanded
+pub fn expand(
+    container: &GtkBox,
+    hideable: &[Widget],
+    collapsed_height: i32,
+    height: i32,
+    duration_ms: u64,
+    easing: Easing,
+) {
+    if is_expanded() {
+        return;
+    }
+    set_expanded(true);
+    animate_height(
+        container,
+        hideable,
+        collapsed_height,
+        height,
+        duration_ms,
+        easing,
+        true,
+    );
+}
+
+/// Collapse from `height` to `collapsed_height` unless already collapsed
+pub fn collapse(
+    container: &GtkBox,
+    hideable: &[Widget],
+    collapsed_height: i32,
+    height: i32,
+    duration_ms: u64,
+    easing: Easing,
+) {
+    if !is_expanded() {
+        return;
+    }
+    set_expanded(false);
+    animate_height(
+        container,
+        hideable,
+        height,
+        collapsed_height,
+        duration_ms,
+        easing,
+        false,
+    );
+}
+
+/// Snap straight to the collapsed layout without animating
+pub fn reset_collapsed(container: &GtkBox, hideable: &[Widget], width: i32, collapsed_height: i32) {
+    set_expanded(false);
+    container.set_size_request(width, collapsed_height);
+    for w in hideable {
+        w.set_visible(false);
+    }
+}
@@ -0,0 +1,31 @@
+// Embeds every src/themes/*.css as a built-in theme, named after the file
+use std::fmt::Write;
+use std::path::Path;
+
+fn main() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../themes");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut themes: Vec<(String, String)> = std::fs::read_dir(&dir)
+        .expect("src/themes missing")
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "css"))
+        .filter_map(|p| {
+            let name = p.file_stem()?.to_str()?.to_string();
+            let path = p.canonicalize().ok()?.to_str()?.to_string();
+            Some((name, path))
+        })
+        .collect();
+    themes.sort();
+
+    let mut out = String::from("const BUILTIN_THEMES: &[(&str, &str)] = &[\n");
+    for (name, path) in &themes {
+        println!("cargo:rerun-if-changed={}", path);
+        writeln!(out, "    ({:?}, include_str!({:?})),", name, path).unwrap();
+    }
+    out.push_str("];\n");
+
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    std::fs::write(Path::new(&out_dir).join("themes.rs"), out).unwrap();
+}
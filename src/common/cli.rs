@@ -1,12 +1,92 @@
-use crate::paths::config_dir;
+use crate::i18n::tr;
+use crate::paths::{config_dir, runtime_dir};
+use std::io::Write;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-/// Check if a process is running and return its PID
+static ONESHOT: AtomicBool = AtomicBool::new(false);
+
+/// `--oneshot`: no pidfile, and the process exits once the window closes
+pub fn set_oneshot(oneshot: bool) {
+    ONESHOT.store(oneshot, Ordering::Relaxed);
+}
+
+pub fn is_oneshot() -> bool {
+    ONESHOT.load(Ordering::Relaxed)
+}
+
+/// Whether `pid` runs the same binary as us, so a recycled PID from a
+/// stale pidfile isn't mistaken for the daemon. Assumes yes without /proc.
+fn is_our_process(pid: i32) -> bool {
+    let Some(exe_name) = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+    else {
+        return true;
+    };
+    let Ok(comm) = std::fs::read_to_string(format!("/proc/{}/comm", pid)) else {
+        return true;
+    };
+    // The kernel truncates comm to 15 bytes
+    let expected = &exe_name.as_bytes()[..exe_name.len().min(15)];
+    if comm.trim_end().as_bytes() == expected {
+        return true;
+    }
+    std::fs::read(format!("/proc/{}/cmdline", pid))
+        .ok()
+        .and_then(|c| {
+            let argv0 = c.split(|&b| b == 0).next()?.to_vec();
+            let argv0 = String::from_utf8_lossy(&argv0).into_owned();
+            std::path::Path::new(&argv0)
+                .file_name()
+                .map(|n| n.to_string_lossy() == exe_name)
+        })
+        .unwrap_or(false)
+}
+
+/// Check if the daemon is running and return its PID
 pub fn get_pid(pidfile: &str) -> Option<i32> {
-    std::fs::read_to_string(pidfile)
+    read_private(pidfile)?
+        .trim()
+        .parse::<i32>()
         .ok()
-        .and_then(|s| s.trim().parse::<i32>().ok())
         .filter(|&pid| unsafe { libc::kill(pid, 0) } == 0)
+        .filter(|&pid| is_our_process(pid))
+}
+
+/// `--help` output: `about`, then one line per (arguments, description) in
+/// `lines`. Arguments with a leading space continue the line above.
+pub fn print_usage(app_name: &str, about: &str, lines: &[(&str, &str)]) {
+    eprintln!("{} - {}\n", app_name, tr(about));
+    eprintln!("{}", tr("Usage:"));
+    for (args, what) in lines {
+        match args.strip_prefix(' ') {
+            Some(option) => eprintln!("      {:<29}{}", option, tr(what)),
+            None => eprintln!("  {} {:<20} {}", app_name, args, tr(what)),
+        }
+    }
+}
+
+/// Print version and the git commit the binary was built from
+pub fn cmd_version(app_name: &str, version: &str, git_hash: &str) {
+    println!("{} {} ({})", app_name, version, git_hash);
+}
+
+/// `show-themes`, or with `json` a JSON array of the names
+pub fn cmd_list_themes(json: bool) {
+    let names = crate::paths::builtin_themes()
+        .into_iter()
+        .map(|(name, _)| name);
+    if json {
+        let names = names.map(crate::json::Json::str).collect();
+        println!("{}", crate::json::Json::Arr(names).pretty());
+    } else {
+        println!("{}", tr("Available themes:"));
+        for name in names {
+            println!("  {}", name);
+        }
+    }
 }
 
 /// Show config directory contents
@@ -25,6 +105,64 @@ pub fn cmd_config(app_name: &str) {
     }
 }
 
+/// `--check-config`: report malformed lines, unknown keys, bad values, bad
+/// or conflicting keybinds, broken includes and a missing theme without
+/// starting. Exits 1 if there are any.
+pub fn cmd_check_config(app_name: &str, base: &crate::config::ConfigBase) {
+    let path = config_dir(app_name).join("config");
+    let shared = crate::config::shared_config_path();
+    if !path.exists() && !shared.exists() {
+        println!("No config at {}, using defaults", path.display());
+        return;
+    }
+    let content = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut problems = crate::config::config_syntax_errors(&content);
+    if let Ok(shared_content) = std::fs::read_to_string(&shared) {
+        problems.extend(
+            crate::config::config_syntax_errors(&shared_content)
+                .into_iter()
+                .map(|p| format!("{} (in {})", p, shared.display())),
+        );
+    }
+    problems.extend(
+        base.unknown_keys
+            .iter()
+            .map(|k| format!("unknown key {}", k)),
+    );
+    problems.extend(
+        base.bad_keybinds
+            .iter()
+            .map(|k| format!("bad keybind {}", k)),
+    );
+    problems.extend(base.bad_values.iter().map(|v| format!("bad value {}", v)));
+    problems.extend(
+        crate::keys::keybind_conflicts(&base.keybinds)
+            .into_iter()
+            .map(|c| format!("keybind conflict {}", c)),
+    );
+    problems.extend(base.include_errors.iter().cloned());
+    let theme = &base.theme;
+    let is_name = !theme.contains('/') && !theme.ends_with(".css");
+    if is_name && crate::paths::get_theme_css(theme).is_none() && theme != "default" {
+        problems.push(format!("unknown theme '{}'", theme));
+    } else if !is_name
+        && !std::path::Path::new(theme).is_file()
+        && *theme != config_dir(app_name).join("style.css").to_string_lossy()
+    {
+        problems.push(format!("theme file not found: {}", theme));
+    }
+
+    if problems.is_empty() {
+        println!("{}: ok", path.display());
+        return;
+    }
+    println!("{}:", path.display());
+    for p in &problems {
+        println!("  {}", p);
+    }
+    std::process::exit(1);
+}
+
 /// Generate default config files
 pub fn cmd_generate_config(app_name: &str, default_css: &str, default_config: &str) {
     let dir = config_dir(app_name);
@@ -41,6 +179,52 @@ pub fn cmd_generate_config(app_name: &str, default_css: &str, default_config: &s
     println!("Config directory: {}", dir.display());
 }
 
+/// `--edit-config`: open the config in `$EDITOR`, writing the defaults
+/// first if there is none, in this terminal or else via `in_terminal`.
+/// Without `$EDITOR` or a terminal to run it in, the file goes to `xdg-open`.
+pub fn cmd_edit_config(
+    app_name: &str,
+    default_css: &str,
+    default_config: &str,
+    in_terminal: &dyn Fn(&str) -> Option<Command>,
+) {
+    use std::io::IsTerminal;
+
+    let path = config_dir(app_name).join("config");
+    if !path.exists() {
+        cmd_generate_config(app_name, default_css, default_config);
+    }
+    let quoted = format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"));
+    let editor = std::env::var("EDITOR")
+        .ok()
+        .filter(|e| !e.trim().is_empty());
+
+    let cmd = match editor {
+        // $EDITOR may carry flags (`code -w`), so let the shell split it
+        Some(editor) if std::io::stdin().is_terminal() => {
+            match Command::new("sh")
+                .arg("-c")
+                .arg(format!("{} {}", editor, quoted))
+                .status()
+            {
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to run {}: {}", editor, e),
+            }
+            return;
+        }
+        Some(editor) => in_terminal(&format!("{} {}", editor, quoted)),
+        None => None,
+    };
+    let mut cmd = cmd.unwrap_or_else(|| {
+        let mut open = Command::new("xdg-open");
+        open.arg(&path);
+        open
+    });
+    if let Err(e) = cmd.spawn() {
+        eprintln!("Failed to open {}: {}", path.display(), e);
+    }
+}
+
 /// Reload daemon (kill existing + spawn new)
 pub fn cmd_reload(app_name: &str, pidfile: &str) {
     let exe = std::env::current_exe().expect("cannot find self");
@@ -54,16 +238,65 @@ pub fn cmd_reload(app_name: &str, pidfile: &str) {
         }
         let _ = std::fs::remove_file(pidfile);
     }
-    let _ = Command::new(&exe)
+    spawn_daemon(&exe);
+    println!("{} reloaded", app_name);
+}
+
+fn spawn_daemon(exe: &std::path::Path) {
+    let _ = Command::new(exe)
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
         .spawn();
-    println!("{} reloaded", app_name);
+}
+
+/// Pings `--restart-if-unresponsive` sends before giving up on the daemon
+const PING_ATTEMPTS: u32 = 3;
+/// How long each ping waits for its answer
+const PING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// `--restart-if-unresponsive`: ping the daemon and, if it doesn't answer
+/// any of `PING_ATTEMPTS` pings, kill it and start a new one. Starts one
+/// if none is running.
+pub fn cmd_restart_if_unresponsive(app_name: &str, pidfile: &str) {
+    let exe = std::env::current_exe().expect("cannot find self");
+    let Some(pid) = get_pid(pidfile) else {
+        spawn_daemon(&exe);
+        println!("{} was not running; started", app_name);
+        return;
+    };
+    for _ in 0..PING_ATTEMPTS {
+        match ping(app_name, pid, PING_TIMEOUT) {
+            Ok(true) => {
+                println!("{} is responding", app_name);
+                return;
+            }
+            Ok(false) => {}
+            // Can't tell, so leave it alone
+            Err(e) => {
+                eprintln!("Failed to ping {}: {}", app_name, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    unsafe { libc::kill(pid, libc::SIGKILL) };
+    for _ in 0..20 {
+        if unsafe { libc::kill(pid, 0) } != 0 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let _ = std::fs::remove_file(pidfile);
+    spawn_daemon(&exe);
+    println!(
+        "{} did not respond within {}s; restarted",
+        app_name,
+        PING_ATTEMPTS as u64 * PING_TIMEOUT.as_secs()
+    );
 }
 
 /// Write PID file
 pub fn write_pid(pidfile: &str) {
-    let _ = std::fs::write(pidfile, std::process::id().to_string());
+    let _ = write_private(pidfile, &std::process::id().to_string());
 }
 
 /// Remove PID file
@@ -71,7 +304,188 @@ pub fn remove_pid(pidfile: &str) {
     let _ = std::fs::remove_file(pidfile);
 }
 
+/// `<app>.<ext>` in `$XDG_RUNTIME_DIR`, else `/tmp/<app>-<uid>.<ext>`
+fn runtime_file(app_name: &str, ext: &str) -> String {
+    match runtime_dir() {
+        Some(dir) => dir
+            .join(format!("{}.{}", app_name, ext))
+            .to_string_lossy()
+            .into_owned(),
+        None => format!("/tmp/{}-{}.{}", app_name, unsafe { libc::getuid() }, ext),
+    }
+}
+
+/// Write `contents` to `path` as a 0600 file of ours. Won't follow a
+/// symlink or write into a file someone else created there first.
+fn write_private(path: &str, contents: &str) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .mode(0o600)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)?;
+    let meta = file.metadata()?;
+    if meta.uid() != unsafe { libc::getuid() } {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} belongs to another user", path),
+        ));
+    }
+    // The mode above only applies to new files
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.set_len(0)?;
+    file.write_all(contents.as_bytes())
+}
+
+/// Contents of `path` if it's a regular file of ours; anything else may
+/// have been planted
+fn read_private(path: &str) -> Option<String> {
+    let meta = std::fs::symlink_metadata(path).ok()?;
+    if !meta.is_file() || meta.uid() != unsafe { libc::getuid() } {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+
 /// Get pidfile path for an app
 pub fn pidfile_path(app_name: &str) -> String {
-    format!("/tmp/{}-{}.pid", app_name, unsafe { libc::getuid() })
+    runtime_file(app_name, "pid")
+}
+
+/// File a `--theme` request is written to before signalling the daemon
+pub fn theme_request_path(app_name: &str) -> String {
+    runtime_file(app_name, "theme")
+}
+
+/// Ask the running daemon to switch to `theme` (delivered with SIGUSR2)
+pub fn request_theme(app_name: &str, pid: i32, theme: &str) -> std::io::Result<()> {
+    write_private(&theme_request_path(app_name), theme)?;
+    unsafe { libc::kill(pid, libc::SIGUSR2) };
+    Ok(())
+}
+
+/// Theme name from a pending `--theme` request, consuming it
+pub fn take_theme_request(app_name: &str) -> Option<String> {
+    let path = theme_request_path(app_name);
+    let theme = read_private(&path)?;
+    let _ = std::fs::remove_file(&path);
+    Some(theme.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+/// File a `--query` is written to before signalling the daemon
+fn query_request_path(app_name: &str) -> String {
+    runtime_file(app_name, "query")
+}
+
+/// Have the window open next with `query` in its search box
+pub fn request_query(app_name: &str, query: &str) -> std::io::Result<()> {
+    write_private(&query_request_path(app_name), query)
+}
+
+/// Query from a pending `--query`, consuming it
+pub fn take_query_request(app_name: &str) -> Option<String> {
+    let path = query_request_path(app_name);
+    let query = read_private(&path)?;
+    let _ = std::fs::remove_file(&path);
+    Some(query)
+}
+
+/// The text after `--query` anywhere in `args`, else the word after `open`
+pub fn query_arg(args: &[String]) -> Option<String> {
+    if let Some(i) = args.iter().position(|a| a == "--query") {
+        return args.get(i + 1).cloned();
+    }
+    match args.get(1).map(String::as_str) {
+        Some("open") => args.get(2).filter(|a| !a.starts_with("--")).cloned(),
+        _ => None,
+    }
+}
+
+/// File a ping is written to before signalling the daemon (SIGUSR2)
+fn ping_path(app_name: &str) -> String {
+    runtime_file(app_name, "ping")
+}
+
+/// File the daemon writes the ping's token back to
+fn pong_path(app_name: &str) -> String {
+    runtime_file(app_name, "pong")
+}
+
+/// Whether the daemon at `pid` answers a ping within `timeout`. The answer
+/// comes from its main loop, so a daemon stuck in a handler won't give one.
+pub fn ping(app_name: &str, pid: i32, timeout: std::time::Duration) -> std::io::Result<bool> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let token = format!("{}-{}", std::process::id(), nanos);
+    let pong = pong_path(app_name);
+    let _ = std::fs::remove_file(&pong);
+    write_private(&ping_path(app_name), &token)?;
+    unsafe { libc::kill(pid, libc::SIGUSR2) };
+    let start = std::time::Instant::now();
+    while start.elapsed() < timeout {
+        if read_private(&pong).is_some_and(|t| t == token) {
+            let _ = std::fs::remove_file(&pong);
+            return Ok(true);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    Ok(false)
+}
+
+/// Answer a pending ping, consuming it; false if there was none. For the
+/// daemon's SIGUSR2 handler, ahead of anything else it does.
+pub fn answer_ping(app_name: &str) -> bool {
+    let path = ping_path(app_name);
+    let Some(token) = read_private(&path) else {
+        return false;
+    };
+    let _ = std::fs::remove_file(&path);
+    let _ = write_private(&pong_path(app_name), &token);
+    true
+}
+
+/// What a SIGUSR1 to the daemon asks of its window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowRequest {
+    Toggle,
+    /// Show, or focus if already shown; never hides
+    Open,
+    /// Hide; nothing if already hidden
+    Close,
+}
+
+/// File an `open` / `close` request is written to before signalling the daemon
+pub fn window_request_path(app_name: &str) -> String {
+    runtime_file(app_name, "window")
+}
+
+/// Send `request` to the running daemon (delivered with SIGUSR1). A toggle
+/// is the bare signal, so it clears any request left behind.
+pub fn request_window(app_name: &str, pid: i32, request: WindowRequest) -> std::io::Result<()> {
+    let path = window_request_path(app_name);
+    match request {
+        WindowRequest::Toggle => {
+            let _ = std::fs::remove_file(&path);
+        }
+        WindowRequest::Open => write_private(&path, "open")?,
+        WindowRequest::Close => write_private(&path, "close")?,
+    }
+    unsafe { libc::kill(pid, libc::SIGUSR1) };
+    Ok(())
+}
+
+/// The pending window request, consuming it; a toggle when there is none
+pub fn take_window_request(app_name: &str) -> WindowRequest {
+    let path = window_request_path(app_name);
+    let Some(request) = read_private(&path) else {
+        return WindowRequest::Toggle;
+    };
+    let _ = std::fs::remove_file(&path);
+    match request.trim() {
+        "open" => WindowRequest::Open,
+        "close" => WindowRequest::Close,
+        _ => WindowRequest::Toggle,
+    }
 }
@@ -1,7 +1,14 @@
-use crate::keys::{default_keybinds, parse_action, parse_key_combos, Action, KeyCombo};
+use crate::json::Json;
+use crate::keys::{
+    default_keybinds, parse_action, parse_key_combos, parse_single_combo, Action, KeyCombo,
+};
 use crate::logging::log;
-use crate::paths::{config_dir, shellexpand};
+use crate::paths::{config_dir, expand_env, shellexpand};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Window color for `opaque_background = true`
+const DEFAULT_OPAQUE_BACKGROUND: &str = "#1e1e1e";
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Anchor {
@@ -15,6 +22,16 @@ pub enum Anchor {
     Cursor,
 }
 
+/// Layer-shell stacking layer (`[window] layer`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum WindowLayer {
+    Background,
+    Bottom,
+    Top,
+    #[default]
+    Overlay,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum Easing {
     Linear,
@@ -57,6 +74,17 @@ impl Easing {
     }
 }
 
+/// None for anything but the four layer names, so typos get reported
+pub fn parse_window_layer(s: &str) -> Option<WindowLayer> {
+    match s.to_lowercase().as_str() {
+        "background" => Some(WindowLayer::Background),
+        "bottom" => Some(WindowLayer::Bottom),
+        "top" => Some(WindowLayer::Top),
+        "overlay" => Some(WindowLayer::Overlay),
+        _ => None,
+    }
+}
+
 pub fn parse_easing(s: &str) -> Easing {
     match s.to_lowercase().replace('-', "_").as_str() {
         "linear" => Easing::Linear,
@@ -68,6 +96,39 @@ pub fn parse_easing(s: &str) -> Easing {
     }
 }
 
+/// Which row is selected when the list is (re)built
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum InitialSelection {
+    #[default]
+    First,
+    None,
+    Last,
+}
+
+/// Row layout (`[style] density`): two lines and 48px icons, or one line
+/// with small icons so more rows fit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Density {
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+pub fn parse_density(s: &str) -> Density {
+    match s.to_lowercase().as_str() {
+        "compact" => Density::Compact,
+        _ => Density::Comfortable,
+    }
+}
+
+pub fn parse_initial_selection(s: &str) -> InitialSelection {
+    match s.to_lowercase().as_str() {
+        "none" => InitialSelection::None,
+        "last" | "last_used" | "last-used" => InitialSelection::Last,
+        _ => InitialSelection::First,
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ConfigBase {
     pub width: i32,
@@ -77,8 +138,34 @@ pub struct ConfigBase {
     pub margin_bottom: i32,
     pub margin_left: i32,
     pub margin_right: i32,
+    pub layer: WindowLayer,
+    /// Space reserved at the anchored edge: 0 overlays other windows,
+    /// -1 reserves the window's own size
+    pub exclusive_zone: i32,
     pub theme: String,
+    pub notify_css_errors: bool,
+    pub opaque_background: Option<String>,
+    pub follow_wallpaper: bool,
+    pub density: Density,
     pub keybinds: HashMap<Action, Vec<KeyCombo>>,
+    pub animations: bool,
+    pub animation_duration: u64,
+    pub animation_easing: Easing,
+    pub remember_search: bool,
+    pub wrap_navigation: bool,
+    pub escape_clears_first: bool,
+    pub smartcase: bool,
+    pub page_step: u32,
+    pub initial_selection: InitialSelection,
+    pub log_path: String,
+    pub log_keep: usize,
+    pub log_max_size_mb: u64,
+    pub unknown_keys: Vec<String>,
+    pub bad_keybinds: Vec<String>,
+    /// Values an app couldn't use, as `[section] key = value: why`
+    pub bad_values: Vec<String>,
+    pub unset_vars: Vec<String>,
+    pub include_errors: Vec<String>,
 }
 
 impl ConfigBase {
@@ -91,15 +178,40 @@ impl ConfigBase {
             margin_bottom: 0,
             margin_left: 0,
             margin_right: 0,
+            layer: WindowLayer::Overlay,
+            exclusive_zone: 0,
             theme: config_dir(app_name)
                 .join("style.css")
                 .to_string_lossy()
                 .to_string(),
+            notify_css_errors: false,
+            opaque_background: None,
+            follow_wallpaper: false,
+            density: Density::Comfortable,
             keybinds: default_keybinds(),
+            animations: true,
+            animation_duration: 200,
+            animation_easing: Easing::EaseOut,
+            remember_search: false,
+            wrap_navigation: false,
+            escape_clears_first: false,
+            smartcase: true,
+            page_step: 0,
+            initial_selection: InitialSelection::First,
+            log_path: String::new(),
+            log_keep: 1,
+            log_max_size_mb: 10,
+            unknown_keys: Vec::new(),
+            bad_keybinds: Vec::new(),
+            bad_values: Vec::new(),
+            unset_vars: Vec::new(),
+            include_errors: Vec::new(),
         }
     }
 
-    pub fn parse_section(&mut self, app_name: &str, section: &str, key: &str, val: &str) {
+    /// Apply one `key = val` from `[section]`. Returns false for keys the
+    /// shared config doesn't know, so apps can try their own.
+    pub fn parse_section(&mut self, section: &str, key: &str, val: &str) -> bool {
         match section {
             "window" => match key {
                 "width" => self.width = val.parse().unwrap_or(self.width),
@@ -109,26 +221,239 @@ impl ConfigBase {
                 "margin_bottom" => self.margin_bottom = val.parse().unwrap_or(0),
                 "margin_left" => self.margin_left = val.parse().unwrap_or(0),
                 "margin_right" => self.margin_right = val.parse().unwrap_or(0),
-                _ => log(app_name, &format!("unknown window key: {}", key)),
+                "layer" => match parse_window_layer(val) {
+                    Some(layer) => self.layer = layer,
+                    None => self.unknown_keys.push(format!(
+                        "[window] layer = {} (expected overlay, top, bottom or background)",
+                        val
+                    )),
+                },
+                "exclusive_zone" => self.exclusive_zone = val.parse().unwrap_or(0),
+                _ => return false,
+            },
+            "style" => match key {
+                "theme" => self.theme = self.expand(val),
+                "notify_errors" => self.notify_css_errors = parse_bool(val, false),
+                "opaque_background" => self.opaque_background = parse_opaque(val),
+                "follow_wallpaper" => self.follow_wallpaper = parse_bool(val, false),
+                "density" => self.density = parse_density(val),
+                _ => return false,
             },
-            "style" => {
-                if key == "theme" {
-                    self.theme = shellexpand(val);
+            "behavior" => match key {
+                "animations" => self.animations = parse_bool(val, true),
+                "animation_duration" => {
+                    self.animation_duration = val.parse().unwrap_or(200);
                 }
-            }
+                "animation_easing" => self.animation_easing = parse_easing(val),
+                "remember_search" => self.remember_search = parse_bool(val, false),
+                "wrap_navigation" => self.wrap_navigation = parse_bool(val, false),
+                "escape_clears_first" => self.escape_clears_first = parse_bool(val, false),
+                "smartcase" => self.smartcase = parse_bool(val, true),
+                "page_step" => self.page_step = val.parse().unwrap_or(0),
+                "initial_selection" => self.initial_selection = parse_initial_selection(val),
+                _ => return false,
+            },
+            "logging" => match key {
+                "path" => self.log_path = self.expand(val),
+                "keep" => self.log_keep = val.parse().unwrap_or(1),
+                "max_size_mb" => self.log_max_size_mb = val.parse().unwrap_or(10).max(1),
+                _ => return false,
+            },
             "keybinds" => {
-                if let Some(action) = parse_action(key) {
-                    let combos = parse_key_combos(val);
-                    if !combos.is_empty() {
-                        self.keybinds.insert(action, combos);
-                    }
+                let Some(action) = parse_action(key) else {
+                    return false;
+                };
+                for bad in val
+                    .split_whitespace()
+                    .filter(|c| parse_single_combo(c).is_none())
+                {
+                    self.bad_keybinds
+                        .push(format!("{} = {}: can't parse '{}'", key, val, bad));
+                }
+                let combos = parse_key_combos(val);
+                if !combos.is_empty() {
+                    self.keybinds.insert(action, combos);
                 }
             }
-            _ => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Apply `shared_config_path()` if it exists and return the entries that
+    /// aren't common to both apps, for the app's own `parse_key`. Apps call
+    /// this before parsing their own file, so per-app values win.
+    pub fn apply_shared(&mut self) -> Vec<(String, String, String)> {
+        let path = shared_config_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Vec::new();
+        };
+        let (entries, errors) = parse_config_with_includes(&content, &path);
+        self.include_errors.extend(errors);
+        entries
+            .into_iter()
+            .filter(|(section, key, val)| !self.parse_section(section, key, val))
+            .collect()
+    }
+
+    /// Remember a shared-file key neither the shared config nor the app
+    /// took. Apps skip the other app's keys, which the file may also hold.
+    pub fn unknown_shared_key(&mut self, section: &str, key: &str) {
+        self.unknown_keys.push(format!(
+            "[{}] {} (in {})",
+            section,
+            key,
+            shared_config_path().display()
+        ));
+    }
+
+    /// Remember a key neither the shared nor the app config recognized
+    pub fn unknown_key(&mut self, section: &str, key: &str) {
+        self.unknown_keys.push(format!("[{}] {}", section, key));
+    }
+
+    /// Expand `~/`, `$VAR` and `${VAR}` in a path-like value, noting unset
+    /// variables for the startup report
+    pub fn expand(&mut self, val: &str) -> String {
+        let (expanded, unset) = expand_env(val);
+        for var in unset {
+            if !self.unset_vars.contains(&var) {
+                self.unset_vars.push(var);
+            }
+        }
+        expanded
+    }
+
+    /// Log unknown config keys and unset variables once per process, with a
+    /// notification so typos don't go unnoticed
+    pub fn report_config_problems(&self, app_name: &str) {
+        static REPORTED: std::sync::Once = std::sync::Once::new();
+        let mut problems = Vec::new();
+        if !self.unknown_keys.is_empty() {
+            problems.push(format!(
+                "unknown config keys: {}",
+                self.unknown_keys.join(", ")
+            ));
+        }
+        if !self.bad_values.is_empty() {
+            problems.push(format!("bad config values: {}", self.bad_values.join(", ")));
         }
+        if !self.unset_vars.is_empty() {
+            problems.push(format!("unset variables: ${}", self.unset_vars.join(", $")));
+        }
+        problems.extend(self.include_errors.iter().cloned());
+        if problems.is_empty() {
+            return;
+        }
+        REPORTED.call_once(|| {
+            let msg = problems.join("; ");
+            log(app_name, &msg);
+            let _ = std::process::Command::new("notify-send")
+                .args([app_name, msg.as_str()])
+                .spawn();
+        });
+    }
+
+    /// The effective shared settings as JSON, one object per config
+    /// section, keyed like the config file. Apps add their own keys with
+    /// `Json::set`.
+    pub fn to_json(&self) -> Json {
+        let mut keybinds: Vec<(&'static str, Json)> = self
+            .keybinds
+            .iter()
+            .map(|(action, combos)| {
+                let combos = combos.iter().map(|c| Json::str(c.to_string()));
+                (action.name(), Json::Arr(combos.collect()))
+            })
+            .collect();
+        keybinds.sort_by_key(|(name, _)| *name);
+
+        let mut json = Json::Obj(Vec::new());
+        for (section, key, value) in [
+            ("window", "width", Json::num(self.width)),
+            ("window", "height", Json::num(self.height)),
+            ("window", "anchor", Json::variant(&self.anchor)),
+            ("window", "margin_top", Json::num(self.margin_top)),
+            ("window", "margin_bottom", Json::num(self.margin_bottom)),
+            ("window", "margin_left", Json::num(self.margin_left)),
+            ("window", "margin_right", Json::num(self.margin_right)),
+            ("window", "layer", Json::variant(&self.layer)),
+            ("window", "exclusive_zone", Json::num(self.exclusive_zone)),
+            ("style", "theme", Json::str(&self.theme)),
+            ("style", "notify_errors", Json::Bool(self.notify_css_errors)),
+            (
+                "style",
+                "opaque_background",
+                self.opaque_background
+                    .as_ref()
+                    .map_or(Json::Null, Json::str),
+            ),
+            (
+                "style",
+                "follow_wallpaper",
+                Json::Bool(self.follow_wallpaper),
+            ),
+            ("style", "density", Json::variant(&self.density)),
+            ("behavior", "animations", Json::Bool(self.animations)),
+            (
+                "behavior",
+                "animation_duration",
+                Json::num(self.animation_duration),
+            ),
+            (
+                "behavior",
+                "animation_easing",
+                Json::variant(&self.animation_easing),
+            ),
+            (
+                "behavior",
+                "remember_search",
+                Json::Bool(self.remember_search),
+            ),
+            (
+                "behavior",
+                "wrap_navigation",
+                Json::Bool(self.wrap_navigation),
+            ),
+            (
+                "behavior",
+                "escape_clears_first",
+                Json::Bool(self.escape_clears_first),
+            ),
+            ("behavior", "smartcase", Json::Bool(self.smartcase)),
+            ("behavior", "page_step", Json::num(self.page_step)),
+            (
+                "behavior",
+                "initial_selection",
+                Json::variant(&self.initial_selection),
+            ),
+            ("logging", "path", Json::str(&self.log_path)),
+            ("logging", "keep", Json::num(self.log_keep)),
+            ("logging", "max_size_mb", Json::num(self.log_max_size_mb)),
+        ] {
+            json.set(section, key, value);
+        }
+        for (action, combos) in keybinds {
+            json.set("keybinds", action, combos);
+        }
+        json
+    }
+
+    /// Point the logger at the `[logging]` settings
+    pub fn apply_logging(&self) {
+        let path = (!self.log_path.is_empty()).then(|| std::path::PathBuf::from(&self.log_path));
+        crate::logging::configure(path, self.log_keep, self.log_max_size_mb * 1024 * 1024);
     }
 }
 
+/// Settings both apps read before their own config:
+/// `~/.config/gui-common/config`
+pub fn shared_config_path() -> PathBuf {
+    config_dir(SHARED_CONFIG_DIR).join("config")
+}
+
+const SHARED_CONFIG_DIR: &str = "gui-common";
+
 pub fn parse_anchor(s: &str) -> Anchor {
     match s.to_lowercase().replace('-', "_").as_str() {
         "center" => Anchor::Center,
@@ -143,6 +468,16 @@ pub fn parse_anchor(s: &str) -> Anchor {
     }
 }
 
+/// `true` picks a neutral dark color, `false` keeps the themes'
+/// transparency, anything else is taken as a CSS color
+fn parse_opaque(s: &str) -> Option<String> {
+    match s.to_lowercase().as_str() {
+        "true" | "yes" | "1" | "on" => Some(DEFAULT_OPAQUE_BACKGROUND.to_string()),
+        "false" | "no" | "0" | "off" | "" => None,
+        _ => Some(s.to_string()),
+    }
+}
+
 pub fn parse_bool(s: &str, default: bool) -> bool {
     match s.to_lowercase().as_str() {
         "true" | "yes" | "1" | "on" => true,
@@ -173,3 +508,129 @@ pub fn parse_config_file(content: &str) -> Vec<(String, String, String)> {
     }
     results
 }
+
+/// Deepest chain of nested `include`s followed
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// `parse_config_file` for the config at `path`, following `include = file`
+/// lines. Included values land where the include sits, so later lines
+/// override them; lines before any header in an included file belong to the
+/// section the include is in. Relative paths resolve against the including
+/// file. Returns the entries and any include errors.
+pub fn parse_config_with_includes(
+    content: &str,
+    path: &Path,
+) -> (Vec<(String, String, String)>, Vec<String>) {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    let mut chain = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+    follow_includes(content, path, "", &mut chain, &mut entries, &mut errors);
+    (entries, errors)
+}
+
+fn follow_includes(
+    content: &str,
+    path: &Path,
+    outer_section: &str,
+    chain: &mut Vec<PathBuf>,
+    entries: &mut Vec<(String, String, String)>,
+    errors: &mut Vec<String>,
+) {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for (section, key, val) in parse_config_file(content) {
+        let section = if section.is_empty() {
+            outer_section.to_string()
+        } else {
+            section
+        };
+        if key != "include" {
+            entries.push((section, key, val));
+            continue;
+        }
+        let target = dir.join(shellexpand(&val));
+        let canonical = target.canonicalize().unwrap_or_else(|_| target.clone());
+        if chain.contains(&canonical) {
+            errors.push(format!("include cycle at {}", target.display()));
+            continue;
+        }
+        if chain.len() > MAX_INCLUDE_DEPTH {
+            errors.push(format!("includes nested too deep at {}", target.display()));
+            continue;
+        }
+        match std::fs::read_to_string(&target) {
+            Ok(c) => {
+                chain.push(canonical);
+                follow_includes(&c, &target, &section, chain, entries, errors);
+                chain.pop();
+            }
+            Err(e) => errors.push(format!("include {}: {}", target.display(), e)),
+        }
+    }
+}
+
+/// Lines `parse_config_file` skips because they aren't a section header,
+/// comment or `key = value`
+pub fn config_syntax_errors(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let t = line.trim();
+            !(t.is_empty()
+                || t.starts_with('#')
+                || (t.starts_with('[') && t.ends_with(']'))
+                || t.contains('='))
+        })
+        .map(|(i, line)| format!("line {}: expected key = value: {}", i + 1, line.trim()))
+        .collect()
+}
+
+/// Set `key = value` in `[section]` of the app's config file, replacing an
+/// existing assignment or adding one (and the section) if missing
+pub fn set_config_value(
+    app_name: &str,
+    section: &str,
+    key: &str,
+    value: &str,
+) -> std::io::Result<()> {
+    let path = config_dir(app_name).join("config");
+    let content = std::fs::read_to_string(&path).unwrap_or_default();
+    let assignment = format!("{} = {}", key, value);
+
+    let mut out: Vec<String> = Vec::new();
+    let mut in_section = false;
+    let mut done = false;
+    for line in content.lines() {
+        let t = line.trim();
+        if t.starts_with('[') && t.ends_with(']') {
+            if in_section && !done {
+                // Keep blank lines separating sections after the new key
+                let at = out.len() - out.iter().rev().take_while(|l| l.trim().is_empty()).count();
+                out.insert(at, assignment.clone());
+                done = true;
+            }
+            in_section = t[1..t.len() - 1].trim().eq_ignore_ascii_case(section);
+        } else if in_section && !done && !t.starts_with('#') {
+            if let Some((k, _)) = t.split_once('=') {
+                if k.trim().eq_ignore_ascii_case(key) {
+                    out.push(assignment.clone());
+                    done = true;
+                    continue;
+                }
+            }
+        }
+        out.push(line.to_string());
+    }
+    if !done {
+        if !in_section {
+            if !out.is_empty() {
+                out.push(String::new());
+            }
+            out.push(format!("[{}]", section));
+        }
+        out.push(assignment);
+    }
+
+    std::fs::create_dir_all(config_dir(app_name))?;
+    std::fs::write(&path, out.join("\n") + "\n")
+}
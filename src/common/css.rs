@@ -1,19 +1,189 @@
+use crate::config::{Density, Easing};
 use crate::logging::log;
+use std::cell::{Cell, RefCell};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-pub fn load_css(app_name: &str, theme_path: &str, default_css: &str) -> String {
-    let p = PathBuf::from(theme_path);
-    if p.exists() {
-        if let Ok(css) = std::fs::read_to_string(&p) {
-            log(app_name, &format!("loaded css from {}", p.display()));
-            return css;
+thread_local! {
+    static OVERRIDE_PROVIDER: RefCell<Option<gtk4::CssProvider>> = const { RefCell::new(None) };
+    static BACKGROUND_PROVIDER: RefCell<Option<gtk4::CssProvider>> = const { RefCell::new(None) };
+    static WALLPAPER_PROVIDER: RefCell<Option<gtk4::CssProvider>> = const { RefCell::new(None) };
+}
+
+static NOTIFY_ERRORS: AtomicBool = AtomicBool::new(false);
+static OPAQUE: AtomicBool = AtomicBool::new(false);
+static COMPACT: AtomicBool = AtomicBool::new(false);
+
+/// Also show CSS parse errors with notify-send (`[style] notify_errors`)
+pub fn set_notify_errors(on: bool) {
+    NOTIFY_ERRORS.store(on, Ordering::Relaxed);
+}
+
+/// Row layout for every list built from now on (`[style] density`)
+pub fn set_density(density: Density) {
+    COMPACT.store(density == Density::Compact, Ordering::Relaxed);
+}
+
+/// Single-line rows without subtitles
+pub fn is_compact() -> bool {
+    COMPACT.load(Ordering::Relaxed)
+}
+
+/// Icon and thumbnail size for list rows
+pub fn row_icon_size() -> i32 {
+    if is_compact() {
+        24
+    } else {
+        48
+    }
+}
+
+/// Put `provider` in `slot` at `priority`, removing whatever was there
+fn swap_provider(
+    slot: &'static std::thread::LocalKey<RefCell<Option<gtk4::CssProvider>>>,
+    provider: Option<gtk4::CssProvider>,
+    priority: u32,
+) {
+    let Some(display) = gtk4::gdk::Display::default() else {
+        return;
+    };
+    slot.with(|p| {
+        if let Some(old) = p.borrow_mut().take() {
+            gtk4::style_context_remove_provider_for_display(&display, &old);
         }
+        if let Some(provider) = provider {
+            gtk4::style_context_add_provider_for_display(&display, &provider, priority);
+            *p.borrow_mut() = Some(provider);
+        }
+    });
+}
+
+/// Load `source` above the startup stylesheet, replacing any earlier
+/// override so reloads and theme switches don't pile up providers
+pub fn apply_override_css(app_name: &str, source: &CssSource) {
+    swap_provider(
+        &OVERRIDE_PROVIDER,
+        Some(css_provider(app_name, source)),
+        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+    );
+}
+
+/// Whether `[style] opaque_background` is set; built-in themes then skip
+/// their transparency prefix
+pub fn is_opaque() -> bool {
+    OPAQUE.load(Ordering::Relaxed)
+}
+
+/// Paint the window with a solid `color` above every theme, or go back to
+/// the themes' own transparent background with `None`. Call before
+/// resolving the theme so built-ins pick up the setting.
+pub fn apply_background(color: Option<&str>) {
+    OPAQUE.store(color.is_some(), Ordering::Relaxed);
+    let provider = color.map(|color| {
+        let provider = gtk4::CssProvider::new();
+        provider.load_from_data(&format!(
+            "window,\nwindow.background {{\n  background-color: {};\n}}\n",
+            color
+        ));
+        provider
+    });
+    swap_provider(
+        &BACKGROUND_PROVIDER,
+        provider,
+        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 3,
+    );
+}
+
+/// Recolor the active theme from pywal/matugen output when `follow` is set
+/// (`[style] follow_wallpaper`); re-read on every reload
+pub fn apply_wallpaper_colors(app_name: &str, follow: bool) {
+    let provider = follow
+        .then(crate::wallpaper::wallpaper_css)
+        .flatten()
+        .map(|(path, css)| {
+            log(
+                app_name,
+                &format!("using wallpaper colors from {}", path.display()),
+            );
+            css_provider(app_name, &CssSource::Builtin(css))
+        });
+    if follow && provider.is_none() {
+        log(
+            app_name,
+            "follow_wallpaper: no pywal or matugen colors found",
+        );
+    }
+    swap_provider(
+        &WALLPAPER_PROVIDER,
+        provider,
+        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 2,
+    );
+}
+
+/// Where a stylesheet comes from
+pub enum CssSource {
+    /// A user file, loaded by path so relative `@import`s resolve
+    File(PathBuf),
+    /// Compiled-in CSS (defaults and built-in themes)
+    Builtin(String),
+}
+
+/// `file:line:col` for a parse error; built-in CSS has no file
+fn section_location(section: &gtk4::CssSection) -> String {
+    use gtk4::prelude::FileExt;
+    let file = section
+        .file()
+        .and_then(|f| f.path())
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "<builtin>".to_string());
+    let start = section.start_location();
+    format!("{}:{}:{}", file, start.lines() + 1, start.line_chars() + 1)
+}
+
+/// Build a provider for `source`. Parse errors go to the log, and the first
+/// one per load is also sent as a notification when enabled.
+pub fn css_provider(app_name: &str, source: &CssSource) -> gtk4::CssProvider {
+    let provider = gtk4::CssProvider::new();
+    let app = app_name.to_string();
+    let notified = Cell::new(false);
+    provider.connect_parsing_error(move |_, section, error| {
+        let msg = format!(
+            "css parse error at {}: {}",
+            section_location(section),
+            error.message()
+        );
+        log(&app, &msg);
+        if NOTIFY_ERRORS.load(Ordering::Relaxed) && !notified.replace(true) {
+            let _ = std::process::Command::new("notify-send")
+                .args([app.as_str(), msg.as_str()])
+                .spawn();
+        }
+    });
+    match source {
+        CssSource::File(path) => provider.load_from_path(path),
+        CssSource::Builtin(css) => provider.load_from_data(css),
+    }
+    provider
+}
+
+/// Resolve a `theme` setting: a built-in theme name or a CSS file path.
+/// Falls back to `default_css` when neither exists.
+pub fn load_css(app_name: &str, theme: &str, default_css: &str) -> CssSource {
+    if !theme.contains('/') && !theme.ends_with(".css") {
+        return CssSource::Builtin(
+            crate::paths::get_theme_css(theme).unwrap_or_else(|| default_css.to_string()),
+        );
+    }
+    let p = PathBuf::from(theme);
+    if p.is_file() {
+        log(app_name, &format!("loading css from {}", p.display()));
+        return CssSource::File(p);
     }
     log(
         app_name,
-        &format!("theme not found: {}, using default", theme_path),
+        &format!("theme not found: {}, using default", theme),
     );
-    default_css.to_string()
+    CssSource::Builtin(default_css.to_string())
 }
 
 pub fn char_truncate(s: &str, max: usize) -> String {
@@ -25,11 +195,27 @@ pub fn char_truncate(s: &str, max: usize) -> String {
     }
 }
 
-pub fn scroll_to_selected(listbox: &gtk4::ListBox, scroll: &gtk4::ScrolledWindow) {
+/// Scroll so the selected row is visible; `duration_ms == 0` jumps instantly
+pub fn scroll_to_selected(
+    listbox: &gtk4::ListBox,
+    scroll: &gtk4::ScrolledWindow,
+    duration_ms: u64,
+    easing: Easing,
+) {
+    use gtk4::prelude::*;
+    if let Some(row) = listbox.selected_row() {
+        scroll_to_widget(row.upcast_ref(), scroll, duration_ms, easing);
+    }
+}
+
+/// Scroll so `row` (a child of the scrolled list) is visible
+pub fn scroll_to_widget(
+    row: &gtk4::Widget,
+    scroll: &gtk4::ScrolledWindow,
+    duration_ms: u64,
+    easing: Easing,
+) {
     use gtk4::prelude::*;
-    let Some(row) = listbox.selected_row() else {
-        return;
-    };
     let adj = scroll.vadjustment();
     let alloc = row.allocation();
     let row_y = alloc.y() as f64;
@@ -45,38 +231,34 @@ pub fn scroll_to_selected(listbox: &gtk4::ListBox, scroll: &gtk4::ScrolledWindow
     } else {
         return;
     };
-    animate_scroll(adj, target);
+    animate_scroll(adj, target, duration_ms, easing);
 }
 
-fn animate_scroll(adj: gtk4::Adjustment, target: f64) {
+fn animate_scroll(adj: gtk4::Adjustment, target: f64, duration_ms: u64, easing: Easing) {
     use gtk4::prelude::*;
     let start = adj.value();
     let diff = target - start;
-    if diff.abs() < 1.0 {
+    if diff.abs() < 1.0 || crate::anim::duration(duration_ms) == 0 {
         adj.set_value(target);
         return;
     }
-    let duration_ms = 150;
     let steps = 15;
-    let step_ms = duration_ms / steps;
+    let step_ms = (duration_ms / steps).max(1);
     let adj_clone = adj.clone();
     let step = std::rc::Rc::new(std::cell::Cell::new(0));
     let step_clone = step.clone();
-    glib::timeout_add_local(
-        std::time::Duration::from_millis(step_ms as u64),
-        move || {
-            let s = step_clone.get() + 1;
-            step_clone.set(s);
-            let t = s as f64 / steps as f64;
-            let eased = 1.0 - (1.0 - t).powi(3);
-            let val = start + diff * eased;
-            adj_clone.set_value(val);
-            if s >= steps {
-                adj_clone.set_value(target);
-                glib::ControlFlow::Break
-            } else {
-                glib::ControlFlow::Continue
-            }
-        },
-    );
+    glib::timeout_add_local(std::time::Duration::from_millis(step_ms), move || {
+        let s = step_clone.get() + 1;
+        step_clone.set(s);
+        let t = s as f64 / steps as f64;
+        let eased = easing.apply(t);
+        let val = start + diff * eased;
+        adj_clone.set_value(val);
+        if s >= steps {
+            adj_clone.set_value(target);
+            glib::ControlFlow::Break
+        } else {
+            glib::ControlFlow::Continue
+        }
+    });
 }
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Translations that ship in the binary, by language code. Files in the
+/// data dirs (see `catalog_dirs`) take precedence.
+const BUILTIN: &[(&str, &str)] = &[("de", include_str!("locale/de.po"))];
+
+static CATALOG: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Load the catalog for the user's language, once, before any UI text is
+/// built. Without one (or for `C` / `POSIX` / English), strings stay as
+/// written.
+pub fn init(app_name: &str) {
+    CATALOG.get_or_init(|| load(app_name).unwrap_or_default());
+}
+
+/// `msgid` in the user's language, or as is when it has no translation
+pub fn tr(msgid: &str) -> &str {
+    CATALOG
+        .get()
+        .and_then(|c| c.get(msgid))
+        .map_or(msgid, String::as_str)
+}
+
+/// `tr`, then each `{}` in the result filled from `args` in order.
+/// Translations may move the `{}` around but must keep their count.
+pub fn trf(msgid: &str, args: &[&dyn Display]) -> String {
+    fill(tr(msgid), args)
+}
+
+/// `template` with each `{}` replaced by the next of `args`
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut parts = template.split("{}");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+/// Languages to try, most preferred first: `LANGUAGE` (a `:` list), else
+/// the first of `LC_ALL`, `LC_MESSAGES` and `LANG`. `de_DE.UTF-8` is tried
+/// as `de_DE`, then `de`.
+fn languages() -> Vec<String> {
+    languages_from(|name| std::env::var(name).ok())
+}
+
+/// `languages` with the environment looked up through `env`
+fn languages_from(env: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let var = |name| env(name).filter(|v| !v.is_empty());
+    let locale = var("LC_ALL")
+        .or_else(|| var("LC_MESSAGES"))
+        .or_else(|| var("LANG"));
+    // LANGUAGE is ignored under the C locale, as gettext does
+    let list = match (&locale, var("LANGUAGE")) {
+        (Some(l), _) if l == "C" || l == "POSIX" => return Vec::new(),
+        (_, Some(language)) => language,
+        (Some(l), None) => l.clone(),
+        (None, None) => return Vec::new(),
+    };
+    let mut langs = Vec::new();
+    for entry in list.split(':') {
+        let name = entry.split(['.', '@']).next().unwrap_or_default();
+        let base = name.split('_').next().unwrap_or_default();
+        for lang in [name, base] {
+            if !lang.is_empty() && !langs.iter().any(|l| l == lang) {
+                langs.push(lang.to_string());
+            }
+        }
+    }
+    langs
+}
+
+/// `<data dir>/<app>/locale` for `$XDG_DATA_HOME` and each of
+/// `$XDG_DATA_DIRS`, in lookup order
+fn catalog_dirs(app_name: &str) -> Vec<PathBuf> {
+    let home = std::env::var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            PathBuf::from(std::env::var("HOME").unwrap_or("/tmp".into())).join(".local/share")
+        });
+    let system = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    std::iter::once(home)
+        .chain(system.split(':').map(PathBuf::from))
+        .map(|d| d.join(app_name).join("locale"))
+        .collect()
+}
+
+fn load(app_name: &str) -> Option<HashMap<String, String>> {
+    let dirs = catalog_dirs(app_name);
+    for lang in languages() {
+        if lang == "en" || lang.starts_with("en_") {
+            return None;
+        }
+        for dir in &dirs {
+            if let Ok(po) = std::fs::read_to_string(dir.join(format!("{}.po", lang))) {
+                return Some(parse_po(&po));
+            }
+        }
+        if let Some((_, po)) = BUILTIN.iter().find(|(l, _)| *l == lang) {
+            return Some(parse_po(po));
+        }
+    }
+    None
+}
+
+/// The translated entries of a gettext `.po` file. Untranslated and fuzzy
+/// entries, the header and plural forms are skipped.
+fn parse_po(po: &str) -> HashMap<String, String> {
+    enum Field {
+        None,
+        Id,
+        Str,
+    }
+    let mut catalog = HashMap::new();
+    let (mut id, mut msg) = (String::new(), String::new());
+    let mut field = Field::None;
+    // Whether the current entry, and the one whose flags or context were
+    // just read, are to be dropped
+    let (mut skip, mut skip_next) = (false, false);
+    let mut flush = |id: &mut String, msg: &mut String, skip: bool| {
+        if !id.is_empty() && !msg.is_empty() && !skip {
+            catalog.insert(std::mem::take(id), std::mem::take(msg));
+        }
+        id.clear();
+        msg.clear();
+    };
+    for line in po.lines().map(str::trim) {
+        if let Some(flags) = line.strip_prefix("#,") {
+            skip_next = flags.split(',').any(|f| f.trim() == "fuzzy");
+        } else if line.starts_with('#') || line.is_empty() {
+            continue;
+        } else if let Some(rest) = line.strip_prefix("msgid ") {
+            flush(&mut id, &mut msg, skip);
+            skip = std::mem::take(&mut skip_next);
+            id = unquote(rest);
+            field = Field::Id;
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            msg = unquote(rest);
+            field = Field::Str;
+        } else if line.starts_with('"') {
+            match field {
+                Field::Id => id.push_str(&unquote(line)),
+                Field::Str => msg.push_str(&unquote(line)),
+                Field::None => {}
+            }
+        } else if line.starts_with("msgctxt") {
+            // Contexts aren't supported; leave out the entry that follows
+            skip_next = true;
+            field = Field::None;
+        } else {
+            // msgid_plural and msgstr[n]: plurals aren't supported
+            skip = true;
+            field = Field::None;
+        }
+    }
+    flush(&mut id, &mut msg, skip);
+    catalog
+}
+
+/// A `.po` string literal's contents, with C escapes resolved
+fn unquote(s: &str) -> String {
+    let s = s.trim();
+    let s = s.strip_prefix('"').unwrap_or(s);
+    let s = s.strip_suffix('"').unwrap_or(s);
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn langs(vars: &[(&str, &str)]) -> Vec<String> {
+        languages_from(|name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        })
+    }
+
+    #[test]
+    fn multi_line_strings_are_joined() {
+        let po = "msgid \"\"\n\"Project-Id-Version: x\\n\"\n\n\
+                  msgid \"\"\n\"Copy \"\n\"all\"\nmsgstr \"Alles \"\n\"kopieren\"\n";
+        let catalog = parse_po(po);
+        assert_eq!(catalog.len(), 1);
+        assert_eq!(catalog["Copy all"], "Alles kopieren");
+    }
+
+    #[test]
+    fn fuzzy_context_and_untranslated_entries_are_dropped() {
+        let po = "#, fuzzy\nmsgid \"Close\"\nmsgstr \"Schließen\"\n\n\
+                  msgctxt \"menu\"\nmsgid \"Open\"\nmsgstr \"Öffnen\"\n\n\
+                  msgid \"Delete\"\nmsgstr \"\"\n\n\
+                  #, c-format\nmsgid \"Copy\"\nmsgstr \"Kopieren\"\n";
+        let catalog = parse_po(po);
+        assert_eq!(catalog.len(), 1);
+        assert_eq!(catalog["Copy"], "Kopieren");
+    }
+
+    #[test]
+    fn plurals_are_dropped_without_taking_the_next_entry() {
+        let po = "msgid \"{} file\"\nmsgid_plural \"{} files\"\n\
+                  msgstr[0] \"{} Datei\"\nmsgstr[1] \"{} Dateien\"\n\n\
+                  msgid \"Open\"\nmsgstr \"Öffnen\"\n";
+        let catalog = parse_po(po);
+        assert_eq!(catalog.len(), 1);
+        assert_eq!(catalog["Open"], "Öffnen");
+    }
+
+    #[test]
+    fn escapes_are_resolved() {
+        assert_eq!(unquote(r#""a\nb\tc""#), "a\nb\tc");
+        assert_eq!(unquote(r#""say \"hi\" \\ bye""#), "say \"hi\" \\ bye");
+        let catalog = parse_po("msgid \"Line\\n\"\nmsgstr \"Zeile\\n\"\n");
+        assert_eq!(catalog["Line\n"], "Zeile\n");
+    }
+
+    #[test]
+    fn language_list_wins_unless_the_locale_is_c() {
+        assert_eq!(
+            langs(&[("LANGUAGE", "fr:de_AT"), ("LANG", "de_DE.UTF-8")]),
+            ["fr", "de_AT", "de"]
+        );
+        assert!(langs(&[("LANGUAGE", "de"), ("LC_ALL", "C")]).is_empty());
+        assert!(langs(&[("LANGUAGE", "de"), ("LANG", "POSIX")]).is_empty());
+        assert!(langs(&[]).is_empty());
+    }
+
+    #[test]
+    fn lc_all_beats_lc_messages_and_lang() {
+        let vars = [
+            ("LC_ALL", "de_CH.UTF-8@euro"),
+            ("LC_MESSAGES", "fr_FR"),
+            ("LANG", "es_ES"),
+        ];
+        assert_eq!(langs(&vars), ["de_CH", "de"]);
+        assert_eq!(langs(&vars[1..]), ["fr_FR", "fr"]);
+        // Set but empty counts as unset
+        assert_eq!(langs(&[("LC_ALL", ""), ("LANG", "es_ES")]), ["es_ES", "es"]);
+    }
+
+    #[test]
+    fn placeholders_fill_in_order_wherever_they_moved() {
+        assert_eq!(fill("{} apps", &[&3]), "3 apps");
+        assert_eq!(fill("Apps: {}", &[&3]), "Apps: 3");
+        assert_eq!(fill("{} von {} kopiert", &[&2, &5]), "2 von 5 kopiert");
+        assert_eq!(fill("Kopiert ({}/{})", &[&2, &5]), "Kopiert (2/5)");
+        // A translation that dropped a placeholder loses that argument
+        assert_eq!(fill("{} kopiert", &[&2, &5]), "2 kopiert");
+        // No catalog is loaded in tests, so trf fills the msgid itself
+        assert_eq!(trf("{} of {}", &[&2, &5]), "2 of 5");
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt::{Debug, Display};
+
+/// A JSON value, enough for the `--dump-config` style outputs. Objects keep
+/// their fields in insertion order so the output shape stays stable.
+#[derive(Clone, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Num(String),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn num(n: impl Display) -> Self {
+        Json::Num(n.to_string())
+    }
+
+    pub fn str(s: impl Into<String>) -> Self {
+        Json::Str(s.into())
+    }
+
+    /// An enum value as the config spells it: `TopLeft` -> "top_left"
+    pub fn variant(v: &impl Debug) -> Self {
+        let mut out = String::new();
+        for (i, c) in format!("{:?}", v).chars().enumerate() {
+            if c.is_uppercase() && i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        }
+        Json::Str(out)
+    }
+
+    /// Set `key` in the object `section` of this object, creating the
+    /// section if needed. Does nothing on non-objects.
+    pub fn set(&mut self, section: &str, key: &str, value: Json) {
+        let Json::Obj(sections) = self else {
+            return;
+        };
+        let pos = match sections.iter().position(|(name, _)| name == section) {
+            Some(pos) => pos,
+            None => {
+                sections.push((section.to_string(), Json::Obj(Vec::new())));
+                sections.len() - 1
+            }
+        };
+        if let Json::Obj(fields) = &mut sections[pos].1 {
+            match fields.iter_mut().find(|(name, _)| name == key) {
+                Some((_, slot)) => *slot = value,
+                None => fields.push((key.to_string(), value)),
+            }
+        }
+    }
+
+    /// Two-space indented, one field or element per line
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let pad = |out: &mut String, depth: usize| out.push_str(&"  ".repeat(depth));
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Json::Num(n) => out.push_str(n),
+            Json::Str(s) => {
+                out.push('"');
+                out.push_str(&escape(s));
+                out.push('"');
+            }
+            Json::Arr(items) if items.is_empty() => out.push_str("[]"),
+            Json::Obj(fields) if fields.is_empty() => out.push_str("{}"),
+            Json::Arr(items) => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    pad(out, depth + 1);
+                    item.write(out, depth + 1);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                pad(out, depth);
+                out.push(']');
+            }
+            Json::Obj(fields) => {
+                out.push_str("{\n");
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    pad(out, depth + 1);
+                    out.push_str(&format!("\"{}\": ", escape(key)));
+                    value.write(out, depth + 1);
+                    out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                pad(out, depth);
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Escape `s` for use inside a JSON string literal
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -1,8 +1,18 @@
+use gtk4::prelude::*;
 use std::collections::HashMap;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Action {
     Select,
+    SelectToggleTerminal,
+    CopyPath,
+    ActionsMenu,
+    /// Mark or unmark the selected entry for `CopyMarked`
+    ToggleMark,
+    /// Copy every marked text entry, joined, as one clipboard item
+    CopyMarked,
+    /// Refetch the history, keeping the query and selection
+    Refresh,
     Delete,
     ClearSearch,
     Close,
@@ -27,20 +37,134 @@ pub struct KeyCombo {
     pub mods: gdk4::ModifierType,
 }
 
+/// Config names for each action, as used in `[keybinds]`
+const ACTION_NAMES: &[(&str, Action)] = &[
+    ("select", Action::Select),
+    ("select_toggle_terminal", Action::SelectToggleTerminal),
+    ("copy_path", Action::CopyPath),
+    ("actions_menu", Action::ActionsMenu),
+    ("toggle_mark", Action::ToggleMark),
+    ("copy_marked", Action::CopyMarked),
+    ("refresh", Action::Refresh),
+    ("delete", Action::Delete),
+    ("clear_search", Action::ClearSearch),
+    ("close", Action::Close),
+    ("next", Action::Next),
+    ("prev", Action::Prev),
+    ("page_down", Action::PageDown),
+    ("page_up", Action::PageUp),
+    ("first", Action::First),
+    ("last", Action::Last),
+];
+
 pub fn parse_action(s: &str) -> Option<Action> {
-    match s {
-        "select" => Some(Action::Select),
-        "delete" => Some(Action::Delete),
-        "clear_search" => Some(Action::ClearSearch),
-        "close" => Some(Action::Close),
-        "next" => Some(Action::Next),
-        "prev" => Some(Action::Prev),
-        "page_down" => Some(Action::PageDown),
-        "page_up" => Some(Action::PageUp),
-        "first" => Some(Action::First),
-        "last" => Some(Action::Last),
-        _ => None,
+    ACTION_NAMES
+        .iter()
+        .find(|(name, _)| *name == s)
+        .map(|(_, a)| a.clone())
+}
+
+impl Action {
+    /// The `[keybinds]` name for this action
+    pub fn name(&self) -> &'static str {
+        ACTION_NAMES
+            .iter()
+            .find(|(_, a)| a == self)
+            .map(|(name, _)| *name)
+            .unwrap_or("?")
+    }
+}
+
+impl KeyCombo {
+    /// `Ctrl+Shift+c` style, for messages
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        for (m, name) in [
+            (gdk4::ModifierType::CONTROL_MASK, "Ctrl"),
+            (gdk4::ModifierType::SHIFT_MASK, "Shift"),
+            (gdk4::ModifierType::ALT_MASK, "Alt"),
+            (gdk4::ModifierType::SUPER_MASK, "Super"),
+        ] {
+            if self.mods.contains(m) {
+                parts.push(name.to_string());
+            }
+        }
+        parts.push(
+            self.key
+                .name()
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| "?".to_string()),
+        );
+        parts.join("+")
+    }
+}
+
+/// The canonical `[keybinds]` spelling, e.g. `ctrl+shift+page_up`, which
+/// `parse_single_combo` reads back to the same combo. Key names are
+/// lowercased unless that would read back as a different key.
+impl std::fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (m, name) in [
+            (gdk4::ModifierType::CONTROL_MASK, "ctrl"),
+            (gdk4::ModifierType::SHIFT_MASK, "shift"),
+            (gdk4::ModifierType::ALT_MASK, "alt"),
+            (gdk4::ModifierType::SUPER_MASK, "super"),
+        ] {
+            if self.mods.contains(m) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        let Some(name) = self.key.name() else {
+            return write!(f, "?");
+        };
+        let lower = name.to_lowercase();
+        if parse_single_combo(&lower).is_some_and(|c| c.key == self.key) {
+            write!(f, "{}", lower)
+        } else {
+            write!(f, "{}", name)
+        }
+    }
+}
+
+/// Footer hints for `actions` as (first bound combo, label), in table
+/// order. Unbound actions are left out rather than shown with a stale key.
+pub fn status_hints(
+    keybinds: &HashMap<Action, Vec<KeyCombo>>,
+    actions: &[(Action, &'static str)],
+) -> Vec<(String, &'static str)> {
+    actions
+        .iter()
+        .filter_map(|(action, label)| {
+            let combo = keybinds.get(action)?.first()?;
+            Some((combo.to_string(), *label))
+        })
+        .collect()
+}
+
+/// Combos bound to more than one action, as `combo: action, action`
+pub fn keybind_conflicts(keybinds: &HashMap<Action, Vec<KeyCombo>>) -> Vec<String> {
+    let mut by_combo: Vec<(&KeyCombo, Vec<&'static str>)> = Vec::new();
+    for (action, combos) in keybinds {
+        for combo in combos {
+            match by_combo
+                .iter_mut()
+                .find(|(c, _)| c.key == combo.key && c.mods == combo.mods)
+            {
+                Some((_, actions)) => actions.push(action.name()),
+                None => by_combo.push((combo, vec![action.name()])),
+            }
+        }
     }
+    let mut conflicts: Vec<String> = by_combo
+        .into_iter()
+        .filter(|(_, actions)| actions.len() > 1)
+        .map(|(combo, mut actions)| {
+            actions.sort();
+            format!("{}: {}", combo.label(), actions.join(", "))
+        })
+        .collect();
+    conflicts.sort();
+    conflicts
 }
 
 pub fn parse_key_combos(s: &str) -> Vec<KeyCombo> {
@@ -60,7 +184,8 @@ pub fn parse_single_combo(s: &str) -> Option<KeyCombo> {
             "shift" => mods |= gdk4::ModifierType::SHIFT_MASK,
             "alt" | "mod1" => mods |= gdk4::ModifierType::ALT_MASK,
             "super" | "mod4" => mods |= gdk4::ModifierType::SUPER_MASK,
-            _ => {}
+            // A typo like `ctlr+d` would otherwise bind plain `d`
+            _ => return None,
         }
     }
 
@@ -79,12 +204,63 @@ pub fn parse_single_combo(s: &str) -> Option<KeyCombo> {
         "page_up" | "pageup" | "pgup" => gdk4::Key::Page_Up,
         "page_down" | "pagedown" | "pgdn" => gdk4::Key::Page_Down,
         "space" => gdk4::Key::space,
+        "menu" => gdk4::Key::Menu,
+        "insert" | "ins" => gdk4::Key::Insert,
+        "print" | "printscreen" | "print_screen" => gdk4::Key::Print,
+        "pause" => gdk4::Key::Pause,
+        "kp_enter" => gdk4::Key::KP_Enter,
+        "kp_add" | "kp_plus" => gdk4::Key::KP_Add,
+        "kp_subtract" | "kp_minus" => gdk4::Key::KP_Subtract,
+        "kp_multiply" => gdk4::Key::KP_Multiply,
+        "kp_divide" => gdk4::Key::KP_Divide,
+        "kp_decimal" => gdk4::Key::KP_Decimal,
+        s if s.len() == 4 && s.starts_with("kp_") && s.as_bytes()[3].is_ascii_digit() => {
+            gdk4::Key::from_name(format!("KP_{}", &s[3..]))?
+        }
         s if s.len() == 1 => gdk4::Key::from_name(s)?,
-        _ => return None,
+        s if s.starts_with('f') && s[1..].parse::<u8>().is_ok() => {
+            gdk4::Key::from_name(s.to_uppercase())?
+        }
+        s if s.starts_with("xf86") => xf86_key(key_str, s)?,
+        // Any other keysym by its exact name: `slash`, `Scroll_Lock`
+        _ => gdk4::Key::from_name(*key_str)?,
     };
     Some(KeyCombo { key, mods })
 }
 
+/// Media and hardware keys: `XF86AudioPlay` as written, or any casing of
+/// the common ones (`xf86copy`, `XF86audioplay`)
+fn xf86_key(name: &str, lower: &str) -> Option<gdk4::Key> {
+    const KNOWN: &[&str] = &[
+        "XF86Copy",
+        "XF86Paste",
+        "XF86Cut",
+        "XF86Search",
+        "XF86Tools",
+        "XF86Favorites",
+        "XF86Calculator",
+        "XF86Explorer",
+        "XF86HomePage",
+        "XF86Launch1",
+        "XF86Launch2",
+        "XF86LaunchA",
+        "XF86LaunchB",
+        "XF86AudioPlay",
+        "XF86AudioPause",
+        "XF86AudioStop",
+        "XF86AudioNext",
+        "XF86AudioPrev",
+        "XF86AudioMute",
+        "XF86AudioRaiseVolume",
+        "XF86AudioLowerVolume",
+        "XF86AudioMicMute",
+    ];
+    gdk4::Key::from_name(name).or_else(|| {
+        let canonical = KNOWN.iter().find(|k| k.to_lowercase() == lower)?;
+        gdk4::Key::from_name(*canonical)
+    })
+}
+
 pub fn match_action(
     keybinds: &HashMap<Action, Vec<KeyCombo>>,
     key: gdk4::Key,
@@ -94,11 +270,18 @@ pub fn match_action(
         | gdk4::ModifierType::SHIFT_MASK
         | gdk4::ModifierType::ALT_MASK
         | gdk4::ModifierType::SUPER_MASK;
-    let pressed = mods & relevant;
+    let mut pressed = mods & relevant;
+    // Shift turns letters uppercase; bindings are parsed lowercase
+    let mut key = key.to_lower();
+    // Shift+Tab often arrives as ISO_Left_Tab; match it as Tab+Shift
+    if key == gdk4::Key::ISO_Left_Tab {
+        key = gdk4::Key::Tab;
+        pressed |= gdk4::ModifierType::SHIFT_MASK;
+    }
 
     for (action, combos) in keybinds {
         for combo in combos {
-            if combo.key == key && combo.mods == pressed {
+            if combo.key.to_lower() == key && combo.mods == pressed {
                 return Some(action.clone());
             }
         }
@@ -106,6 +289,34 @@ pub fn match_action(
     None
 }
 
+/// Cursor and selection keys the search entry should handle itself
+/// (Home, End, Left, Right with any Ctrl/Shift combination, and Ctrl+A)
+pub fn is_text_editing_key(key: gdk4::Key, mods: gdk4::ModifierType) -> bool {
+    let other = gdk4::ModifierType::ALT_MASK | gdk4::ModifierType::SUPER_MASK;
+    if mods.intersects(other) {
+        return false;
+    }
+    match key.to_lower() {
+        gdk4::Key::Home
+        | gdk4::Key::End
+        | gdk4::Key::Left
+        | gdk4::Key::Right
+        | gdk4::Key::KP_Home
+        | gdk4::Key::KP_End
+        | gdk4::Key::KP_Left
+        | gdk4::Key::KP_Right => true,
+        gdk4::Key::a => mods.contains(gdk4::ModifierType::CONTROL_MASK),
+        _ => false,
+    }
+}
+
+/// Whether keyboard focus is inside `entry` (GTK focuses its inner text widget)
+pub fn entry_has_focus(window: &impl IsA<gtk4::Window>, entry: &gtk4::Entry) -> bool {
+    window
+        .focus()
+        .is_some_and(|f| &f == entry.upcast_ref::<gtk4::Widget>() || f.is_ancestor(entry))
+}
+
 /// Get the character for a key press (for vim mode)
 pub fn key_to_char(key: gdk4::Key) -> Option<char> {
     key.to_unicode().filter(|c| c.is_ascii_graphic())
@@ -126,6 +337,54 @@ pub fn default_keybinds() -> HashMap<Action, Vec<KeyCombo>> {
             },
         ],
     );
+    kb.insert(
+        Action::SelectToggleTerminal,
+        vec![KeyCombo {
+            key: gdk4::Key::Return,
+            mods: gdk4::ModifierType::SHIFT_MASK,
+        }],
+    );
+    kb.insert(
+        Action::CopyPath,
+        vec![KeyCombo {
+            key: gdk4::Key::c,
+            mods: gdk4::ModifierType::CONTROL_MASK | gdk4::ModifierType::SHIFT_MASK,
+        }],
+    );
+    kb.insert(
+        Action::ToggleMark,
+        vec![KeyCombo {
+            key: gdk4::Key::space,
+            mods: gdk4::ModifierType::CONTROL_MASK,
+        }],
+    );
+    kb.insert(
+        Action::CopyMarked,
+        vec![KeyCombo {
+            key: gdk4::Key::Return,
+            mods: gdk4::ModifierType::CONTROL_MASK,
+        }],
+    );
+    kb.insert(
+        Action::Refresh,
+        vec![KeyCombo {
+            key: gdk4::Key::r,
+            mods: gdk4::ModifierType::CONTROL_MASK,
+        }],
+    );
+    kb.insert(
+        Action::ActionsMenu,
+        vec![
+            KeyCombo {
+                key: gdk4::Key::Menu,
+                mods: gdk4::ModifierType::empty(),
+            },
+            KeyCombo {
+                key: gdk4::Key::F10,
+                mods: gdk4::ModifierType::SHIFT_MASK,
+            },
+        ],
+    );
     kb.insert(
         Action::Delete,
         vec![KeyCombo {
@@ -203,3 +462,105 @@ pub fn default_keybinds() -> HashMap<Action, Vec<KeyCombo>> {
     );
     kb
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_round_trip(combo: &KeyCombo) {
+        let text = combo.to_string();
+        let back = parse_single_combo(&text).unwrap_or_else(|| panic!("{} doesn't parse", text));
+        assert_eq!((back.key, back.mods), (combo.key, combo.mods), "{}", text);
+    }
+
+    #[test]
+    fn default_keybinds_round_trip() {
+        for combo in default_keybinds().values().flatten() {
+            assert_round_trip(combo);
+        }
+    }
+
+    #[test]
+    fn other_combos_round_trip() {
+        for s in [
+            "shift+tab",
+            "ctrl+shift+page_up",
+            "alt+super+x",
+            "kp_5",
+            "ctrl+kp_enter",
+            "kp_add",
+            "f1",
+            "shift+f12",
+            "XF86AudioPlay",
+            "super+xf86copy",
+            "slash",
+        ] {
+            let combo = parse_single_combo(s).unwrap_or_else(|| panic!("{} doesn't parse", s));
+            assert_round_trip(&combo);
+        }
+    }
+
+    #[test]
+    fn iso_left_tab_matches_shift_tab() {
+        let mut kb = HashMap::new();
+        kb.insert(Action::Prev, parse_key_combos("shift+tab"));
+        let shift = gdk4::ModifierType::SHIFT_MASK;
+        let iso = gdk4::Key::ISO_Left_Tab;
+        assert_eq!(match_action(&kb, iso, shift), Some(Action::Prev));
+        assert_eq!(
+            match_action(&kb, iso, gdk4::ModifierType::empty()),
+            Some(Action::Prev)
+        );
+        assert_eq!(
+            match_action(&kb, gdk4::Key::Tab, gdk4::ModifierType::empty()),
+            None
+        );
+    }
+
+    #[test]
+    fn text_editing_keys() {
+        let none = gdk4::ModifierType::empty();
+        let ctrl = gdk4::ModifierType::CONTROL_MASK;
+        let shift = gdk4::ModifierType::SHIFT_MASK;
+        assert!(is_text_editing_key(gdk4::Key::Home, none));
+        assert!(is_text_editing_key(gdk4::Key::KP_Left, ctrl | shift));
+        assert!(is_text_editing_key(gdk4::Key::a, ctrl));
+        assert!(!is_text_editing_key(gdk4::Key::a, none));
+        assert!(!is_text_editing_key(
+            gdk4::Key::End,
+            gdk4::ModifierType::ALT_MASK
+        ));
+        assert!(!is_text_editing_key(gdk4::Key::Up, none));
+    }
+
+    fn key_of(s: &str) -> Option<gdk4::Key> {
+        parse_single_combo(s).map(|c| c.key)
+    }
+
+    #[test]
+    fn f_keys_and_media_keys() {
+        assert_eq!(key_of("f5"), Some(gdk4::Key::F5));
+        assert_eq!(key_of("shift+F12"), Some(gdk4::Key::F12));
+        assert_eq!(key_of("XF86AudioPlay"), Some(gdk4::Key::AudioPlay));
+        assert_eq!(key_of("xf86audioplay"), Some(gdk4::Key::AudioPlay));
+        assert_eq!(key_of("xf86copy"), Some(gdk4::Key::Copy));
+        assert_eq!(key_of("xf86nosuchkey"), None);
+    }
+
+    #[test]
+    fn keypad_and_editing_key_names() {
+        assert_eq!(key_of("kp_5"), Some(gdk4::Key::KP_5));
+        assert_eq!(key_of("KP_Enter"), Some(gdk4::Key::KP_Enter));
+        assert_eq!(key_of("kp_plus"), Some(gdk4::Key::KP_Add));
+        assert_eq!(key_of("ins"), Some(gdk4::Key::Insert));
+        assert_eq!(key_of("print_screen"), Some(gdk4::Key::Print));
+        assert_eq!(key_of("pause"), Some(gdk4::Key::Pause));
+    }
+
+    #[test]
+    fn unknown_modifier_is_rejected() {
+        assert!(parse_single_combo("ctlr+d").is_none());
+        assert!(parse_single_combo("+d").is_none());
+        assert_eq!(parse_key_combos("ctlr+d ctrl+d").len(), 1);
+    }
+}
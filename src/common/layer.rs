@@ -1,12 +1,19 @@
+use gtk4::prelude::*;
 use gtk4::ApplicationWindow;
 use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
 use std::process::Command;
 
-use crate::config::{Anchor, ConfigBase};
+use crate::config::{Anchor, ConfigBase, WindowLayer};
+use crate::logging::log;
 
 pub fn apply_layer_shell(window: &ApplicationWindow, cfg: &ConfigBase, namespace: &str) {
     window.init_layer_shell();
-    window.set_layer(Layer::Overlay);
+    window.set_layer(match cfg.layer {
+        WindowLayer::Background => Layer::Background,
+        WindowLayer::Bottom => Layer::Bottom,
+        WindowLayer::Top => Layer::Top,
+        WindowLayer::Overlay => Layer::Overlay,
+    });
     window.set_keyboard_mode(KeyboardMode::Exclusive);
     window.set_namespace(namespace);
 
@@ -35,14 +42,17 @@ pub fn apply_layer_shell(window: &ApplicationWindow, cfg: &ConfigBase, namespace
             window.set_anchor(Edge::Right, true);
         }
         Anchor::Cursor => {
-            let (cx, cy) = get_cursor_position();
             window.set_anchor(Edge::Top, true);
             window.set_anchor(Edge::Left, true);
-            window.set_margin(Edge::Top, cy);
-            window.set_margin(Edge::Left, cx);
+            update_cursor_position(window, cfg, namespace);
         }
     }
 
+    match cfg.exclusive_zone {
+        -1 => window.auto_exclusive_zone_enable(),
+        zone => window.set_exclusive_zone(zone),
+    }
+
     if cfg.margin_top != 0 {
         window.set_margin(Edge::Top, cfg.margin_top);
     }
@@ -67,8 +77,48 @@ pub fn get_cursor_position() -> (i32, i32) {
     (0, 0)
 }
 
-pub fn update_cursor_position(window: &gtk4::Window) {
+/// Offset of a window `size` long at `pos` within a monitor span starting
+/// at `origin`, pulled back so it ends on screen. A window bigger than the
+/// monitor sticks to its start.
+fn clamp_to_monitor(pos: i32, size: i32, origin: i32, extent: i32) -> i32 {
+    (pos - origin).min(extent - size).max(0)
+}
+
+/// Move a cursor-anchored window to the cursor, on the monitor under it.
+/// Near a screen edge the window is shifted back so none of it (at the
+/// configured size or the size it has now, whichever is bigger) is cut off.
+pub fn update_cursor_position(window: &impl IsA<gtk4::Window>, cfg: &ConfigBase, app_name: &str) {
     let (cx, cy) = get_cursor_position();
-    window.set_margin(Edge::Top, cy);
-    window.set_margin(Edge::Left, cx);
+    let monitor = gtk4::gdk::Display::default().and_then(|display| {
+        let monitors = display.monitors();
+        (0..monitors.n_items())
+            .filter_map(|i| monitors.item(i).and_downcast::<gtk4::gdk::Monitor>())
+            .find(|m| m.geometry().contains_point(cx, cy))
+    });
+    let Some(monitor) = monitor else {
+        window.set_margin(Edge::Top, cy);
+        window.set_margin(Edge::Left, cx);
+        return;
+    };
+
+    let geom = monitor.geometry();
+    let width = cfg.width.max(window.as_ref().width());
+    let height = cfg.height.max(window.as_ref().height());
+    let left = clamp_to_monitor(cx, width, geom.x(), geom.width());
+    let top = clamp_to_monitor(cy, height, geom.y(), geom.height());
+    if (left, top) != (cx - geom.x(), cy - geom.y()) {
+        log(
+            app_name,
+            &format!(
+                "window at cursor ({}, {}) would leave the screen, shifted to ({}, {})",
+                cx,
+                cy,
+                geom.x() + left,
+                geom.y() + top
+            ),
+        );
+    }
+    window.set_monitor(&monitor);
+    window.set_margin(Edge::Top, top);
+    window.set_margin(Edge::Left, left);
 }
@@ -1,24 +1,37 @@
+pub mod anim;
 pub mod cli;
 pub mod config;
 pub mod css;
+pub mod i18n;
+pub mod json;
 pub mod keys;
 pub mod layer;
 pub mod logging;
+pub mod nav;
 pub mod paths;
+pub mod search;
+pub mod selftest;
+pub mod session;
+pub mod theme_preview;
 pub mod vim;
+pub mod wallpaper;
 
 pub use cli::{
     cmd_config, cmd_generate_config, cmd_reload, get_pid, pidfile_path, remove_pid, write_pid,
 };
-pub use config::{parse_anchor, parse_bool, parse_easing, Anchor, ConfigBase, Easing};
-pub use css::{char_truncate, load_css, scroll_to_selected};
+pub use config::{
+    parse_anchor, parse_bool, parse_easing, Anchor, ConfigBase, Density, Easing, InitialSelection,
+    WindowLayer,
+};
+pub use css::{char_truncate, css_provider, load_css, scroll_to_selected, CssSource};
 pub use keys::{
-    key_to_char, match_action, parse_action, parse_key_combos, parse_single_combo, Action,
-    KeyCombo, VimMode,
+    entry_has_focus, is_text_editing_key, key_to_char, match_action, parse_action,
+    parse_key_combos, parse_single_combo, Action, KeyCombo, VimMode,
 };
 pub use layer::apply_layer_shell;
 pub use logging::{log, log_dir, log_path, MAX_LOG_SIZE};
-pub use paths::{builtin_themes, cache_dir, config_dir, get_theme_css, shellexpand};
+pub use nav::{navigate, Nav, NavOptions};
+pub use paths::{builtin_themes, cache_dir, config_dir, expand_env, get_theme_css, shellexpand};
 pub use vim::{
     get_vim_mode, handle_vim_insert_key, handle_vim_normal_key, set_vim_mode, update_mode_display,
     VimAction,
@@ -1,8 +1,34 @@
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::RwLock;
 
+/// Default size at which the log is rotated
 pub const MAX_LOG_SIZE: u64 = 10 * 1024 * 1024;
 
+struct LogSettings {
+    path: Option<PathBuf>,
+    keep: usize,
+    max_size: u64,
+}
+
+static SETTINGS: RwLock<LogSettings> = RwLock::new(LogSettings {
+    path: None,
+    keep: 1,
+    max_size: MAX_LOG_SIZE,
+});
+
+/// Override the log file (None = default location), how many rotated
+/// files to keep, and the size in bytes that triggers rotation
+pub fn configure(path: Option<PathBuf>, keep: usize, max_size: u64) {
+    if let Ok(mut s) = SETTINGS.write() {
+        *s = LogSettings {
+            path,
+            keep,
+            max_size,
+        };
+    }
+}
+
 pub fn log_dir(app_name: &str) -> PathBuf {
     std::env::var("XDG_STATE_HOME")
         .map(PathBuf::from)
@@ -13,16 +39,40 @@ pub fn log_dir(app_name: &str) -> PathBuf {
 }
 
 pub fn log_path(app_name: &str) -> PathBuf {
-    log_dir(app_name).join(format!("{}.log", app_name))
+    SETTINGS
+        .read()
+        .ok()
+        .and_then(|s| s.path.clone())
+        .unwrap_or_else(|| log_dir(app_name).join(format!("{}.log", app_name)))
+}
+
+/// Shift `log.1` -> `log.2` ... and move the live log to `log.1`,
+/// dropping anything past `keep`. `keep == 0` just truncates.
+fn rotate(path: &std::path::Path, keep: usize) {
+    let numbered = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    if keep == 0 {
+        let _ = std::fs::remove_file(path);
+        return;
+    }
+    let _ = std::fs::remove_file(numbered(keep));
+    for n in (1..keep).rev() {
+        let _ = std::fs::rename(numbered(n), numbered(n + 1));
+    }
+    let _ = std::fs::rename(path, numbered(1));
 }
 
 pub fn log(app_name: &str, msg: &str) {
-    let dir = log_dir(app_name);
-    let _ = std::fs::create_dir_all(&dir);
     let path = log_path(app_name);
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let (keep, max_size) = SETTINGS
+        .read()
+        .map(|s| (s.keep, s.max_size))
+        .unwrap_or((1, MAX_LOG_SIZE));
     if let Ok(meta) = std::fs::metadata(&path) {
-        if meta.len() > MAX_LOG_SIZE {
-            let _ = std::fs::rename(&path, dir.join(format!("{}.log.1", app_name)));
+        if meta.len() > max_size {
+            rotate(&path, keep);
         }
     }
     let timestamp = {
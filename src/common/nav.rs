@@ -0,0 +1,277 @@
+use crate::config::{ConfigBase, Easing};
+use crate::css::scroll_to_selected;
+use crate::keys::Action;
+use crate::vim::VimAction;
+use gtk4::prelude::*;
+
+/// List movement shared by the keybind and vim handlers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Nav {
+    Next,
+    Prev,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    First,
+    Last,
+    /// Sideways; only grids move on these
+    Left,
+    Right,
+}
+
+impl Nav {
+    pub fn from_action(action: &Action) -> Option<Self> {
+        match action {
+            Action::Next => Some(Nav::Next),
+            Action::Prev => Some(Nav::Prev),
+            Action::PageDown => Some(Nav::PageDown),
+            Action::PageUp => Some(Nav::PageUp),
+            Action::First => Some(Nav::First),
+            Action::Last => Some(Nav::Last),
+            _ => None,
+        }
+    }
+
+    pub fn from_vim(action: VimAction) -> Option<Self> {
+        match action {
+            VimAction::Down => Some(Nav::Next),
+            VimAction::Up => Some(Nav::Prev),
+            VimAction::Left => Some(Nav::Left),
+            VimAction::Right => Some(Nav::Right),
+            VimAction::HalfPageDown => Some(Nav::HalfPageDown),
+            VimAction::HalfPageUp => Some(Nav::HalfPageUp),
+            VimAction::Top => Some(Nav::First),
+            VimAction::Bottom => Some(Nav::Last),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct NavOptions {
+    pub wrap: bool,
+    pub page_step: u32,
+    pub duration_ms: u64,
+    pub easing: Easing,
+}
+
+impl NavOptions {
+    pub fn from_config(base: &ConfigBase) -> Self {
+        Self {
+            wrap: base.wrap_navigation,
+            page_step: base.page_step,
+            duration_ms: base.animation_duration,
+            easing: base.animation_easing,
+        }
+    }
+}
+
+/// Index `nav` lands on from `current` in a list of `n` rows, `page` rows
+/// per page. `None` when there is nowhere to go.
+pub fn nav_target(current: i32, n: i32, nav: Nav, page: i32, wrap: bool) -> Option<i32> {
+    if n <= 0 {
+        return None;
+    }
+    let last = n - 1;
+    let half = (page / 2).max(1);
+    let target = match nav {
+        Nav::First => 0,
+        Nav::Last => last,
+        Nav::Next if current >= last => return wrap.then_some(0),
+        Nav::Prev if current <= 0 => return wrap.then_some(last),
+        Nav::Next => current + 1,
+        Nav::Prev => current - 1,
+        Nav::PageDown => (current + page).min(last),
+        Nav::PageUp => (current - page).max(0),
+        Nav::HalfPageDown => (current + half).min(last),
+        Nav::HalfPageUp => (current - half).max(0),
+        Nav::Left | Nav::Right => return None,
+    };
+    Some(target)
+}
+
+/// Like `nav_target` for `n` items laid out `cols` to a row: Next/Prev move
+/// a row down/up, Left/Right one item, pages move `page` rows. Moving down
+/// from the row above a short last row lands on its last item.
+pub fn grid_target(
+    current: i32,
+    n: i32,
+    cols: i32,
+    nav: Nav,
+    page: i32,
+    wrap: bool,
+) -> Option<i32> {
+    if n <= 0 {
+        return None;
+    }
+    let last = n - 1;
+    let cols = cols.max(1);
+    let half = (page / 2).max(1);
+    let last_row = last / cols;
+    let row = current / cols;
+    let down = |rows: i32| (current + rows * cols).min(last);
+    let up = |rows: i32| (current - rows * cols).max(current % cols);
+    let target = match nav {
+        Nav::First => 0,
+        Nav::Last => last,
+        Nav::Right if current >= last => return wrap.then_some(0),
+        Nav::Left if current <= 0 => return wrap.then_some(last),
+        Nav::Right => current + 1,
+        Nav::Left => current - 1,
+        Nav::Next if row >= last_row => return wrap.then_some(current % cols),
+        Nav::Prev if row == 0 => return wrap.then_some((last_row * cols + current).min(last)),
+        Nav::Next => down(1),
+        Nav::Prev => up(1),
+        Nav::PageDown => down(page.max(1)),
+        Nav::PageUp => up(page.max(1)),
+        Nav::HalfPageDown => down(half),
+        Nav::HalfPageUp => up(half),
+    };
+    Some(target)
+}
+
+/// Rows a page jump moves: `page_step` when set, else however many rows
+/// fully fit in the scrolled viewport
+pub fn page_rows(listbox: &gtk4::ListBox, scroll: &gtk4::ScrolledWindow, page_step: u32) -> i32 {
+    let row_height = listbox
+        .selected_row()
+        .or_else(|| listbox.row_at_index(0))
+        .map(|r| r.height())
+        .unwrap_or(0);
+    rows_per_page(row_height, scroll, page_step)
+}
+
+/// `page_rows` for any layout, given the height of one row
+pub fn rows_per_page(row_height: i32, scroll: &gtk4::ScrolledWindow, page_step: u32) -> i32 {
+    if page_step > 0 {
+        return page_step as i32;
+    }
+    if row_height <= 0 {
+        return 10;
+    }
+    ((scroll.vadjustment().page_size() / row_height as f64).floor() as i32).max(1)
+}
+
+/// Move the selection and scroll to it. With nothing selected (see
+/// `initial_selection = none`), any move except Last lands on the first row.
+pub fn navigate(
+    listbox: &gtk4::ListBox,
+    scroll: &gtk4::ScrolledWindow,
+    nav: Nav,
+    opts: &NavOptions,
+) {
+    let (current, nav) = match listbox.selected_row() {
+        Some(r) => (r.index(), nav),
+        None if nav == Nav::Last => (0, Nav::Last),
+        None => (0, Nav::First),
+    };
+    let n = listbox.observe_children().n_items() as i32;
+    let page = match nav {
+        Nav::PageDown | Nav::PageUp | Nav::HalfPageDown | Nav::HalfPageUp => {
+            page_rows(listbox, scroll, opts.page_step)
+        }
+        _ => 0,
+    };
+    let Some(target) = nav_target(current, n, nav, page, opts.wrap) else {
+        return;
+    };
+    if let Some(row) = listbox.row_at_index(target) {
+        listbox.select_row(Some(&row));
+        scroll_to_selected(listbox, scroll, opts.duration_ms, opts.easing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_list_goes_nowhere() {
+        for nav in [Nav::Next, Nav::Prev, Nav::First, Nav::Last, Nav::PageDown] {
+            assert_eq!(nav_target(0, 0, nav, 5, true), None);
+        }
+    }
+
+    #[test]
+    fn next_prev_wrap() {
+        assert_eq!(nav_target(4, 5, Nav::Next, 0, true), Some(0));
+        assert_eq!(nav_target(0, 5, Nav::Prev, 0, true), Some(4));
+        assert_eq!(nav_target(2, 5, Nav::Next, 0, true), Some(3));
+    }
+
+    #[test]
+    fn next_prev_stop_without_wrap() {
+        assert_eq!(nav_target(4, 5, Nav::Next, 0, false), None);
+        assert_eq!(nav_target(0, 5, Nav::Prev, 0, false), None);
+        assert_eq!(nav_target(2, 5, Nav::Prev, 0, false), Some(1));
+    }
+
+    #[test]
+    fn pages_clamp_to_the_ends() {
+        assert_eq!(nav_target(2, 20, Nav::PageDown, 5, false), Some(7));
+        assert_eq!(nav_target(17, 20, Nav::PageDown, 5, true), Some(19));
+        assert_eq!(nav_target(7, 20, Nav::PageUp, 5, false), Some(2));
+        assert_eq!(nav_target(3, 20, Nav::PageUp, 5, true), Some(0));
+        assert_eq!(nav_target(3, 20, Nav::HalfPageDown, 5, false), Some(5));
+    }
+
+    #[test]
+    fn first_last() {
+        assert_eq!(nav_target(3, 20, Nav::First, 0, false), Some(0));
+        assert_eq!(nav_target(3, 20, Nav::Last, 0, false), Some(19));
+    }
+
+    // 10 items, 4 to a row:
+    //   0 1 2 3
+    //   4 5 6 7
+    //   8 9
+    #[test]
+    fn grid_next_into_a_short_last_row_lands_on_its_end() {
+        assert_eq!(grid_target(4, 10, 4, Nav::Next, 0, false), Some(8));
+        assert_eq!(grid_target(6, 10, 4, Nav::Next, 0, false), Some(9));
+        assert_eq!(grid_target(7, 10, 4, Nav::Next, 0, false), Some(9));
+        assert_eq!(grid_target(9, 10, 4, Nav::Next, 0, false), None);
+        assert_eq!(grid_target(9, 10, 4, Nav::Next, 0, true), Some(1));
+    }
+
+    #[test]
+    fn grid_prev_wraps_from_the_top_row() {
+        assert_eq!(grid_target(5, 10, 4, Nav::Prev, 0, false), Some(1));
+        assert_eq!(grid_target(1, 10, 4, Nav::Prev, 0, false), None);
+        assert_eq!(grid_target(1, 10, 4, Nav::Prev, 0, true), Some(9));
+        // Column 2 has no item in the last row, so its end is the last item
+        assert_eq!(grid_target(2, 10, 4, Nav::Prev, 0, true), Some(9));
+        assert_eq!(grid_target(0, 10, 4, Nav::Prev, 0, true), Some(8));
+    }
+
+    #[test]
+    fn grid_left_right_cross_rows_and_wrap_at_the_ends() {
+        assert_eq!(grid_target(3, 10, 4, Nav::Right, 0, false), Some(4));
+        assert_eq!(grid_target(4, 10, 4, Nav::Left, 0, false), Some(3));
+        assert_eq!(grid_target(9, 10, 4, Nav::Right, 0, false), None);
+        assert_eq!(grid_target(9, 10, 4, Nav::Right, 0, true), Some(0));
+        assert_eq!(grid_target(0, 10, 4, Nav::Left, 0, false), None);
+        assert_eq!(grid_target(0, 10, 4, Nav::Left, 0, true), Some(9));
+    }
+
+    #[test]
+    fn grid_pages_clamp() {
+        assert_eq!(grid_target(1, 10, 4, Nav::PageDown, 5, false), Some(9));
+        assert_eq!(grid_target(9, 10, 4, Nav::PageUp, 5, false), Some(1));
+        assert_eq!(grid_target(1, 10, 4, Nav::HalfPageDown, 2, false), Some(5));
+        assert_eq!(grid_target(6, 10, 4, Nav::HalfPageUp, 2, false), Some(2));
+    }
+
+    #[test]
+    fn grid_of_one_column_is_a_list() {
+        for cols in [0, 1] {
+            assert_eq!(grid_target(2, 5, cols, Nav::Next, 0, false), Some(3));
+            assert_eq!(grid_target(2, 5, cols, Nav::Left, 0, false), Some(1));
+            assert_eq!(grid_target(4, 5, cols, Nav::Next, 0, true), Some(0));
+            assert_eq!(grid_target(0, 5, cols, Nav::Prev, 0, true), Some(4));
+            assert_eq!(grid_target(1, 5, cols, Nav::PageDown, 10, false), Some(4));
+        }
+        assert_eq!(grid_target(0, 0, 4, Nav::Next, 0, true), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn config_dir(app_name: &str) -> PathBuf {
     std::env::var("XDG_CONFIG_HOME")
@@ -20,13 +20,78 @@ pub fn cache_dir(app_name: &str) -> PathBuf {
     d
 }
 
+/// `$XDG_RUNTIME_DIR`, which only this user can enter, if it's set to an
+/// existing directory. Callers fall back to `/tmp` with their own modes.
+pub fn runtime_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|d| d.is_absolute() && d.is_dir())
+}
+
+/// Whether `program` is a path or an executable found in `$PATH`
+pub fn in_path(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// Expand a leading `~/` and `$VAR` / `${VAR}` from the environment.
+/// Unset variables are left as written.
 pub fn shellexpand(s: &str) -> String {
+    expand_env(s).0
+}
+
+/// Like `shellexpand`, also returning the names of unset variables
+pub fn expand_env(s: &str) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(s.len());
+    let mut unset = Vec::new();
+    let mut rest = s;
     if let Some(stripped) = s.strip_prefix("~/") {
-        if let Ok(h) = std::env::var("HOME") {
-            return format!("{}/{}", h, stripped);
+        match std::env::var("HOME") {
+            Ok(h) => {
+                out.push_str(&h);
+                out.push('/');
+            }
+            Err(_) => out.push_str("~/"),
+        }
+        rest = stripped;
+    }
+
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, written, tail) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (
+                    &braced[..end],
+                    &rest[pos..pos + end + 3],
+                    &braced[end + 1..],
+                ),
+                None => ("", "$", after),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &rest[pos..pos + end + 1], &after[end..])
+        };
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            out.push_str(written);
+        } else {
+            match std::env::var(name) {
+                Ok(v) => out.push_str(&v),
+                Err(_) => {
+                    out.push_str(written);
+                    unset.push(name.to_string());
+                }
+            }
         }
+        rest = tail;
     }
-    s.to_string()
+    out.push_str(rest);
+    (out, unset)
 }
 
 pub fn themes_dir() -> PathBuf {
@@ -37,15 +102,12 @@ pub fn themes_dir() -> PathBuf {
         .join("themes")
 }
 
+// Generated by build.rs from src/themes/*.css
+include!(concat!(env!("OUT_DIR"), "/themes.rs"));
+
+/// Built-in themes as `(name, css)`, sorted by name
 pub fn builtin_themes() -> Vec<(&'static str, &'static str)> {
-    vec![
-        ("dracula", include_str!("../themes/dracula.css")),
-        ("catppuccin", include_str!("../themes/catppuccin.css")),
-        ("onedark", include_str!("../themes/onedark.css")),
-        ("monokai", include_str!("../themes/monokai.css")),
-        ("material-3", include_str!("../themes/material-3.css")),
-        ("material-you", include_str!("../themes/material-you.css")),
-    ]
+    BUILTIN_THEMES.to_vec()
 }
 
 pub fn get_theme_css(name: &str) -> Option<String> {
@@ -65,8 +127,78 @@ headerbar,
 "#;
     for (n, css) in builtin_themes() {
         if n == name {
+            if crate::css::is_opaque() {
+                return Some(css.to_string());
+            }
             return Some(format!("{}\n{}", transparency, css));
         }
     }
     None
 }
+
+/// User themes: `*.css` files in `~/.config/<app>/themes/`, sorted by name
+pub fn user_themes(app_name: &str) -> Vec<(String, PathBuf)> {
+    let mut themes: Vec<(String, PathBuf)> = std::fs::read_dir(config_dir(app_name).join("themes"))
+        .map(|rd| {
+            rd.flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|e| e == "css"))
+                .filter_map(|p| Some((p.file_stem()?.to_string_lossy().to_string(), p)))
+                .collect()
+        })
+        .unwrap_or_default();
+    themes.sort();
+    themes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_themes_match_src_themes() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../themes");
+        let mut on_disk: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "css"))
+            .map(|p| p.file_stem().unwrap().to_string_lossy().to_string())
+            .collect();
+        on_disk.sort();
+        let builtin: Vec<String> = builtin_themes()
+            .iter()
+            .map(|(n, _)| n.to_string())
+            .collect();
+        assert_eq!(builtin, on_disk);
+        assert!(builtin_themes().iter().all(|(_, css)| !css.is_empty()));
+    }
+
+    #[test]
+    fn expand_env_vars() {
+        std::env::set_var("GUI_COMMON_TEST_DIR", "/srv/x");
+        std::env::remove_var("GUI_COMMON_TEST_UNSET");
+        assert_eq!(shellexpand("$GUI_COMMON_TEST_DIR/a"), "/srv/x/a");
+        assert_eq!(shellexpand("${GUI_COMMON_TEST_DIR}b"), "/srv/xb");
+        let (out, unset) = expand_env("$GUI_COMMON_TEST_UNSET/c");
+        assert_eq!(out, "$GUI_COMMON_TEST_UNSET/c");
+        assert_eq!(unset, ["GUI_COMMON_TEST_UNSET"]);
+    }
+
+    #[test]
+    fn expand_leaves_non_variables() {
+        assert_eq!(
+            shellexpand("cost $5 and ${unterminated"),
+            "cost $5 and ${unterminated"
+        );
+        assert_eq!(shellexpand("trailing $"), "trailing $");
+        assert_eq!(shellexpand("a~/b"), "a~/b");
+    }
+
+    #[test]
+    fn expand_home() {
+        if let Ok(home) = std::env::var("HOME") {
+            assert_eq!(shellexpand("~/x"), format!("{}/x", home));
+        }
+    }
+}
@@ -0,0 +1,32 @@
+/// Whether `query` matches regardless of case: always with `smartcase`
+/// off, otherwise only while it has no uppercase letter
+pub fn ignore_case(query: &str, smartcase: bool) -> bool {
+    !smartcase || !query.chars().any(char::is_uppercase)
+}
+
+/// Substring search under the smartcase rule
+pub fn contains_query(text: &str, query: &str, smartcase: bool) -> bool {
+    if ignore_case(query, smartcase) {
+        text.to_lowercase().contains(&query.to_lowercase())
+    } else {
+        text.contains(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smartcase_on() {
+        assert!(contains_query("Hello World", "hello", true));
+        assert!(contains_query("Hello World", "World", true));
+        assert!(!contains_query("hello world", "World", true));
+    }
+
+    #[test]
+    fn smartcase_off_always_ignores_case() {
+        assert!(ignore_case("World", false));
+        assert!(contains_query("hello world", "WORLD", false));
+    }
+}
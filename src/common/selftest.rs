@@ -0,0 +1,37 @@
+use std::time::{Duration, Instant};
+
+/// Words the synthetic corpus is made of, so matches look like real names
+const WORDS: &[&str] = &[
+    "Visual", "Studio", "Code", "Text", "Editor", "Image", "Viewer", "Terminal", "Music", "Player",
+    "System", "Monitor", "Web", "Browser", "Files", "Manager", "Mail", "Client", "Office",
+    "Writer", "Sound", "Recorder", "Disk", "Usage",
+];
+
+/// Queries run against the corpus: prefixes, acronyms, scattered letters
+/// and one that matches nothing
+pub const QUERIES: &[&str] = &["code", "vsc", "term", "img view", "sysmon", "zzqx"];
+
+/// `f`'s result and how long it took
+pub fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let out = f();
+    (out, start.elapsed())
+}
+
+/// `n` made-up names of three words and a number, the same on every run
+pub fn corpus(n: usize) -> Vec<String> {
+    let word = |i: usize| WORDS[i % WORDS.len()];
+    (0..n)
+        .map(|i| format!("{} {} {} {}", word(i), word(i * 7 + 3), word(i * 13 + 5), i))
+        .collect()
+}
+
+/// One aligned result line: `label`, a count and the time taken
+pub fn report(label: &str, count: usize, elapsed: Duration) {
+    println!(
+        "{:<28} {:>7}  {:>9.3} ms",
+        label,
+        count,
+        elapsed.as_secs_f64() * 1000.0
+    );
+}
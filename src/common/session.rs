@@ -0,0 +1,50 @@
+use crate::config::InitialSelection;
+use gtk4::ListBox;
+use std::cell::RefCell;
+
+thread_local! {
+    static LAST_SEARCH: RefCell<(String, Option<i32>)> = const { RefCell::new((String::new(), None)) };
+}
+
+/// Remember the query and selected row as the window hides
+pub fn save_search(query: &str, selected: Option<i32>) {
+    LAST_SEARCH.with(|s| *s.borrow_mut() = (query.to_string(), selected));
+}
+
+/// Query and row to show on reopen; empty unless `remember_search` is on
+pub fn saved_search(remember: bool) -> (String, Option<i32>) {
+    if !remember {
+        return (String::new(), None);
+    }
+    LAST_SEARCH.with(|s| s.borrow().clone())
+}
+
+/// Row selected when the window last hid
+pub fn last_selected() -> Option<i32> {
+    LAST_SEARCH.with(|s| s.borrow().1)
+}
+
+/// Reselect a remembered row if the list still has it
+pub fn restore_selection(listbox: &ListBox, selected: Option<i32>) {
+    if let Some(row) = selected.and_then(|i| listbox.row_at_index(i)) {
+        listbox.select_row(Some(&row));
+    }
+}
+
+/// Select the starting row for a freshly built list. `Last` reuses the row
+/// selected when the window last hid, but only for an unfiltered list.
+pub fn apply_initial_selection(listbox: &ListBox, initial: InitialSelection, query: &str) {
+    let index = match initial {
+        InitialSelection::None => {
+            listbox.select_row(None::<&gtk4::ListBoxRow>);
+            return;
+        }
+        InitialSelection::Last if query.is_empty() => last_selected()
+            .filter(|&i| listbox.row_at_index(i).is_some())
+            .unwrap_or(0),
+        _ => 0,
+    };
+    if let Some(row) = listbox.row_at_index(index) {
+        listbox.select_row(Some(&row));
+    }
+}
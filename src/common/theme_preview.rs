@@ -0,0 +1,107 @@
+use crate::config::set_config_value;
+use crate::css::{apply_override_css, CssSource};
+use crate::i18n::trf;
+use crate::logging::log;
+use crate::paths::{builtin_themes, get_theme_css, user_themes};
+use gtk4::prelude::*;
+use gtk4::{EventControllerKey, Label, Window};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// `--preview-themes`: cycle themes on real content before picking one
+pub fn set_active(active: bool) {
+    ACTIVE.store(active, Ordering::Relaxed);
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+struct Theme {
+    name: String,
+    /// What goes after `theme =` in the config
+    config_value: String,
+    css: CssSource,
+}
+
+fn all_themes(app_name: &str) -> Vec<Theme> {
+    let mut themes: Vec<Theme> = builtin_themes()
+        .into_iter()
+        .filter_map(|(name, _)| {
+            Some(Theme {
+                name: name.to_string(),
+                config_value: name.to_string(),
+                css: CssSource::Builtin(get_theme_css(name)?),
+            })
+        })
+        .collect();
+    themes.extend(user_themes(app_name).into_iter().map(|(name, path)| Theme {
+        config_value: path.to_string_lossy().to_string(),
+        css: CssSource::File(path),
+        name,
+    }));
+    themes
+}
+
+fn show(app_name: &str, status: &Label, themes: &[Theme], index: usize) {
+    let t = &themes[index];
+    apply_override_css(app_name, &t.css);
+    status.set_text(&trf(
+        "Theme {}/{}: {}  (Left/Right to cycle, Enter to keep)",
+        &[&(index + 1), &themes.len(), &t.name],
+    ));
+}
+
+/// Hook theme cycling into `window`. Left/Right switch themes live, Enter
+/// writes the shown theme to `[style] theme` and closes. Must be attached
+/// before the app's own key controller so it sees keys first.
+pub fn attach(window: &Window, app_name: &'static str, status: &Label, current: &str) {
+    let themes = Rc::new(all_themes(app_name));
+    if themes.is_empty() {
+        return;
+    }
+    let start = themes
+        .iter()
+        .position(|t| t.config_value == current)
+        .unwrap_or(0);
+    let index = Rc::new(Cell::new(start));
+    show(app_name, status, &themes, start);
+
+    let key_ctrl = EventControllerKey::new();
+    key_ctrl.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    let status = status.clone();
+    let win = window.clone();
+    key_ctrl.connect_key_pressed(move |_, key, _, _| {
+        let n = themes.len();
+        match key {
+            gdk4::Key::Right | gdk4::Key::Left => {
+                let i = if key == gdk4::Key::Right {
+                    (index.get() + 1) % n
+                } else {
+                    (index.get() + n - 1) % n
+                };
+                index.set(i);
+                show(app_name, &status, &themes, i);
+                glib::Propagation::Stop
+            }
+            gdk4::Key::Return | gdk4::Key::KP_Enter => {
+                let t = &themes[index.get()];
+                match set_config_value(app_name, "style", "theme", &t.config_value) {
+                    Ok(()) => {
+                        log(app_name, &format!("theme set to {}", t.name));
+                        println!("Saved theme = {}", t.config_value);
+                        println!("Run '{} --reload' to apply it to the daemon", app_name);
+                    }
+                    Err(e) => eprintln!("Failed to save theme: {}", e),
+                }
+                win.set_visible(false);
+                glib::Propagation::Stop
+            }
+            _ => glib::Propagation::Proceed,
+        }
+    });
+    window.add_controller(key_ctrl);
+}
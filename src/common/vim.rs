@@ -1,3 +1,4 @@
+use crate::i18n::tr;
 use crate::keys::{key_to_char, VimMode};
 use gtk4::prelude::*;
 use gtk4::Label;
@@ -15,6 +16,9 @@ pub enum VimAction {
     Close,
     Down,
     Up,
+    /// `h`/`l`, sideways in grid layouts
+    Left,
+    Right,
     Top,
     Bottom,
     HalfPageDown,
@@ -35,12 +39,12 @@ pub fn get_vim_mode() -> VimMode {
 pub fn update_mode_display(label: &Label, mode: VimMode) {
     match mode {
         VimMode::Normal => {
-            label.set_text("NORMAL");
+            label.set_text(tr("NORMAL"));
             label.remove_css_class("vim-mode-insert");
             label.add_css_class("vim-mode-normal");
         }
         VimMode::Insert => {
-            label.set_text("INSERT");
+            label.set_text(tr("INSERT"));
             label.remove_css_class("vim-mode-normal");
             label.add_css_class("vim-mode-insert");
         }
@@ -78,6 +82,14 @@ pub fn handle_vim_normal_key(
                 LAST_KEY.with(|k| *k.borrow_mut() = None);
                 return Some(VimAction::Up);
             }
+            'h' => {
+                LAST_KEY.with(|k| *k.borrow_mut() = None);
+                return Some(VimAction::Left);
+            }
+            'l' => {
+                LAST_KEY.with(|k| *k.borrow_mut() = None);
+                return Some(VimAction::Right);
+            }
             'g' => {
                 let last = LAST_KEY.with(|k| *k.borrow());
                 if last == Some('g') {
@@ -0,0 +1,94 @@
+use std::path::PathBuf;
+
+fn home_cache() -> PathBuf {
+    std::env::var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            PathBuf::from(std::env::var("HOME").unwrap_or("/tmp".into())).join(".cache")
+        })
+}
+
+/// Where pywal and matugen usually write their GTK-friendly colors
+fn candidates() -> Vec<PathBuf> {
+    let cache = home_cache();
+    vec![
+        cache.join("wal/colors.css"),
+        cache.join("matugen/colors.css"),
+        crate::paths::config_dir("matugen").join("colors.css"),
+        crate::paths::config_dir("gtk-4.0").join("colors.css"),
+    ]
+}
+
+/// Color names and values from `--name: value;` (pywal) and
+/// `@define-color name value;` (matugen) lines
+pub fn parse_colors(css: &str) -> Vec<(String, String)> {
+    let mut colors = Vec::new();
+    for line in css.lines() {
+        let t = line.trim().trim_end_matches(';').trim();
+        let pair = if let Some(rest) = t.strip_prefix("--") {
+            rest.split_once(':')
+        } else if let Some(rest) = t.strip_prefix("@define-color") {
+            rest.trim().split_once(char::is_whitespace)
+        } else {
+            None
+        };
+        if let Some((name, value)) = pair {
+            let name = name.trim().replace('-', "_");
+            let value = value.trim();
+            if !name.is_empty() && looks_like_color(value) {
+                colors.push((name, value.to_string()));
+            }
+        }
+    }
+    colors
+}
+
+/// Skip non-color entries like pywal's `--wallpaper: url(...)`
+fn looks_like_color(value: &str) -> bool {
+    ["#", "rgb", "hsl", "@", "alpha(", "shade(", "mix("]
+        .iter()
+        .any(|p| value.starts_with(p))
+}
+
+fn pick<'a>(colors: &'a [(String, String)], names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|n| colors.iter().find(|(k, _)| k == n).map(|(_, v)| v.as_str()))
+}
+
+/// The first wallpaper palette found, as `@define-color`s plus rules that
+/// point the main surfaces at them
+pub fn wallpaper_css() -> Option<(PathBuf, String)> {
+    let (path, colors) = candidates().into_iter().find_map(|p| {
+        let colors = parse_colors(&std::fs::read_to_string(&p).ok()?);
+        (!colors.is_empty()).then_some((p, colors))
+    })?;
+
+    let mut css = String::new();
+    for (name, value) in &colors {
+        css.push_str(&format!("@define-color {} {};\n", name, value));
+    }
+    let bg = pick(&colors, &["background", "surface", "color0"]);
+    let fg = pick(&colors, &["foreground", "on_surface", "color7"]);
+    let accent = pick(&colors, &["primary", "color4", "accent"]);
+    if let Some(bg) = bg {
+        css.push_str(&format!(
+            ".launch-container,\n.clip-container {{\n  background-color: alpha({}, 0.92);\n}}\n",
+            bg
+        ));
+    }
+    if let Some(fg) = fg {
+        css.push_str(&format!(
+            ".launch-search,\n.clip-search,\n.launch-title,\n.clip-title {{\n  color: {};\n}}\n",
+            fg
+        ));
+    }
+    if let Some(accent) = accent {
+        css.push_str(&format!(
+            ".launch-search:focus,\n.clip-search:focus {{\n  border-color: alpha({0}, 0.6);\n}}\n\
+             .launch-list row:selected,\n.clip-list row:selected {{\n  background-color: alpha({0}, 0.15);\n}}\n",
+            accent
+        ));
+    }
+    Some((path, css))
+}
@@ -1,32 +1,37 @@
 use std::cell::RefCell;
-use std::process::Command;
 use std::rc::Rc;
 
 use gdk4::prelude::*;
 use gtk4::prelude::*;
 use gtk4::{
-    Align, Application, ApplicationWindow, Box as GtkBox, CssProvider, Entry, EventControllerKey,
-    Label, ListBox, Orientation, ScrolledWindow,
+    Align, Application, ApplicationWindow, Box as GtkBox, Entry, EventControllerKey, Label,
+    ListBox, Orientation, ScrolledWindow,
 };
 
 use common::{
-    config::Easing,
-    css::load_css,
-    keys::match_action,
+    anim::{self, is_expanded, set_expanded},
+    cli::{take_query_request, take_window_request, WindowRequest},
+    css::{css_provider, load_css, CssSource},
+    i18n::{tr, trf},
+    keys::{match_action, status_hints},
     layer::{apply_layer_shell, update_cursor_position},
     logging::log,
+    nav::{navigate, Nav, NavOptions},
+    session,
     vim::{
         get_vim_mode, handle_vim_insert_key, handle_vim_normal_key, set_vim_mode,
         update_mode_display,
     },
-    Anchor, VimAction, VimMode,
+    Action, Anchor, VimAction, VimMode,
 };
 
-use crate::calc::calc_eval;
-use crate::config::{default_css, Config, APP_NAME};
-use crate::desktop::{launch_app, load_entries, DesktopEntry};
-use crate::search::get_filtered_entry;
-use crate::ui::populate_list;
+use crate::calc::copy_calc_result;
+use crate::config::{default_css, Config, TerminalConfig, APP_NAME};
+use crate::desktop::{launch_app, load_entries, run_entry, DesktopEntry};
+use crate::files::{descend_query, is_file_query, open_file};
+use crate::search::MAX_ROWS;
+use crate::ssh::ssh_filter;
+use crate::ui::{listed_entry, listed_file, populate_list};
 
 pub struct AppWidgets {
     pub search: Entry,
@@ -40,148 +45,55 @@ pub struct AppWidgets {
     pub entries: Rc<RefCell<Vec<DesktopEntry>>>,
 }
 
+/// Footer hints, shown with whatever key each action is bound to
+const STATUS_HINTS: &[(Action, &str)] = &[
+    (Action::Select, "launch"),
+    (Action::SelectToggleTerminal, "terminal"),
+];
+/// Vim normal-mode keys, shown before the bound actions in vim mode
+const VIM_HINTS: &[(&str, &str)] = &[("i", "insert"), ("j/k", "nav")];
+/// Query prefixes rather than keybinds, so never rebound
+const PREFIX_HINTS: &[(&str, &str)] = &[("=", "calc"), ("~", "files")];
+
 thread_local! {
     pub static WIDGETS: RefCell<Option<AppWidgets>> = const { RefCell::new(None) };
     pub static CONFIG: RefCell<Config> = RefCell::new(Config::default());
-    pub static EXPANDED: RefCell<bool> = const { RefCell::new(false) };
-    pub static ANIMATION_GEN: RefCell<u64> = const { RefCell::new(0) };
-}
-
-fn set_expanded(expanded: bool) {
-    EXPANDED.with(|e| *e.borrow_mut() = expanded);
-}
-
-fn is_expanded() -> bool {
-    EXPANDED.with(|e| *e.borrow())
 }
 
-fn next_animation_gen() -> u64 {
-    ANIMATION_GEN.with(|g| {
-        let mut gen = g.borrow_mut();
-        *gen = gen.wrapping_add(1);
-        *gen
-    })
-}
-
-fn current_animation_gen() -> u64 {
-    ANIMATION_GEN.with(|g| *g.borrow())
-}
-
-/// Animate height transition
-#[allow(clippy::too_many_arguments)]
-fn animate_height(
-    container: &GtkBox,
-    scroll: &ScrolledWindow,
-    section_label: &Label,
-    status_bar: &GtkBox,
-    from_height: i32,
-    to_height: i32,
-    duration_ms: u64,
-    easing: Easing,
-    expanding: bool,
-) {
-    // Get a new generation for this animation - any previous animation callbacks
-    // will see their generation is stale and stop
-    let gen = next_animation_gen();
-
-    let steps = 20;
-    let step_ms = duration_ms / steps;
-
-    // Update CSS classes immediately
-    if expanding {
-        container.remove_css_class("collapsed");
-        container.add_css_class("expanded");
-        scroll.set_visible(true);
-        section_label.set_visible(true);
-        status_bar.set_visible(true);
-    } else {
-        container.remove_css_class("expanded");
-        container.add_css_class("collapsed");
-    }
-
-    let container = container.clone();
-    let scroll = scroll.clone();
-    let section_label = section_label.clone();
-    let status_bar = status_bar.clone();
-    let step = Rc::new(std::cell::Cell::new(0u64));
-    let step_clone = step.clone();
-
-    let width = container.width();
-
-    glib::timeout_add_local(std::time::Duration::from_millis(step_ms), move || {
-        // Check if this animation is still current - if not, stop it
-        if current_animation_gen() != gen {
-            return glib::ControlFlow::Break;
-        }
-
-        let s = step_clone.get() + 1;
-        step_clone.set(s);
-
-        let t = s as f64 / steps as f64;
-        let eased = easing.apply(t);
-        let current = from_height as f64 + (to_height - from_height) as f64 * eased;
-
-        container.set_size_request(width, current as i32);
-
-        if s >= steps {
-            container.set_size_request(width, to_height);
-
-            // Hide elements after collapse animation completes
-            // Only do this if we're still the current animation
-            if !expanding && current_animation_gen() == gen {
-                scroll.set_visible(false);
-                section_label.set_visible(false);
-                status_bar.set_visible(false);
-            }
-
-            glib::ControlFlow::Break
-        } else {
-            glib::ControlFlow::Continue
-        }
-    });
+/// Widgets hidden while the launcher is collapsed to the search bar
+fn collapsible(wg: &AppWidgets) -> Vec<gtk4::Widget> {
+    vec![
+        wg.scroll.clone().upcast(),
+        wg.section_label.clone().upcast(),
+        wg.status_bar.clone().upcast(),
+    ]
 }
 
 fn expand(cfg: &Config) {
-    if is_expanded() {
-        return;
-    }
-    set_expanded(true);
-
     WIDGETS.with(|w| {
         if let Some(ref wg) = *w.borrow() {
-            animate_height(
+            anim::expand(
                 &wg.container,
-                &wg.scroll,
-                &wg.section_label,
-                &wg.status_bar,
+                &collapsible(wg),
                 cfg.search_height,
                 cfg.base.height,
-                cfg.animation_duration,
-                cfg.animation_easing,
-                true,
+                cfg.base.animation_duration,
+                cfg.base.animation_easing,
             );
         }
     });
 }
 
 fn collapse(cfg: &Config) {
-    if !is_expanded() {
-        return;
-    }
-    set_expanded(false);
-
     WIDGETS.with(|w| {
         if let Some(ref wg) = *w.borrow() {
-            animate_height(
+            anim::collapse(
                 &wg.container,
-                &wg.scroll,
-                &wg.section_label,
-                &wg.status_bar,
-                cfg.base.height,
+                &collapsible(wg),
                 cfg.search_height,
-                cfg.animation_duration,
-                cfg.animation_easing,
-                false,
+                cfg.base.height,
+                cfg.base.animation_duration,
+                cfg.base.animation_easing,
             );
         }
     });
@@ -199,11 +111,15 @@ pub fn activate(app: &Application) {
     set_expanded(false);
 
     if let Some(win) = app.active_window() {
-        if win.is_visible() {
+        if win.is_visible() && !win.is_active() {
+            // Open but unfocused: take focus back instead of hiding
+            win.present();
+            focus_input(cfg.vim_mode);
+        } else if win.is_visible() {
             win.set_visible(false);
         } else {
             if cfg.base.anchor == Anchor::Cursor {
-                update_cursor_position(&win);
+                update_cursor_position(&win, &cfg.base, APP_NAME);
             }
 
             if cfg.vim_mode {
@@ -215,19 +131,26 @@ pub fn activate(app: &Application) {
 
             WIDGETS.with(|w| {
                 if let Some(ref wg) = *w.borrow() {
-                    // let ents = wg.entries.borrow();
-                    let mut ents = wg.entries.borrow_mut();
-                    *ents = load_entries();
-                    let _ = populate_list(&wg.listbox, &ents, "", cfg.calculator);
-                    wg.status.set_text(&format!("{} apps", ents.len()));
-                    wg.search.set_text("");
+                    let (query, selected) = session::saved_search(cfg.base.remember_search);
+                    {
+                        let mut ents = wg.entries.borrow_mut();
+                        *ents = load_entries();
+                        let n = populate_list(&wg.listbox, &ents, &query, &cfg);
+                        wg.status.set_text(&count_status("{} apps", n));
+                    }
+                    wg.search.set_text(&query);
 
                     // Start collapsed
-                    wg.container
-                        .set_size_request(cfg.base.width, cfg.search_height);
-                    wg.scroll.set_visible(false);
-                    wg.section_label.set_visible(false);
-                    wg.status_bar.set_visible(false);
+                    anim::reset_collapsed(
+                        &wg.container,
+                        &collapsible(wg),
+                        cfg.base.width,
+                        cfg.search_height,
+                    );
+                    if !query.is_empty() {
+                        expand(&cfg);
+                    }
+                    session::restore_selection(&wg.listbox, selected);
 
                     if cfg.vim_mode {
                         update_mode_display(&wg.mode_label, VimMode::Normal);
@@ -243,22 +166,22 @@ pub fn activate(app: &Application) {
         return;
     }
 
-    let css_content = if let Ok(theme) = std::env::var("GUI_THEME_OVERRIDE") {
-        common::paths::get_theme_css(&theme)
-            .unwrap_or_else(|| load_css(APP_NAME, &cfg.base.theme, default_css()))
-    } else if !cfg.base.theme.contains('/') && !cfg.base.theme.ends_with(".css") {
-        common::paths::get_theme_css(&cfg.base.theme).unwrap_or_else(|| default_css().to_string())
-    } else {
-        load_css(APP_NAME, &cfg.base.theme, default_css())
+    common::css::apply_background(cfg.base.opaque_background.as_deref());
+    let css_source = match std::env::var("GUI_THEME_OVERRIDE")
+        .ok()
+        .and_then(|t| common::paths::get_theme_css(&t))
+    {
+        Some(css) => CssSource::Builtin(css),
+        None => load_css(APP_NAME, &cfg.base.theme, default_css()),
     };
 
-    let provider = CssProvider::new();
-    provider.load_from_data(&css_content);
+    let provider = css_provider(APP_NAME, &css_source);
     gtk4::style_context_add_provider_for_display(
         &gdk4::Display::default().expect("no display"),
         &provider,
         gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
     );
+    common::css::apply_wallpaper_colors(APP_NAME, cfg.base.follow_wallpaper);
 
     let entries: Rc<RefCell<Vec<DesktopEntry>>> = Rc::new(RefCell::new(Vec::new()));
 
@@ -284,7 +207,7 @@ pub fn activate(app: &Application) {
     let search_row = GtkBox::new(Orientation::Horizontal, 8);
     search_row.add_css_class("launch-search-row");
     let search = Entry::new();
-    search.set_placeholder_text(Some("Search applications..."));
+    search.set_placeholder_text(Some(tr(&cfg.search_placeholder)));
     search.add_css_class("launch-search");
     search.set_hexpand(true);
     search_row.append(&search);
@@ -294,7 +217,7 @@ pub fn activate(app: &Application) {
     let esc_badge = Label::new(Some("esc"));
     esc_badge.add_css_class("launch-esc-badge");
     hint_box.append(&esc_badge);
-    let hint_text = Label::new(Some("to close"));
+    let hint_text = Label::new(Some(tr("to close")));
     hint_text.add_css_class("launch-hint-text");
     hint_box.append(&hint_text);
     search_row.append(&hint_box);
@@ -303,7 +226,7 @@ pub fn activate(app: &Application) {
     container.append(&search_wrapper);
 
     // expandable content
-    let section_label = Label::new(Some("Applications"));
+    let section_label = Label::new(Some(tr(&cfg.section_label)));
     section_label.set_xalign(0.0);
     section_label.add_css_class("launch-section-label");
     section_label.set_visible(false); // Start hidden
@@ -338,7 +261,7 @@ pub fn activate(app: &Application) {
     }
     status_bar.append(&mode_label);
 
-    let status = Label::new(Some("0 apps"));
+    let status = Label::new(Some(&trf("{} apps", &[&0])));
     status.add_css_class("launch-status-left");
     status.set_halign(Align::Start);
     status.set_hexpand(true);
@@ -347,28 +270,28 @@ pub fn activate(app: &Application) {
     let hints = GtkBox::new(Orientation::Horizontal, 12);
     hints.set_halign(Align::End);
 
-    if cfg.vim_mode {
-        for (k, h) in [("i", "insert"), ("j/k", "nav"), ("Enter", "launch")] {
-            let b = GtkBox::new(Orientation::Horizontal, 0);
-            let kl = Label::new(Some(k));
-            kl.add_css_class("launch-status-key");
-            b.append(&kl);
-            let hl = Label::new(Some(h));
-            hl.add_css_class("launch-status-hint");
-            b.append(&hl);
-            hints.append(&b);
-        }
+    let bound = status_hints(&cfg.base.keybinds, STATUS_HINTS);
+    let hint_keys: Vec<(String, &str)> = if cfg.vim_mode {
+        VIM_HINTS
+            .iter()
+            .map(|&(k, h)| (k.to_string(), h))
+            .chain(bound)
+            .collect()
     } else {
-        for (k, h) in [("Enter", "launch"), ("=", "calc")] {
-            let b = GtkBox::new(Orientation::Horizontal, 0);
-            let kl = Label::new(Some(k));
-            kl.add_css_class("launch-status-key");
-            b.append(&kl);
-            let hl = Label::new(Some(h));
-            hl.add_css_class("launch-status-hint");
-            b.append(&hl);
-            hints.append(&b);
-        }
+        bound
+            .into_iter()
+            .chain(PREFIX_HINTS.iter().map(|&(k, h)| (k.to_string(), h)))
+            .collect()
+    };
+    for (k, h) in hint_keys {
+        let b = GtkBox::new(Orientation::Horizontal, 0);
+        let kl = Label::new(Some(&k));
+        kl.add_css_class("launch-status-key");
+        b.append(&kl);
+        let hl = Label::new(Some(tr(h)));
+        hl.add_css_class("launch-status-hint");
+        b.append(&hl);
+        hints.append(&b);
     }
     status_bar.append(&hints);
     container.append(&status_bar);
@@ -391,19 +314,30 @@ pub fn activate(app: &Application) {
         }
 
         let ents = entries_f.borrow();
-        let n = populate_list(&listbox_f, &ents, &q, cfg_f.calculator);
+        let n = populate_list(&listbox_f, &ents, &q, &cfg_f);
 
         if q.starts_with('=') {
-            status_f.set_text("Calculator");
+            status_f.set_text(tr("Calculator"));
+        } else if cfg_f.file_search && is_file_query(&q) {
+            status_f.set_text(&count_status("{} files", n));
+        } else if cfg_f.ssh_hosts && ssh_filter(&q).is_some() {
+            status_f.set_text(&count_status("{} hosts", n));
         } else {
-            status_f.set_text(&format!("{} apps", n));
+            status_f.set_text(&count_status("{} apps", n));
+        }
+    });
+
+    // file search walks in the background; refresh when its results are in
+    let search_w = search.downgrade();
+    crate::files::on_walk_done(move || {
+        if let Some(s) = search_w.upgrade() {
+            s.emit_by_name::<()>("changed", &[]);
         }
     });
 
     // keybinds
     let key_ctrl = EventControllerKey::new();
     key_ctrl.set_propagation_phase(gtk4::PropagationPhase::Capture);
-    let ek = entries.clone();
     let lk = listbox.clone();
     let wk = window.clone();
     let sk = search.clone();
@@ -412,8 +346,14 @@ pub fn activate(app: &Application) {
 
     key_ctrl.connect_key_pressed(move |_, key, _, mods| {
         let vim_enabled = CONFIG.with(|c| c.borrow().vim_mode);
+        let (nav_opts, escape_clears) = CONFIG.with(|c| {
+            let c = c.borrow();
+            (NavOptions::from_config(&c.base), c.base.escape_clears_first)
+        });
         let terminal = CONFIG.with(|c| c.borrow().terminal.clone());
-        let calc = CONFIG.with(|c| c.borrow().calculator);
+        let calc = CONFIG.with(|c| c.borrow().calc_options());
+        // Shift+Enter runs the entry with its Terminal flag inverted
+        let flip_terminal = mods.contains(gdk4::ModifierType::SHIFT_MASK);
 
         if vim_enabled {
             let current_mode = get_vim_mode();
@@ -421,18 +361,23 @@ pub fn activate(app: &Application) {
             match current_mode {
                 VimMode::Normal => {
                     if let Some(action) = handle_vim_normal_key(key, mods, false) {
+                        if let Some(nav) = Nav::from_vim(action) {
+                            navigate(&lk, &scroll_k, nav, &nav_opts);
+                            return glib::Propagation::Stop;
+                        }
                         match action {
                             VimAction::Close => {
                                 wk.set_visible(false);
                             }
                             VimAction::Select => {
                                 let q = sk.text().to_string();
+                                let idx = lk.selected_row().map(|r| r.index() as usize);
+                                if choose_file(&sk, &wk, &q, idx) {
+                                    return glib::Propagation::Stop;
+                                }
                                 if let Some(row) = lk.selected_row() {
-                                    let ents = ek.borrow();
-                                    if let Some(e) =
-                                        get_filtered_entry(&ents, &q, row.index() as usize)
-                                    {
-                                        launch_app(&e, &terminal);
+                                    if let Some(e) = listed_entry(row.index() as usize) {
+                                        launch_listed(&e, &q, &terminal, flip_terminal);
                                         wk.set_visible(false);
                                     }
                                 }
@@ -454,58 +399,6 @@ pub fn activate(app: &Application) {
                                     }
                                 }
                             }
-                            VimAction::Down => {
-                                if let Some(r) = lk.selected_row() {
-                                    if let Some(n) = lk.row_at_index(r.index() + 1) {
-                                        lk.select_row(Some(&n));
-                                        common::css::scroll_to_selected(&lk, &scroll_k);
-                                    }
-                                }
-                            }
-                            VimAction::Up => {
-                                if let Some(r) = lk.selected_row() {
-                                    if r.index() > 0 {
-                                        if let Some(p) = lk.row_at_index(r.index() - 1) {
-                                            lk.select_row(Some(&p));
-                                            common::css::scroll_to_selected(&lk, &scroll_k);
-                                        }
-                                    }
-                                }
-                            }
-                            VimAction::Top => {
-                                if let Some(r) = lk.row_at_index(0) {
-                                    lk.select_row(Some(&r));
-                                    common::css::scroll_to_selected(&lk, &scroll_k);
-                                }
-                            }
-                            VimAction::Bottom => {
-                                let n = lk.observe_children().n_items();
-                                if n > 0 {
-                                    if let Some(r) = lk.row_at_index(n as i32 - 1) {
-                                        lk.select_row(Some(&r));
-                                        common::css::scroll_to_selected(&lk, &scroll_k);
-                                    }
-                                }
-                            }
-                            VimAction::HalfPageDown => {
-                                if let Some(r) = lk.selected_row() {
-                                    let t = (r.index() + 10)
-                                        .min(lk.observe_children().n_items() as i32 - 1);
-                                    if let Some(nr) = lk.row_at_index(t) {
-                                        lk.select_row(Some(&nr));
-                                        common::css::scroll_to_selected(&lk, &scroll_k);
-                                    }
-                                }
-                            }
-                            VimAction::HalfPageUp => {
-                                if let Some(r) = lk.selected_row() {
-                                    let t = (r.index() - 10).max(0);
-                                    if let Some(nr) = lk.row_at_index(t) {
-                                        lk.select_row(Some(&nr));
-                                        common::css::scroll_to_selected(&lk, &scroll_k);
-                                    }
-                                }
-                            }
                             VimAction::Delete => {} // Not used in launcher
                             _ => {}
                         }
@@ -529,23 +422,19 @@ pub fn activate(app: &Application) {
                     // Enter in insert mode -> select
                     if key == gdk4::Key::Return {
                         let q = sk.text().to_string();
+                        let idx = lk.selected_row().map(|r| r.index() as usize);
+                        if choose_file(&sk, &wk, &q, idx) {
+                            return glib::Propagation::Stop;
+                        }
 
-                        if calc && q.starts_with('=') {
-                            if let Some(result) = calc_eval(&q[1..]) {
-                                let _ = Command::new("sh")
-                                    .arg("-c")
-                                    .arg(format!("echo -n '{}' | wl-copy", result))
-                                    .spawn();
-                                log(APP_NAME, &format!("copied math result: {}", result));
-                                wk.set_visible(false);
-                                return glib::Propagation::Stop;
-                            }
+                        if calc.is_some_and(|opts| copy_calc_result(&q, opts)) {
+                            wk.set_visible(false);
+                            return glib::Propagation::Stop;
                         }
 
                         if let Some(row) = lk.selected_row() {
-                            let ents = ek.borrow();
-                            if let Some(e) = get_filtered_entry(&ents, &q, row.index() as usize) {
-                                launch_app(&e, &terminal);
+                            if let Some(e) = listed_entry(row.index() as usize) {
+                                launch_listed(&e, &q, &terminal, flip_terminal);
                                 wk.set_visible(false);
                             }
                         }
@@ -557,90 +446,62 @@ pub fn activate(app: &Application) {
             }
         } else {
             // Non-vim mode
+            // Cursor keys edit the query while the search entry has focus
+            if common::keys::is_text_editing_key(key, mods)
+                && common::keys::entry_has_focus(&wk, &sk)
+            {
+                return glib::Propagation::Proceed;
+            }
             let action = CONFIG.with(|c| match_action(&c.borrow().base.keybinds, key, mods));
 
             if let Some(action) = action {
+                if let Some(nav) = Nav::from_action(&action) {
+                    navigate(&lk, &scroll_k, nav, &nav_opts);
+                    return glib::Propagation::Stop;
+                }
                 match action {
                     common::Action::Close => {
-                        wk.set_visible(false);
+                        // Optionally a first Escape only empties the query
+                        if escape_clears && !sk.text().is_empty() {
+                            sk.set_text("");
+                        } else {
+                            wk.set_visible(false);
+                        }
                     }
                     common::Action::Select => {
                         let q = sk.text().to_string();
+                        let idx = lk.selected_row().map(|r| r.index() as usize);
+                        if choose_file(&sk, &wk, &q, idx) {
+                            return glib::Propagation::Stop;
+                        }
 
-                        if calc && q.starts_with('=') {
-                            if let Some(result) = calc_eval(&q[1..]) {
-                                let _ = Command::new("sh")
-                                    .arg("-c")
-                                    .arg(format!("echo -n '{}' | wl-copy", result))
-                                    .spawn();
-                                log(APP_NAME, &format!("copied math result: {}", result));
-                                wk.set_visible(false);
-                                return glib::Propagation::Stop;
-                            }
+                        if calc.is_some_and(|opts| copy_calc_result(&q, opts)) {
+                            wk.set_visible(false);
+                            return glib::Propagation::Stop;
                         }
 
                         if let Some(row) = lk.selected_row() {
-                            let ents = ek.borrow();
-                            if let Some(e) = get_filtered_entry(&ents, &q, row.index() as usize) {
-                                launch_app(&e, &terminal);
+                            if let Some(e) = listed_entry(row.index() as usize) {
+                                launch_listed(&e, &q, &terminal, false);
                                 wk.set_visible(false);
                             }
                         }
                     }
-                    common::Action::ClearSearch => {
-                        sk.set_text("");
-                    }
-                    common::Action::Next => {
-                        if let Some(r) = lk.selected_row() {
-                            if let Some(n) = lk.row_at_index(r.index() + 1) {
-                                lk.select_row(Some(&n));
-                                common::css::scroll_to_selected(&lk, &scroll_k);
-                            }
-                        }
-                    }
-                    common::Action::Prev => {
-                        if let Some(r) = lk.selected_row() {
-                            if r.index() > 0 {
-                                if let Some(p) = lk.row_at_index(r.index() - 1) {
-                                    lk.select_row(Some(&p));
-                                    common::css::scroll_to_selected(&lk, &scroll_k);
-                                }
-                            }
-                        }
-                    }
-                    common::Action::PageDown => {
-                        if let Some(r) = lk.selected_row() {
-                            let t =
-                                (r.index() + 10).min(lk.observe_children().n_items() as i32 - 1);
-                            if let Some(nr) = lk.row_at_index(t) {
-                                lk.select_row(Some(&nr));
-                                common::css::scroll_to_selected(&lk, &scroll_k);
-                            }
+                    common::Action::SelectToggleTerminal => {
+                        let q = sk.text().to_string();
+                        let idx = lk.selected_row().map(|r| r.index() as usize);
+                        if choose_file(&sk, &wk, &q, idx) {
+                            return glib::Propagation::Stop;
                         }
-                    }
-                    common::Action::PageUp => {
-                        if let Some(r) = lk.selected_row() {
-                            let t = (r.index() - 10).max(0);
-                            if let Some(nr) = lk.row_at_index(t) {
-                                lk.select_row(Some(&nr));
-                                common::css::scroll_to_selected(&lk, &scroll_k);
+                        if let Some(row) = lk.selected_row() {
+                            if let Some(e) = listed_entry(row.index() as usize) {
+                                launch_listed(&e, &q, &terminal, true);
+                                wk.set_visible(false);
                             }
                         }
                     }
-                    common::Action::First => {
-                        if let Some(r) = lk.row_at_index(0) {
-                            lk.select_row(Some(&r));
-                            common::css::scroll_to_selected(&lk, &scroll_k);
-                        }
-                    }
-                    common::Action::Last => {
-                        let n = lk.observe_children().n_items();
-                        if n > 0 {
-                            if let Some(r) = lk.row_at_index(n as i32 - 1) {
-                                lk.select_row(Some(&r));
-                                common::css::scroll_to_selected(&lk, &scroll_k);
-                            }
-                        }
+                    common::Action::ClearSearch => {
+                        sk.set_text("");
                     }
                     _ => {}
                 }
@@ -649,27 +510,46 @@ pub fn activate(app: &Application) {
         }
         glib::Propagation::Proceed
     });
+    if common::theme_preview::is_active() {
+        common::theme_preview::attach(window.upcast_ref(), APP_NAME, &status, &cfg.base.theme);
+    }
     window.add_controller(key_ctrl);
 
+    let search_v = search.clone();
+    let listbox_v = listbox.clone();
+    window.connect_visible_notify(move |w| {
+        if !w.is_visible() {
+            session::save_search(
+                &search_v.text(),
+                listbox_v.selected_row().map(|r| r.index()),
+            );
+            if common::cli::is_oneshot() {
+                if let Some(app) = w.application() {
+                    app.quit();
+                }
+            }
+        }
+    });
+
     // click to launch
-    let ec = entries.clone();
     let wc = window.clone();
     let sc = search.clone();
     let cfg_c = cfg.clone();
     listbox.connect_row_activated(move |_, row| {
         let q = sc.text().to_string();
-
-        if cfg_c.calculator && q.starts_with('=') {
-            if let Some(result) = calc_eval(&q[1..]) {
-                let _ = Command::new("wl-copy").arg(&result).spawn();
-                wc.set_visible(false);
-                return;
-            }
+        if choose_file(&sc, &wc, &q, Some(row.index() as usize)) {
+            return;
         }
 
-        let ents = ec.borrow();
-        if let Some(e) = get_filtered_entry(&ents, &q, row.index() as usize) {
-            launch_app(&e, &cfg_c.terminal);
+        if cfg_c
+            .calc_options()
+            .is_some_and(|opts| copy_calc_result(&q, opts))
+        {
+            wc.set_visible(false);
+            return;
+        }
+        if let Some(e) = listed_entry(row.index() as usize) {
+            launch_listed(&e, &q, &cfg_c.terminal, false);
             wc.set_visible(false);
         }
     });
@@ -691,8 +571,8 @@ pub fn activate(app: &Application) {
     {
         let mut ents = entries.borrow_mut();
         *ents = load_entries();
-        let n = populate_list(&listbox, &ents, "", cfg.calculator);
-        status.set_text(&format!("{} apps", n));
+        let n = populate_list(&listbox, &ents, "", &cfg);
+        status.set_text(&count_status("{} apps", n));
     }
 
     window.present();
@@ -702,6 +582,11 @@ pub fn activate(app: &Application) {
     } else {
         search.grab_focus();
     }
+    if let Some(query) = take_query_request(APP_NAME) {
+        show_requested_query(&query, cfg.vim_mode);
+    }
+
+    cfg.base.report_config_problems(APP_NAME);
 
     log(
         APP_NAME,
@@ -712,6 +597,81 @@ pub fn activate(app: &Application) {
     );
 }
 
+/// Status text for `n` matches, e.g. `{} apps`, saying so when only the
+/// first `MAX_ROWS` of them are listed
+fn count_status(msgid: &str, n: usize) -> String {
+    let count = trf(msgid, &[&n]);
+    if n > MAX_ROWS {
+        format!("{} ({})", count, trf("first {} shown", &[&MAX_ROWS]))
+    } else {
+        count
+    }
+}
+
+/// Launch the listed `e`. ssh hosts run without counting as a launch, so
+/// a host named like an app doesn't climb that app's ranking.
+fn launch_listed(e: &DesktopEntry, query: &str, terminal: &TerminalConfig, flip_terminal: bool) {
+    let host = CONFIG.with(|c| c.borrow().ssh_hosts) && ssh_filter(query).is_some();
+    if host {
+        run_entry(e, terminal, flip_terminal);
+    } else {
+        launch_app(e, terminal, flip_terminal);
+    }
+}
+
+/// Enter on a file-search row: browse into a directory, open anything else.
+/// Returns false when `query` isn't a file search.
+fn choose_file(
+    search: &Entry,
+    window: &ApplicationWindow,
+    query: &str,
+    idx: Option<usize>,
+) -> bool {
+    let enabled = CONFIG.with(|c| c.borrow().file_search);
+    if !enabled || !is_file_query(query) {
+        return false;
+    }
+    match idx.and_then(listed_file) {
+        Some(f) if f.is_dir => {
+            search.set_text(&descend_query(query, &f));
+            search.set_position(-1);
+        }
+        Some(f) => {
+            open_file(&f.path);
+            window.set_visible(false);
+        }
+        None => {}
+    }
+    true
+}
+
+/// Focus the list in vim mode, the search entry otherwise
+fn focus_input(vim_mode: bool) {
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            if vim_mode {
+                wg.listbox.grab_focus();
+            } else {
+                wg.search.grab_focus();
+            }
+        }
+    });
+}
+
+/// Put a `--query` in the search box, with the cursor after it rather than
+/// the text selected, so typing adds to it
+fn show_requested_query(query: &str, vim_mode: bool) {
+    WIDGETS.with(|w| {
+        if let Some(ref wg) = *w.borrow() {
+            wg.search.set_text(query);
+            if !vim_mode {
+                wg.search.grab_focus_without_selecting();
+                wg.search.set_position(-1);
+            }
+        }
+    });
+}
+
 pub fn setup_signals(app: &Application) {
     glib::unix_signal_add_local(libc::SIGUSR1, {
         let app = app.clone();
@@ -719,12 +679,25 @@ pub fn setup_signals(app: &Application) {
             let cfg = Config::load();
             CONFIG.with(|c| *c.borrow_mut() = cfg.clone());
 
+            let request = take_window_request(APP_NAME);
+            let requested = take_query_request(APP_NAME);
             if let Some(win) = app.active_window() {
-                if win.is_visible() {
+                let toggle = request == WindowRequest::Toggle;
+                if request == WindowRequest::Close
+                    || (toggle && win.is_visible() && win.is_active())
+                {
                     win.set_visible(false);
+                } else if win.is_visible() {
+                    // `open`, or a toggle while open but unfocused: take
+                    // focus back instead of hiding
+                    win.present();
+                    focus_input(cfg.vim_mode);
+                    if let Some(query) = &requested {
+                        show_requested_query(query, cfg.vim_mode);
+                    }
                 } else {
                     if cfg.base.anchor == Anchor::Cursor {
-                        update_cursor_position(&win);
+                        update_cursor_position(&win, &cfg.base, APP_NAME);
                     }
 
                     if cfg.vim_mode {
@@ -736,21 +709,35 @@ pub fn setup_signals(app: &Application) {
 
                     WIDGETS.with(|w| {
                         if let Some(ref wg) = *w.borrow() {
-                            let ents = wg.entries.borrow();
-                            let _ = populate_list(&wg.listbox, &ents, "", cfg.calculator);
-                            wg.status.set_text(&format!("{} apps", ents.len()));
-                            wg.search.set_text("");
+                            let (query, selected) = match &requested {
+                                Some(query) => (query.clone(), None),
+                                None => session::saved_search(cfg.base.remember_search),
+                            };
+                            {
+                                let ents = wg.entries.borrow();
+                                let n = populate_list(&wg.listbox, &ents, &query, &cfg);
+                                wg.status.set_text(&count_status("{} apps", n));
+                            }
+                            wg.search.set_text(&query);
 
                             // Start collapsed
-                            wg.container
-                                .set_size_request(cfg.base.width, cfg.search_height);
-                            wg.scroll.set_visible(false);
-                            wg.section_label.set_visible(false);
-                            wg.status_bar.set_visible(false);
+                            anim::reset_collapsed(
+                                &wg.container,
+                                &collapsible(wg),
+                                cfg.base.width,
+                                cfg.search_height,
+                            );
+                            if !query.is_empty() {
+                                expand(&cfg);
+                            }
+                            session::restore_selection(&wg.listbox, selected);
 
                             if cfg.vim_mode {
                                 update_mode_display(&wg.mode_label, VimMode::Normal);
                                 wg.listbox.grab_focus();
+                            } else if requested.is_some() {
+                                wg.search.grab_focus_without_selecting();
+                                wg.search.set_position(-1);
                             } else {
                                 wg.search.grab_focus();
                             }
@@ -766,16 +753,33 @@ pub fn setup_signals(app: &Application) {
 
     glib::unix_signal_add_local(libc::SIGUSR2, {
         move || {
+            // `--restart-if-unresponsive` checking we're alive. Signals
+            // coalesce, so a theme request may have arrived with it.
+            let pinged = common::cli::answer_ping(APP_NAME);
+            // `--theme <name>` against a running daemon: switch in place
+            if let Some(theme) = common::cli::take_theme_request(APP_NAME) {
+                match common::paths::get_theme_css(&theme) {
+                    Some(css) => {
+                        common::css::apply_override_css(APP_NAME, &CssSource::Builtin(css));
+                        log(APP_NAME, &format!("switched to theme {}", theme));
+                    }
+                    None => log(APP_NAME, &format!("unknown theme requested: {}", theme)),
+                }
+                return glib::ControlFlow::Continue;
+            }
+            if pinged {
+                return glib::ControlFlow::Continue;
+            }
+
             let cfg = Config::load();
             CONFIG.with(|c| *c.borrow_mut() = cfg.clone());
 
-            let provider = CssProvider::new();
-            provider.load_from_data(&load_css(APP_NAME, &cfg.base.theme, default_css()));
-            gtk4::style_context_add_provider_for_display(
-                &gdk4::Display::default().expect("no display"),
-                &provider,
-                gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+            common::css::apply_background(cfg.base.opaque_background.as_deref());
+            common::css::apply_override_css(
+                APP_NAME,
+                &load_css(APP_NAME, &cfg.base.theme, default_css()),
             );
+            common::css::apply_wallpaper_colors(APP_NAME, cfg.base.follow_wallpaper);
             log(APP_NAME, "config + css reloaded");
             glib::ControlFlow::Continue
         }
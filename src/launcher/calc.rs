@@ -1,43 +1,320 @@
+use crate::config::APP_NAME;
+use crate::desktop::print_selection;
+use common::logging::log;
 use std::io::Write;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
-pub fn calc_eval(expr: &str) -> Option<String> {
+/// Upper bound for `calc_scale`; bc is happy with more, the row isn't
+pub const MAX_CALC_SCALE: u32 = 20;
+
+/// What Enter on a calculator result puts on the clipboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalcCopyFormat {
+    /// Just `4`
+    Result,
+    /// `2+2 = 4`
+    Expression,
+}
+
+pub fn parse_calc_copy_format(s: &str) -> CalcCopyFormat {
+    match s.to_lowercase().as_str() {
+        "expression" | "equation" => CalcCopyFormat::Expression,
+        _ => CalcCopyFormat::Result,
+    }
+}
+
+/// How a numeric result is displayed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalcFormat {
+    /// bc's output as-is
+    Plain,
+    /// Thousands separators: `1,000,000`
+    Grouped,
+    /// Scientific notation outside [SCI_SMALL, SCI_LARGE): `1.5e12`
+    Sci,
+}
+
+pub fn parse_calc_format(s: &str) -> CalcFormat {
+    match s.to_lowercase().as_str() {
+        "grouped" => CalcFormat::Grouped,
+        "sci" | "scientific" => CalcFormat::Sci,
+        _ => CalcFormat::Plain,
+    }
+}
+
+/// Magnitudes `sci` leaves in plain notation
+const SCI_LARGE: f64 = 1e9;
+const SCI_SMALL: f64 = 1e-4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CalcOptions {
+    /// Digits after the decimal point passed to bc as `scale`
+    pub scale: u32,
+    /// Drop trailing zeros (and a bare trailing `.`) from the result
+    pub strip_zeros: bool,
+    pub copy_format: CalcCopyFormat,
+    pub format: CalcFormat,
+}
+
+impl Default for CalcOptions {
+    fn default() -> Self {
+        Self {
+            scale: 4,
+            strip_zeros: true,
+            copy_format: CalcCopyFormat::Result,
+            format: CalcFormat::Plain,
+        }
+    }
+}
+
+/// Map a friendly function name to its `bc -l` builtin
+fn bc_function(name: &str) -> Option<&'static str> {
+    match name {
+        "s" | "sin" => Some("s"),
+        "c" | "cos" => Some("c"),
+        "a" | "atan" | "arctan" => Some("a"),
+        "l" | "ln" => Some("l"),
+        "e" | "exp" => Some("e"),
+        "sqrt" => Some("sqrt"),
+        _ => None,
+    }
+}
+
+/// Validate `expr` and rewrite it for bc. Only numbers, operators, the
+/// whitelisted functions (which must be called) and `pi` get through,
+/// so arbitrary bc statements can't be smuggled in.
+fn translate_expr(expr: &str) -> Option<String> {
+    let mut out = String::with_capacity(expr.len());
+    let mut chars = expr.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_ascii_alphabetic() {
+            let mut ident = String::from(c);
+            while let Some(&n) = chars.peek().filter(|n| n.is_ascii_alphabetic()) {
+                ident.push(n);
+                chars.next();
+            }
+            if ident == "pi" {
+                out.push_str("(4*a(1))");
+                continue;
+            }
+            while chars.peek() == Some(&' ') {
+                chars.next();
+            }
+            if chars.peek() != Some(&'(') {
+                return None;
+            }
+            out.push_str(bc_function(&ident)?);
+        } else if c.is_ascii_digit() || "+-*/.^() ".contains(c) {
+            out.push(c);
+        } else {
+            return None;
+        }
+    }
+    Some(out)
+}
+
+pub fn calc_eval(expr: &str, opts: CalcOptions) -> Option<String> {
     let e = expr.trim().trim_matches('=').to_lowercase();
     if e.is_empty() {
         return None;
     }
 
-    let allowed = |c: char| c.is_ascii_digit() || "+-*/.^() ".contains(c);
-    if !e.chars().all(allowed) {
-        return None;
-    }
+    let e = translate_expr(&e)?;
 
     let mut child = Command::new("bc")
         .arg("-l")
         .env("BC_LINE_LENGTH", "0")
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::null())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
         .spawn()
         .ok()?;
 
     if let Some(mut stdin) = child.stdin.take() {
-        let query = format!("scale=4; {}\n", e);
+        let query = format!("scale={}; {}\n", opts.scale.min(MAX_CALC_SCALE), e);
         let _ = stdin.write_all(query.as_bytes());
     }
 
     let output = child.wait_with_output().ok()?;
     if output.status.success() {
-        let res = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if res.contains('.') {
-            let cleaned = res.trim_end_matches('0').trim_end_matches('.').to_string();
-            if cleaned.is_empty() || cleaned == "-" {
-                return Some("0".to_string());
-            }
-            return Some(cleaned);
+        let mut res = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if opts.strip_zeros {
+            res = strip_zeros(&res);
         }
-        Some(res)
+        Some(format_result(&res, opts.format))
     } else {
         None
     }
 }
+
+/// `0.5000` -> `0.5`, `2.000` -> `2`; integers are left alone
+fn strip_zeros(res: &str) -> String {
+    if !res.contains('.') {
+        return res.to_string();
+    }
+    let res = res.trim_end_matches('0').trim_end_matches('.');
+    if res.is_empty() || res == "-" {
+        "0".to_string()
+    } else {
+        res.to_string()
+    }
+}
+
+/// Split bc output into sign, integer and fraction digits, or None if it
+/// isn't a plain decimal number
+fn split_number(s: &str) -> Option<(&str, &str, &str)> {
+    let (sign, digits) = match s.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", s),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    let all_digits = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+    if (int.is_empty() && frac.is_empty()) || !all_digits(int) || !all_digits(frac) {
+        return None;
+    }
+    Some((sign, int, frac))
+}
+
+/// Reformat a bc result per `calc_format`; anything that doesn't parse as a
+/// number comes back unchanged
+pub fn format_result(raw: &str, format: CalcFormat) -> String {
+    let Some((sign, int, frac)) = split_number(raw) else {
+        return raw.to_string();
+    };
+    match format {
+        CalcFormat::Plain => raw.to_string(),
+        CalcFormat::Grouped => {
+            let int = if int.is_empty() { "0" } else { int };
+            let mut grouped = String::with_capacity(int.len() + int.len() / 3);
+            for (i, c) in int.chars().enumerate() {
+                if i > 0 && (int.len() - i) % 3 == 0 {
+                    grouped.push(',');
+                }
+                grouped.push(c);
+            }
+            if frac.is_empty() {
+                format!("{}{}", sign, grouped)
+            } else {
+                format!("{}{}.{}", sign, grouped, frac)
+            }
+        }
+        CalcFormat::Sci => {
+            let Ok(v) = raw.parse::<f64>() else {
+                return raw.to_string();
+            };
+            let mag = v.abs();
+            if v == 0.0 || (SCI_SMALL..SCI_LARGE).contains(&mag) || !v.is_finite() {
+                return raw.to_string();
+            }
+            // Six significant digits, without trailing zeros in the mantissa
+            let s = format!("{:.5e}", v);
+            match s.split_once('e') {
+                Some((mantissa, exp)) if mantissa.contains('.') => format!(
+                    "{}e{}",
+                    mantissa.trim_end_matches('0').trim_end_matches('.'),
+                    exp
+                ),
+                _ => s,
+            }
+        }
+    }
+}
+
+/// The text copied for `result`, per `copy_format`
+pub fn copy_text(expr: &str, result: &str, format: CalcCopyFormat) -> String {
+    match format {
+        CalcCopyFormat::Result => result.to_string(),
+        CalcCopyFormat::Expression => format!("{} = {}", expr.trim().trim_matches('='), result),
+    }
+}
+
+/// Evaluate a `=expr` query and copy the result through wl-copy's stdin
+/// (or print it under `--print`).
+/// Returns false if `query` isn't a calculation that evaluates.
+pub fn copy_calc_result(query: &str, opts: CalcOptions) -> bool {
+    let Some(expr) = query.strip_prefix('=') else {
+        return false;
+    };
+    let Some(result) = calc_eval(expr, opts) else {
+        return false;
+    };
+    let text = copy_text(expr, &result, opts.copy_format);
+    if print_selection(&text) {
+        return true;
+    }
+    match Command::new("wl-copy")
+        .args(["--type", "text/plain"])
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(mut wl) => {
+            if let Some(mut si) = wl.stdin.take() {
+                let _ = si.write_all(text.as_bytes());
+            }
+            let _ = wl.wait();
+            log(APP_NAME, &format!("copied math result: {}", text));
+        }
+        Err(e) => log(APP_NAME, &format!("wl-copy failed: {}", e)),
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_zeros_after_the_point_only() {
+        assert_eq!(strip_zeros("0.5000"), "0.5");
+        assert_eq!(strip_zeros("2.000"), "2");
+        assert_eq!(strip_zeros("100"), "100");
+        assert_eq!(strip_zeros(".000"), "0");
+        assert_eq!(strip_zeros("-.000"), "0");
+        assert_eq!(strip_zeros("-1.2500"), "-1.25");
+    }
+
+    #[test]
+    fn translate_functions_and_pi() {
+        assert_eq!(translate_expr("sqrt(2)*2").as_deref(), Some("sqrt(2)*2"));
+        assert_eq!(
+            translate_expr("sin (1)+ln(2)").as_deref(),
+            Some("s(1)+l(2)")
+        );
+        assert_eq!(translate_expr("2*pi").as_deref(), Some("2*(4*a(1))"));
+    }
+
+    #[test]
+    fn translate_rejects_other_bc() {
+        assert_eq!(translate_expr("scale=100"), None);
+        assert_eq!(translate_expr("sin"), None);
+        assert_eq!(translate_expr("quit()"), None);
+        assert_eq!(translate_expr("1;2"), None);
+    }
+
+    #[test]
+    fn grouped() {
+        assert_eq!(format_result("1234567", CalcFormat::Grouped), "1,234,567");
+        assert_eq!(format_result("-1234.5", CalcFormat::Grouped), "-1,234.5");
+        assert_eq!(format_result("123", CalcFormat::Grouped), "123");
+        assert_eq!(format_result(".25", CalcFormat::Grouped), "0.25");
+    }
+
+    #[test]
+    fn sci_only_outside_the_plain_range() {
+        assert_eq!(format_result("1500000000000", CalcFormat::Sci), "1.5e12");
+        assert_eq!(format_result(".00001234", CalcFormat::Sci), "1.234e-5");
+        assert_eq!(format_result("123456", CalcFormat::Sci), "123456");
+        assert_eq!(format_result("0", CalcFormat::Sci), "0");
+    }
+
+    #[test]
+    fn non_numbers_pass_through() {
+        assert_eq!(
+            format_result("Runtime error", CalcFormat::Grouped),
+            "Runtime error"
+        );
+        assert_eq!(format_result("-", CalcFormat::Sci), "-");
+        assert_eq!(split_number("1.2.3"), None);
+    }
+}
@@ -1,9 +1,14 @@
+use crate::calc::{parse_calc_copy_format, parse_calc_format, CalcOptions, MAX_CALC_SCALE};
+use crate::desktop::{parse_launch_via, set_launch_via, LaunchVia};
+use crate::search::{set_recent_count, SearchWeights};
 use common::{
-    config::{parse_bool, parse_config_file, parse_easing, Easing},
+    config::{parse_bool, parse_config_with_includes},
+    json::Json,
     logging::log,
     paths::config_dir,
     ConfigBase,
 };
+use std::path::Path;
 
 pub const APP_NAME: &str = "launch-gui";
 
@@ -15,72 +20,283 @@ pub fn default_css() -> &'static str {
     include_str!("style.css")
 }
 
+/// How terminal apps are started
+#[derive(Clone, Debug)]
+pub struct TerminalConfig {
+    pub command: String,
+    /// Flag placed before `sh -c <exec>`, e.g. `-e`, `-x` or `--`
+    pub exec_arg: String,
+    /// Full command line with a `{cmd}` placeholder; overrides the two above
+    pub template: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub base: ConfigBase,
     pub search_height: i32,
-    pub animation_duration: u64,
-    pub animation_easing: Easing,
-    pub terminal: String,
+    pub terminal: TerminalConfig,
+    pub launch_via: LaunchVia,
     pub calculator: bool,
+    pub calc: CalcOptions,
+    pub file_search: bool,
+    pub ssh_hosts: bool,
+    pub search: SearchWeights,
     pub vim_mode: bool,
+    pub show_recent: bool,
+    pub recent_count: usize,
+    pub search_placeholder: String,
+    pub section_label: String,
+    pub recent_label: String,
+    pub all_apps_label: String,
 }
 
+/// cliphist-gui's own keys, as `(section, key)`. The shared file may hold them,
+/// so they aren't reported as unknown here.
+const CLIPHIST_KEYS: &[(&str, &str)] = &[
+    ("window", "collapsed"),
+    ("window", "expand_on_open"),
+    ("window", "open_animation"),
+    ("style", "layout"),
+    ("style", "show_badges"),
+    ("behavior", "auto_paste"),
+    ("behavior", "auto_refresh_ms"),
+    ("behavior", "backend"),
+    ("behavior", "binary_marker"),
+    ("behavior", "close_on_select"),
+    ("behavior", "confirm_delete"),
+    ("behavior", "highlight_code"),
+    ("behavior", "image_copy_format"),
+    ("behavior", "image_popover"),
+    ("behavior", "image_popover_size"),
+    ("behavior", "join_separator"),
+    ("behavior", "live_refresh"),
+    ("behavior", "mask_passwords"),
+    ("behavior", "mask_regex"),
+    ("behavior", "max_age_days"),
+    ("behavior", "max_items"),
+    ("behavior", "notify_on_copy"),
+    ("behavior", "notify_timeout_ms"),
+    ("behavior", "paste_command"),
+    ("behavior", "paste_delay_ms"),
+    ("behavior", "show_preview"),
+    ("behavior", "show_tooltips"),
+    ("behavior", "tab_complete"),
+    ("ui", "badge_binary"),
+    ("ui", "badge_image"),
+    ("ui", "badge_lines"),
+    ("ui", "badge_text"),
+    ("ui", "badge_url"),
+];
+
 impl Config {
     pub fn default() -> Self {
         Self {
             base: ConfigBase::new(APP_NAME, 580, 400),
             search_height: 70,
-            animation_duration: 200,
-            animation_easing: Easing::EaseOut,
-            terminal: "kitty".to_string(),
+            terminal: TerminalConfig {
+                command: "kitty".to_string(),
+                exec_arg: "-e".to_string(),
+                template: String::new(),
+            },
+            launch_via: LaunchVia::Sh,
             calculator: true,
+            calc: CalcOptions::default(),
+            file_search: false,
+            ssh_hosts: true,
+            search: SearchWeights::default(),
             vim_mode: false,
+            show_recent: true,
+            recent_count: 5,
+            search_placeholder: "Search applications...".to_string(),
+            section_label: "Applications".to_string(),
+            recent_label: "Recent".to_string(),
+            all_apps_label: "All apps".to_string(),
         }
     }
 
     pub fn load() -> Self {
         let path = config_dir(APP_NAME).join("config");
-        if !path.exists() {
-            return Self::default();
-        }
-        match std::fs::read_to_string(&path) {
-            Ok(c) => {
-                log(APP_NAME, &format!("loaded config from {}", path.display()));
-                Self::parse(&c)
-            }
+        // A missing file is just an empty one: defaults plus the shared config
+        let content = match std::fs::read_to_string(&path) {
+            Ok(c) => Some(c),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
             Err(e) => {
                 log(APP_NAME, &format!("config read error: {}", e));
-                Self::default()
+                None
             }
+        };
+        let cfg = Self::parse(content.as_deref().unwrap_or(""), &path);
+        cfg.base.apply_logging();
+        common::css::set_notify_errors(cfg.base.notify_css_errors);
+        common::css::set_density(cfg.base.density);
+        common::anim::set_animations(cfg.base.animations);
+        set_recent_count(if cfg.show_recent { cfg.recent_count } else { 0 });
+        set_launch_via(cfg.launch_via);
+        if content.is_some() {
+            log(APP_NAME, &format!("loaded config from {}", path.display()));
         }
+        cfg
     }
 
-    pub fn parse(content: &str) -> Self {
+    pub fn parse(content: &str, path: &Path) -> Self {
         let mut cfg = Self::default();
-        for (section, key, val) in parse_config_file(content) {
-            cfg.base.parse_section(APP_NAME, &section, &key, &val);
-            match section.as_str() {
-                "window" => {
-                    if key == "search_height" {
-                        cfg.search_height = val.parse().unwrap_or(70);
-                    }
-                }
-                "behavior" => match key.as_str() {
-                    "terminal" => cfg.terminal = val,
-                    "calculator" => cfg.calculator = parse_bool(&val, true),
-                    "vim_mode" => cfg.vim_mode = parse_bool(&val, false),
-                    "animation_duration" => {
-                        cfg.animation_duration = val.parse().unwrap_or(200);
-                    }
-                    "animation_easing" => {
-                        cfg.animation_easing = parse_easing(&val);
-                    }
-                    _ => {}
-                },
-                _ => {}
+        for (section, key, val) in cfg.base.apply_shared() {
+            let other_app = CLIPHIST_KEYS.contains(&(section.as_str(), key.as_str()));
+            if !cfg.parse_key(&section, &key, val) && !other_app {
+                cfg.base.unknown_shared_key(&section, &key);
             }
         }
+        let (entries, include_errors) = parse_config_with_includes(content, path);
+        cfg.base.include_errors.extend(include_errors);
+        for (section, key, val) in entries {
+            if !cfg.base.parse_section(&section, &key, &val) && !cfg.parse_key(&section, &key, val)
+            {
+                cfg.base.unknown_key(&section, &key);
+            }
+        }
+        cfg.search.smartcase = cfg.base.smartcase;
         cfg
     }
+
+    /// Every effective setting, defaults included, for `--dump-config`
+    pub fn to_json(&self) -> Json {
+        let mut json = self.base.to_json();
+        for (section, key, value) in [
+            ("window", "search_height", Json::num(self.search_height)),
+            ("behavior", "terminal", Json::str(&self.terminal.command)),
+            (
+                "behavior",
+                "terminal_exec_arg",
+                Json::str(&self.terminal.exec_arg),
+            ),
+            (
+                "behavior",
+                "terminal_template",
+                Json::str(&self.terminal.template),
+            ),
+            ("behavior", "launch_via", Json::variant(&self.launch_via)),
+            ("behavior", "calculator", Json::Bool(self.calculator)),
+            ("behavior", "calc_scale", Json::num(self.calc.scale)),
+            (
+                "behavior",
+                "calc_strip_zeros",
+                Json::Bool(self.calc.strip_zeros),
+            ),
+            ("behavior", "calc_format", Json::variant(&self.calc.format)),
+            (
+                "behavior",
+                "calc_copy_format",
+                Json::variant(&self.calc.copy_format),
+            ),
+            ("behavior", "file_search", Json::Bool(self.file_search)),
+            ("behavior", "ssh_hosts", Json::Bool(self.ssh_hosts)),
+            ("behavior", "vim_mode", Json::Bool(self.vim_mode)),
+            ("behavior", "show_recent", Json::Bool(self.show_recent)),
+            ("behavior", "recent_count", Json::num(self.recent_count)),
+            ("search", "exact", Json::num(self.search.exact)),
+            ("search", "prefix", Json::num(self.search.prefix)),
+            ("search", "contains", Json::num(self.search.contains)),
+            ("search", "consecutive", Json::num(self.search.consecutive)),
+            ("search", "word_start", Json::num(self.search.word_start)),
+            ("search", "frequency", Json::num(self.search.frequency)),
+            (
+                "ui",
+                "search_placeholder",
+                Json::str(&self.search_placeholder),
+            ),
+            ("ui", "section_label", Json::str(&self.section_label)),
+            ("ui", "recent_label", Json::str(&self.recent_label)),
+            ("ui", "all_apps_label", Json::str(&self.all_apps_label)),
+        ] {
+            json.set(section, key, value);
+        }
+        json
+    }
+
+    /// Launcher-only keys; false if `key` isn't one
+    fn parse_key(&mut self, section: &str, key: &str, val: String) -> bool {
+        match (section, key) {
+            ("window", "search_height") => self.search_height = val.parse().unwrap_or(70),
+            ("behavior", "terminal") => self.terminal.command = self.base.expand(&val),
+            ("behavior", "terminal_exec_arg") => self.terminal.exec_arg = val,
+            ("behavior", "terminal_template") => {
+                if val.contains("{cmd}") {
+                    self.terminal.template = self.base.expand(&val);
+                } else {
+                    log(
+                        APP_NAME,
+                        &format!(
+                            "terminal_template '{}' has no {{cmd}} placeholder, ignoring",
+                            val
+                        ),
+                    );
+                }
+            }
+            ("behavior", "launch_via") => match parse_launch_via(&val) {
+                Some(v) => self.launch_via = v,
+                None => log(APP_NAME, &format!("unknown launch_via '{}', using sh", val)),
+            },
+            ("behavior", "calculator") => self.calculator = parse_bool(&val, true),
+            ("behavior", "calc_scale") => {
+                self.calc.scale = val.parse::<u32>().unwrap_or(4).min(MAX_CALC_SCALE)
+            }
+            ("behavior", "calc_strip_zeros") => self.calc.strip_zeros = parse_bool(&val, true),
+            ("behavior", "calc_format") => self.calc.format = parse_calc_format(&val),
+            ("behavior", "calc_copy_format") => {
+                self.calc.copy_format = parse_calc_copy_format(&val)
+            }
+            ("behavior", "file_search") => self.file_search = parse_bool(&val, false),
+            ("behavior", "ssh_hosts") => self.ssh_hosts = parse_bool(&val, true),
+            ("behavior", "vim_mode") => self.vim_mode = parse_bool(&val, false),
+            ("behavior", "show_recent") => self.show_recent = parse_bool(&val, true),
+            ("behavior", "recent_count") => self.recent_count = val.parse().unwrap_or(5),
+            ("ui", "search_placeholder") => self.search_placeholder = val,
+            ("ui", "section_label") => self.section_label = val,
+            ("ui", "recent_label") => self.recent_label = val,
+            ("ui", "all_apps_label") => self.all_apps_label = val,
+            ("search", key) => {
+                let d = SearchWeights::default();
+                let (slot, default) = match key {
+                    "exact" => (&mut self.search.exact, d.exact),
+                    "prefix" => (&mut self.search.prefix, d.prefix),
+                    "contains" => (&mut self.search.contains, d.contains),
+                    "consecutive" => (&mut self.search.consecutive, d.consecutive),
+                    "word_start" => (&mut self.search.word_start, d.word_start),
+                    "frequency" => (&mut self.search.frequency, d.frequency),
+                    _ => return false,
+                };
+                *slot = val.parse().unwrap_or(default);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Calculator settings, or None when the calculator is disabled
+    pub fn calc_options(&self) -> Option<CalcOptions> {
+        self.calculator.then_some(self.calc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::config::parse_config_file;
+
+    #[test]
+    fn cliphist_keys_in_the_shared_file_are_not_reported() {
+        let mut cfg = Config::default();
+        let default = include_str!("../cliphist/config.default");
+        for (section, key, val) in parse_config_file(default) {
+            if cfg.base.parse_section(&section, &key, &val) || cfg.parse_key(&section, &key, val) {
+                continue;
+            }
+            assert!(
+                CLIPHIST_KEYS.contains(&(section.as_str(), key.as_str())),
+                "[{}] {} missing from CLIPHIST_KEYS",
+                section,
+                key
+            );
+        }
+    }
 }
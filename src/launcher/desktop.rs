@@ -1,11 +1,79 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::OnceLock;
 
-use crate::config::APP_NAME;
+use crate::config::{TerminalConfig, APP_NAME};
 use common::logging::log;
+use common::paths::{cache_dir, in_path, shellexpand};
+
+/// Bump when the cache line format (or how Exec is expanded) changes so old
+/// caches are ignored
+const CACHE_VERSION: &str = "v4";
+
+static CACHE_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// What `--print` writes to stdout instead of launching
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrintField {
+    Name,
+    Exec,
+}
+
+static PRINT_FIELD: OnceLock<PrintField> = OnceLock::new();
+
+/// What launched commands are wrapped in (`[behavior] launch_via`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchVia {
+    /// Plain `sh -c`, as a child of the launcher
+    Sh,
+    /// `systemd-run --user --scope`, a transient scope per app
+    SystemdRun,
+    /// `uwsm app --`, for uwsm-managed sessions
+    Uwsm,
+}
+
+pub fn parse_launch_via(s: &str) -> Option<LaunchVia> {
+    match s.to_lowercase().as_str() {
+        "sh" => Some(LaunchVia::Sh),
+        "systemd-run" | "systemd_run" => Some(LaunchVia::SystemdRun),
+        "uwsm" => Some(LaunchVia::Uwsm),
+        _ => None,
+    }
+}
+
+static LAUNCH_VIA: AtomicU8 = AtomicU8::new(LaunchVia::Sh as u8);
+
+pub fn set_launch_via(via: LaunchVia) {
+    LAUNCH_VIA.store(via as u8, Ordering::Relaxed);
+}
+
+/// Wrap `cmd` per `launch_via`, or return it as is when that's `sh` or the
+/// wrapper isn't installed
+fn wrap_launch(cmd: Command) -> Command {
+    let prefix: &[&str] = match LAUNCH_VIA.load(Ordering::Relaxed) {
+        v if v == LaunchVia::SystemdRun as u8 => &["systemd-run", "--user", "--scope", "--quiet"],
+        v if v == LaunchVia::Uwsm as u8 => &["uwsm", "app", "--"],
+        _ => return cmd,
+    };
+    if !in_path(prefix[0]) {
+        log(
+            APP_NAME,
+            &format!("launch_via: {} not found, using sh", prefix[0]),
+        );
+        return cmd;
+    }
+    let mut wrapped = Command::new(prefix[0]);
+    wrapped
+        .args(&prefix[1..])
+        .arg(cmd.get_program())
+        .args(cmd.get_args());
+    wrapped
+}
 
 thread_local! {
     pub static FREQUENCY: RefCell<HashMap<String, u32>> = RefCell::new(HashMap::new());
@@ -20,6 +88,10 @@ pub struct DesktopEntry {
     pub description: String,
     pub terminal: bool,
     pub path: PathBuf,
+    /// `Path=`: directory to run the command in
+    pub workdir: Option<PathBuf>,
+    /// `StartupWMClass=`, searched along with the program name
+    pub wm_class: String,
     pub score: i32,
 }
 
@@ -42,6 +114,195 @@ pub fn xdg_data_dirs() -> Vec<PathBuf> {
     dirs
 }
 
+fn split_desktop_list(val: &str) -> Vec<String> {
+    val.split(';')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Undo the string-value escapes (`\s`, `\n`, `\t`, `\r`, `\\`) that apply
+/// to every desktop file value
+fn unescape_value(val: &str) -> String {
+    let mut out = String::with_capacity(val.len());
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Split an Exec line into arguments: whitespace separates them, and
+/// double quotes group one, inside which `\` escapes `"`, `` ` ``, `$` and `\`.
+/// Single quotes group literally. None on an unterminated quote.
+fn split_exec(exec: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut cur = String::new();
+    let mut in_arg = false;
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => cur.push(chars.next()?),
+                        q => cur.push(q),
+                    }
+                }
+            }
+            // Not in the spec, but hand-written entries use `sh -c '...'`
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        q => cur.push(q),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut cur));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                cur.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(cur);
+    }
+    Some(args)
+}
+
+/// Basename of the program `exec` runs, past an `env` prefix and its
+/// variables: `env GDK_BACKEND=x11 /usr/lib/firefox/firefox -P` -> `firefox`
+pub fn program_name(exec: &str) -> String {
+    let args = split_exec(exec).unwrap_or_default();
+    let mut args = args.iter().map(String::as_str).peekable();
+    if args.peek() == Some(&"env") {
+        args.next();
+    }
+    args.find(|a| !a.contains('=') && !a.starts_with('-'))
+        .and_then(|p| Path::new(p).file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Quote `arg` for `sh -c` when it has anything beyond plain word characters
+fn sh_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Expand the field codes in one Exec argument; None when it was only a
+/// dropped code
+fn expand_arg(arg: &str, name: &str, path: &Path) -> Option<String> {
+    let mut expanded = String::with_capacity(arg.len());
+    let mut dropped = false;
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('c') => expanded.push_str(name),
+            Some('k') => expanded.push_str(&path.to_string_lossy()),
+            Some('f' | 'F' | 'u' | 'U' | 'd' | 'D' | 'n' | 'N' | 'v' | 'm' | 'i') => dropped = true,
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    (!(dropped && expanded.is_empty())).then_some(expanded)
+}
+
+/// Expand an Exec line's field codes per the desktop entry spec and return
+/// it as a `sh -c` command line. `%%` becomes `%`; `%c`, `%k` and `%i`
+/// become the name, file path and `--icon <icon>`; the file/URL codes and
+/// deprecated ones are dropped, taking their argument with them when they
+/// stand alone. Quoting in the original is preserved argument by argument.
+/// An unterminated quote falls back to the line as written, codes stripped.
+fn expand_exec(exec: &str, name: &str, icon: &str, path: &Path) -> Option<String> {
+    let exec = unescape_value(exec);
+    let Some(args) = split_exec(&exec) else {
+        let raw: Vec<String> = exec
+            .split_whitespace()
+            .filter_map(|a| expand_arg(a, name, path))
+            .collect();
+        return (!raw.is_empty()).then(|| raw.join(" "));
+    };
+    let mut out = Vec::new();
+    for arg in args {
+        if arg == "%i" {
+            if !icon.is_empty() {
+                out.push("--icon".to_string());
+                out.push(icon.to_string());
+            }
+            continue;
+        }
+        out.extend(expand_arg(&arg, name, path));
+    }
+    if out.is_empty() {
+        return None;
+    }
+    Some(
+        out.iter()
+            .map(|a| sh_quote(a))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Apply OnlyShowIn/NotShowIn against `current` (XDG_CURRENT_DESKTOP,
+/// colon-separated). Nothing is filtered when the desktop is unknown.
+pub fn shown_in_desktop(
+    current: Option<&str>,
+    only_show_in: &[String],
+    not_show_in: &[String],
+) -> bool {
+    let desktops: Vec<String> = match current {
+        Some(c) if !c.trim().is_empty() => c
+            .split(':')
+            .map(|d| d.trim().to_lowercase())
+            .filter(|d| !d.is_empty())
+            .collect(),
+        _ => return true,
+    };
+
+    if !only_show_in.is_empty() && !desktops.iter().any(|d| only_show_in.contains(d)) {
+        return false;
+    }
+    !desktops.iter().any(|d| not_show_in.contains(d))
+}
+
 pub fn parse_desktop_file(path: &PathBuf) -> Option<DesktopEntry> {
     let content = std::fs::read_to_string(path).ok()?;
 
@@ -52,7 +313,11 @@ pub fn parse_desktop_file(path: &PathBuf) -> Option<DesktopEntry> {
     let mut terminal = false;
     let mut no_display = false;
     let mut hidden = false;
+    let mut only_show_in = Vec::new();
+    let mut not_show_in = Vec::new();
     let mut in_desktop_entry = false;
+    let mut workdir = String::new();
+    let mut wm_class = String::new();
 
     for line in content.lines() {
         let t = line.trim();
@@ -75,9 +340,13 @@ pub fn parse_desktop_file(path: &PathBuf) -> Option<DesktopEntry> {
                 "Icon" => icon = val.to_string(),
                 "Comment" if description.is_empty() => description = val.to_string(),
                 "GenericName" if description.is_empty() => description = val.to_string(),
+                "Path" => workdir = val.to_string(),
+                "StartupWMClass" => wm_class = val.to_string(),
                 "Terminal" => terminal = val.to_lowercase() == "true",
                 "NoDisplay" => no_display = val.to_lowercase() == "true",
                 "Hidden" => hidden = val.to_lowercase() == "true",
+                "OnlyShowIn" => only_show_in = split_desktop_list(val),
+                "NotShowIn" => not_show_in = split_desktop_list(val),
                 _ => {}
             }
         }
@@ -87,18 +356,12 @@ pub fn parse_desktop_file(path: &PathBuf) -> Option<DesktopEntry> {
         return None;
     }
 
-    let exec_clean = exec
-        .replace("%f", "")
-        .replace("%F", "")
-        .replace("%u", "")
-        .replace("%U", "")
-        .replace("%c", "")
-        .replace("%k", "")
-        .replace("%i", "")
-        .replace("%d", "")
-        .replace("%D", "")
-        .trim()
-        .to_string();
+    let current = std::env::var("XDG_CURRENT_DESKTOP").ok();
+    if !shown_in_desktop(current.as_deref(), &only_show_in, &not_show_in) {
+        return None;
+    }
+
+    let exec_clean = expand_exec(&exec, &name, &icon, path)?;
 
     Some(DesktopEntry {
         name,
@@ -107,6 +370,8 @@ pub fn parse_desktop_file(path: &PathBuf) -> Option<DesktopEntry> {
         description,
         terminal,
         path: path.clone(),
+        workdir: (!workdir.is_empty()).then(|| PathBuf::from(shellexpand(&workdir))),
+        wm_class,
         score: 0,
     })
 }
@@ -126,53 +391,405 @@ fn walkdir(dir: PathBuf) -> Vec<PathBuf> {
     files
 }
 
-pub fn load_entries() -> Vec<DesktopEntry> {
+/// Parse files across threads, keeping results in input order
+fn parse_parallel(paths: &[PathBuf]) -> Vec<Option<DesktopEntry>> {
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(8);
+    if threads <= 1 || paths.len() < 32 {
+        return paths.iter().map(parse_desktop_file).collect();
+    }
+
+    let chunk_size = paths.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || chunk.iter().map(parse_desktop_file).collect::<Vec<_>>())
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_default())
+            .collect()
+    })
+}
+
+pub fn scan_entries() -> Vec<DesktopEntry> {
+    // Gather paths in directory priority order so local entries win the dedup
+    let paths: Vec<PathBuf> = xdg_data_dirs()
+        .into_iter()
+        .filter(|dir| dir.exists())
+        .flat_map(walkdir)
+        .filter(|path| path.extension().map(|e| e == "desktop").unwrap_or(false))
+        .collect();
+
     let mut entries = Vec::new();
     let mut seen = HashSet::new();
+    for entry in parse_parallel(&paths).into_iter().flatten() {
+        if seen.insert(entry.name.clone()) {
+            entries.push(entry);
+        }
+    }
 
-    for dir in xdg_data_dirs() {
-        if !dir.exists() {
-            continue;
+    entries.sort_by_key(|e| e.name.to_lowercase());
+    log(
+        APP_NAME,
+        &format!("scanned {} desktop entries", entries.len()),
+    );
+    entries
+}
+
+/// Skip reading and writing the desktop entry cache (`--no-cache`)
+pub fn set_cache_disabled(disabled: bool) {
+    CACHE_DISABLED.store(disabled, Ordering::Relaxed);
+}
+
+/// `--print`: selecting an entry prints it instead of launching it
+pub fn set_print_field(field: PrintField) {
+    let _ = PRINT_FIELD.set(field);
+}
+
+/// Write `line` to stdout for `--print`, flushed since the process exits
+/// right after. Returns false when not in print mode.
+pub fn print_selection(line: &str) -> bool {
+    if PRINT_FIELD.get().is_none() {
+        return false;
+    }
+    let mut out = std::io::stdout().lock();
+    let _ = writeln!(out, "{}", line);
+    let _ = out.flush();
+    true
+}
+
+fn cache_path() -> PathBuf {
+    cache_dir(APP_NAME).join("desktop-entries")
+}
+
+/// Newest mtime (unix secs) of `dir` and every directory below it
+fn max_dir_mtime(dir: &Path) -> u64 {
+    let mut newest = std::fs::metadata(dir)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if let Ok(rd) = std::fs::read_dir(dir) {
+        for entry in rd.flatten() {
+            let p = entry.path();
+            if p.is_dir() {
+                newest = newest.max(max_dir_mtime(&p));
+            }
         }
+    }
+    newest
+}
 
-        let walker = walkdir(dir.clone());
-        for path in walker {
-            if path.extension().map(|e| e == "desktop").unwrap_or(false) {
-                if let Some(entry) = parse_desktop_file(&path) {
-                    if seen.insert(entry.name.clone()) {
-                        entries.push(entry);
-                    }
-                }
+/// Header line identifying the state of the scanned directories
+fn cache_stamp(dirs: &[PathBuf]) -> String {
+    let mtime = dirs.iter().map(|d| max_dir_mtime(d)).max().unwrap_or(0);
+    let joined: Vec<String> = dirs.iter().map(|d| d.display().to_string()).collect();
+    // Entries are filtered by desktop at parse time, so a DE switch invalidates
+    let desktop = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    format!(
+        "{}\t{}\t{}\t{}",
+        CACHE_VERSION,
+        mtime,
+        desktop,
+        joined.join(":")
+    )
+}
+
+fn escape_field(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape_field(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('t') => out.push('\t'),
+                Some('n') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
             }
+        } else {
+            out.push(c);
         }
     }
+    out
+}
 
-    entries.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    log(
-        APP_NAME,
-        &format!("loaded {} desktop entries", entries.len()),
-    );
+fn read_cache(stamp: &str) -> Option<Vec<DesktopEntry>> {
+    let content = std::fs::read_to_string(cache_path()).ok()?;
+    let mut lines = content.lines();
+    if lines.next()? != stamp {
+        return None;
+    }
+    lines
+        .map(|line| {
+            let f: Vec<String> = line.split('\t').map(unescape_field).collect();
+            if f.len() != 8 {
+                return None;
+            }
+            Some(DesktopEntry {
+                name: f[0].clone(),
+                exec: f[1].clone(),
+                icon: f[2].clone(),
+                description: f[3].clone(),
+                terminal: f[4] == "1",
+                path: PathBuf::from(&f[5]),
+                workdir: (!f[6].is_empty()).then(|| PathBuf::from(&f[6])),
+                wm_class: f[7].clone(),
+                score: 0,
+            })
+        })
+        .collect()
+}
+
+fn write_cache(stamp: &str, entries: &[DesktopEntry]) {
+    let mut out = format!("{}\n", stamp);
+    for e in entries {
+        let fields = [
+            e.name.as_str(),
+            e.exec.as_str(),
+            e.icon.as_str(),
+            e.description.as_str(),
+            if e.terminal { "1" } else { "0" },
+            &e.path.to_string_lossy(),
+            &e.workdir
+                .as_deref()
+                .map(|w| w.to_string_lossy())
+                .unwrap_or_default(),
+            e.wm_class.as_str(),
+        ];
+        let escaped: Vec<String> = fields.iter().map(|f| escape_field(f)).collect();
+        out.push_str(&escaped.join("\t"));
+        out.push('\n');
+    }
+    if let Err(e) = std::fs::write(cache_path(), out) {
+        log(APP_NAME, &format!("failed to write desktop cache: {}", e));
+    }
+}
+
+/// Load desktop entries, reusing the on-disk cache while no
+/// applications directory has changed since it was written
+pub fn load_entries() -> Vec<DesktopEntry> {
+    if CACHE_DISABLED.load(Ordering::Relaxed) {
+        return scan_entries();
+    }
+
+    let stamp = cache_stamp(&xdg_data_dirs());
+    if let Some(entries) = read_cache(&stamp) {
+        log(
+            APP_NAME,
+            &format!("loaded {} desktop entries from cache", entries.len()),
+        );
+        return entries;
+    }
+
+    let entries = scan_entries();
+    write_cache(&stamp, &entries);
     entries
 }
 
-pub fn launch_app(entry: &DesktopEntry, terminal: &str) {
+/// The cached entries while the cache is current, without scanning or
+/// writing anything
+pub fn cached_entries() -> Option<Vec<DesktopEntry>> {
+    read_cache(&cache_stamp(&xdg_data_dirs()))
+}
+
+/// Drop the cache and rescan; returns the number of entries found
+pub fn rebuild_cache() -> usize {
+    let _ = std::fs::remove_file(cache_path());
+    load_entries().len()
+}
+
+/// Build the command that runs `exec` inside the configured terminal.
+/// A standalone `{cmd}` token in the template expands to `sh -c <exec>`;
+/// a placeholder embedded in a longer token is replaced by `exec` verbatim.
+pub fn terminal_command(terminal: &TerminalConfig, exec: &str) -> Option<Command> {
+    if terminal.template.is_empty() {
+        let mut cmd = Command::new(&terminal.command);
+        if !terminal.exec_arg.is_empty() {
+            cmd.arg(&terminal.exec_arg);
+        }
+        cmd.arg("sh").arg("-c").arg(exec);
+        return Some(cmd);
+    }
+
+    let mut parts = terminal.template.split_whitespace();
+    let mut cmd = Command::new(parts.next()?);
+    for part in parts {
+        if part == "{cmd}" {
+            cmd.arg("sh").arg("-c").arg(exec);
+        } else {
+            cmd.arg(part.replace("{cmd}", exec));
+        }
+    }
+    Some(cmd)
+}
+
+/// Launch `entry` and count it towards its search ranking.
+/// `flip_terminal` inverts the entry's Terminal flag for this launch only
+pub fn launch_app(entry: &DesktopEntry, terminal: &TerminalConfig, flip_terminal: bool) {
+    if PRINT_FIELD.get().is_none() {
+        FREQUENCY.with(|f| {
+            let mut freq = f.borrow_mut();
+            *freq.entry(entry.name.clone()).or_insert(0) += 1;
+        });
+    }
+    run_entry(entry, terminal, flip_terminal);
+}
+
+/// Run `entry` (or print it under `--print`) without counting the launch,
+/// for ssh hosts whose names would otherwise boost apps of the same name
+pub fn run_entry(entry: &DesktopEntry, terminal: &TerminalConfig, flip_terminal: bool) {
     let exec = &entry.exec;
 
-    FREQUENCY.with(|f| {
-        let mut freq = f.borrow_mut();
-        *freq.entry(entry.name.clone()).or_insert(0) += 1;
-    });
+    if let Some(field) = PRINT_FIELD.get() {
+        print_selection(match field {
+            PrintField::Name => &entry.name,
+            PrintField::Exec => exec,
+        });
+        return;
+    }
 
     log(APP_NAME, &format!("launching: {} ({})", entry.name, exec));
 
-    if entry.terminal {
-        let _ = Command::new(terminal)
-            .arg("-e")
-            .arg("sh")
-            .arg("-c")
-            .arg(exec)
-            .spawn();
+    let cmd = if entry.terminal != flip_terminal {
+        match terminal_command(terminal, exec) {
+            Some(cmd) => cmd,
+            None => return,
+        }
     } else {
-        let _ = Command::new("sh").arg("-c").arg(exec).spawn();
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(exec);
+        cmd
+    };
+    let mut cmd = wrap_launch(cmd);
+    // A missing Path= directory would fail the spawn; run from here instead
+    match entry.workdir.as_deref() {
+        Some(dir) if dir.is_dir() => {
+            cmd.current_dir(dir);
+        }
+        Some(dir) => log(
+            APP_NAME,
+            &format!(
+                "{}: Path={} doesn't exist, ignoring",
+                entry.name,
+                dir.display()
+            ),
+        ),
+        None => {}
+    }
+    let result = cmd.spawn();
+    if let Err(e) = result {
+        log(APP_NAME, &format!("failed to launch {}: {}", entry.name, e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(exec: &str) -> Option<String> {
+        expand_exec(exec, "Editor", "editor", Path::new("/apps/editor.desktop"))
+    }
+
+    #[test]
+    fn percent_escape() {
+        assert_eq!(expand("date +%%H").as_deref(), Some("date +%H"));
+    }
+
+    #[test]
+    fn quoted_args() {
+        assert_eq!(
+            split_exec(r#"sh -c "echo \"hi\" there""#).unwrap(),
+            vec!["sh", "-c", r#"echo "hi" there"#]
+        );
+        assert_eq!(
+            split_exec("sh -c 'a  b'").unwrap(),
+            vec!["sh", "-c", "a  b"]
+        );
+        assert_eq!(
+            expand(r#"edit "my file""#).as_deref(),
+            Some("edit 'my file'")
+        );
+    }
+
+    #[test]
+    fn file_codes_dropped_mid_line() {
+        assert_eq!(
+            expand("edit %F --new-window").as_deref(),
+            Some("edit --new-window")
+        );
+        assert_eq!(
+            expand("edit --open=%u -x").as_deref(),
+            Some("edit --open= -x")
+        );
+    }
+
+    #[test]
+    fn name_path_and_icon() {
+        assert_eq!(
+            expand("edit %i --class %c %k").as_deref(),
+            Some("edit --icon editor --class Editor /apps/editor.desktop")
+        );
+    }
+
+    #[test]
+    fn unterminated_quote_falls_back_to_raw() {
+        assert_eq!(split_exec(r#"edit "oops %F"#), None);
+        assert_eq!(expand(r#"edit "oops %F"#).as_deref(), Some(r#"edit "oops"#));
+    }
+
+    fn list(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn only_show_in() {
+        let gnome = list(&["gnome"]);
+        assert!(shown_in_desktop(Some("GNOME"), &gnome, &[]));
+        assert!(shown_in_desktop(Some("ubuntu:GNOME"), &gnome, &[]));
+        assert!(!shown_in_desktop(Some("Hyprland"), &gnome, &[]));
+    }
+
+    #[test]
+    fn not_show_in() {
+        let kde = list(&["kde"]);
+        assert!(!shown_in_desktop(Some("KDE"), &[], &kde));
+        assert!(shown_in_desktop(Some("sway"), &[], &kde));
+    }
+
+    #[test]
+    fn unknown_desktop_shows_everything() {
+        let gnome = list(&["gnome"]);
+        assert!(shown_in_desktop(None, &gnome, &gnome));
+        assert!(shown_in_desktop(Some(" "), &gnome, &[]));
+    }
+
+    fn parse(name: &str, content: &str) -> Option<DesktopEntry> {
+        let path = std::env::temp_dir().join(format!("{}-{}.desktop", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        let entry = parse_desktop_file(&path);
+        let _ = std::fs::remove_file(&path);
+        entry
+    }
+
+    #[test]
+    fn path_key_sets_workdir() {
+        std::env::set_var("LAUNCH_GUI_TEST_GAMES", "/srv/games");
+        let with_path = "[Desktop Entry]\nName=Game\nExec=run\nPath=$LAUNCH_GUI_TEST_GAMES/game\n";
+        let entry = parse("with-path", with_path).unwrap();
+        assert_eq!(entry.workdir, Some(PathBuf::from("/srv/games/game")));
+        let entry = parse("without-path", "[Desktop Entry]\nName=Tool\nExec=tool\n").unwrap();
+        assert_eq!(entry.workdir, None);
     }
 }
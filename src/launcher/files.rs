@@ -0,0 +1,288 @@
+use crate::config::APP_NAME;
+use crate::desktop::print_selection;
+use crate::search::{fuzzy_match, SearchWeights};
+use common::logging::log;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+
+/// How many directories below the starting one a search descends
+const MAX_DEPTH: usize = 4;
+
+/// Stop collecting after this many files, so `/` or a huge home stays cheap
+const MAX_FILES: usize = 20_000;
+
+#[derive(Clone, Debug)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    /// Path relative to the directory being browsed, `/`-terminated for dirs
+    pub label: String,
+    pub is_dir: bool,
+}
+
+enum Walk {
+    Running(PathBuf),
+    Done(PathBuf, Rc<Vec<PathBuf>>),
+}
+
+thread_local! {
+    /// Last walk, so typing a filter doesn't rescan the tree every keystroke
+    static WALK_CACHE: RefCell<Option<Walk>> = const { RefCell::new(None) };
+    /// Called on the main thread when a background walk finishes
+    static ON_WALK_DONE: RefCell<Option<Box<dyn Fn()>>> = const { RefCell::new(None) };
+}
+
+/// `~`, `./` (both from $HOME) and `/` switch the launcher to files
+pub fn is_file_query(query: &str) -> bool {
+    query.starts_with('~') || query.starts_with("./") || query.starts_with('/')
+}
+
+fn home() -> PathBuf {
+    PathBuf::from(std::env::var("HOME").unwrap_or("/".into()))
+}
+
+/// Split a file query into the directory to browse and the filter typed
+/// after its last `/`
+fn split_query(query: &str) -> (PathBuf, &str) {
+    let (base, rest) = if let Some(rest) = query.strip_prefix("./") {
+        (home(), rest)
+    } else if let Some(rest) = query.strip_prefix('~') {
+        (home(), rest.trim_start_matches('/'))
+    } else {
+        (PathBuf::from("/"), &query[1..])
+    };
+    match rest.rsplit_once('/') {
+        Some((dir, filter)) => (base.join(dir), filter),
+        None => (base, rest),
+    }
+}
+
+/// The query as typed, up to and including its last `/`
+fn typed_dir(query: &str) -> &str {
+    match query.rfind('/') {
+        Some(i) => &query[..=i],
+        None => "~/",
+    }
+}
+
+fn entry(dir: &Path, path: PathBuf, is_dir: bool) -> FileEntry {
+    let rel = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy();
+    let label = if is_dir {
+        format!("{}/", rel)
+    } else {
+        rel.to_string()
+    };
+    FileEntry {
+        path,
+        label,
+        is_dir,
+    }
+}
+
+/// Immediate children of `dir`, directories first
+fn children(dir: &Path) -> Vec<FileEntry> {
+    let mut out: Vec<FileEntry> = std::fs::read_dir(dir)
+        .map(|rd| {
+            rd.flatten()
+                .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+                .map(|e| {
+                    let p = e.path();
+                    let is_dir = p.is_dir();
+                    entry(dir, p, is_dir)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    out.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.label.to_lowercase().cmp(&b.label.to_lowercase()))
+    });
+    out
+}
+
+/// Files below `root`, breadth first so a capped walk keeps the shallow
+/// ones. Stays on the root's filesystem, which keeps /proc, /sys and
+/// network mounts out, and doesn't follow symlinked directories.
+fn walk(root: &Path) -> Vec<PathBuf> {
+    let Ok(dev) = std::fs::metadata(root).map(|m| m.dev()) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    let mut queue = VecDeque::from([(root.to_path_buf(), 0)]);
+    while let Some((dir, depth)) = queue.pop_front() {
+        let Ok(rd) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in rd.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(ft) = entry.file_type() else {
+                continue;
+            };
+            if ft.is_dir() {
+                let same_dev = entry.metadata().is_ok_and(|m| m.dev() == dev);
+                if depth < MAX_DEPTH && same_dev {
+                    queue.push_back((entry.path(), depth + 1));
+                }
+            } else {
+                files.push(entry.path());
+                if files.len() >= MAX_FILES {
+                    log(
+                        APP_NAME,
+                        &format!(
+                            "file search stopped at {} files in {}",
+                            MAX_FILES,
+                            root.display()
+                        ),
+                    );
+                    return files;
+                }
+            }
+        }
+    }
+    files
+}
+
+/// The walk of `dir` if it has finished. Otherwise starts one on a worker
+/// thread (unless it's already running) and returns None; the
+/// `on_walk_done` callback runs once the files are in.
+fn walk_cached(dir: &Path) -> Option<Rc<Vec<PathBuf>>> {
+    let done = WALK_CACHE.with(|c| match c.borrow().as_ref() {
+        Some(Walk::Done(d, files)) if d == dir => Some(Some(files.clone())),
+        Some(Walk::Running(d)) if d == dir => Some(None),
+        _ => None,
+    });
+    if let Some(files) = done {
+        return files;
+    }
+
+    let root = dir.to_path_buf();
+    WALK_CACHE.with(|c| *c.borrow_mut() = Some(Walk::Running(root.clone())));
+    glib::spawn_future_local(async move {
+        let r = root.clone();
+        let Ok(files) = gio::spawn_blocking(move || walk(&r)).await else {
+            return;
+        };
+        // Dropped if the query moved elsewhere while we were walking
+        let current = WALK_CACHE
+            .with(|c| matches!(c.borrow().as_ref(), Some(Walk::Running(d)) if *d == root));
+        if !current {
+            return;
+        }
+        WALK_CACHE.with(|c| *c.borrow_mut() = Some(Walk::Done(root, Rc::new(files))));
+        ON_WALK_DONE.with(|f| {
+            if let Some(f) = f.borrow().as_ref() {
+                f();
+            }
+        });
+    });
+    None
+}
+
+/// Run `f` whenever a background walk finishes, to refresh the list
+pub fn on_walk_done(f: impl Fn() + 'static) {
+    ON_WALK_DONE.with(|c| *c.borrow_mut() = Some(Box::new(f)));
+}
+
+/// Drop the cached walk; the next search rescans
+pub fn forget_walk() {
+    WALK_CACHE.with(|c| *c.borrow_mut() = None);
+}
+
+/// Files for a file query: the directory's contents when nothing follows
+/// the last `/`, else subdirectories and files below it that fuzzy-match.
+/// Until the walk below the directory is done only subdirectories match.
+pub fn list_files(query: &str, w: &SearchWeights) -> Vec<FileEntry> {
+    let (dir, filter) = split_query(query);
+    if filter.is_empty() {
+        return children(&dir);
+    }
+
+    let walked = walk_cached(&dir).unwrap_or_default();
+    let candidates = children(&dir)
+        .into_iter()
+        .filter(|e| e.is_dir)
+        .chain(walked.iter().map(|p| entry(&dir, p.clone(), false)));
+    let mut matched: Vec<(FileEntry, i32)> = candidates
+        .filter_map(|e| {
+            let name = e.path.file_name()?.to_string_lossy().to_string();
+            let name_score = fuzzy_match(filter, &name, w);
+            let path_score = fuzzy_match(filter, &e.label, w).map(|s| s / 2);
+            name_score.max(path_score).map(|s| (e, s))
+        })
+        .collect();
+    // Shallower paths first among equal scores
+    matched.sort_by(|a, b| {
+        b.1.cmp(&a.1).then_with(|| {
+            a.0.label
+                .matches('/')
+                .count()
+                .cmp(&b.0.label.matches('/').count())
+        })
+    });
+    matched.into_iter().map(|(e, _)| e).collect()
+}
+
+/// The query that browses into directory `entry`
+pub fn descend_query(query: &str, entry: &FileEntry) -> String {
+    format!("{}{}", typed_dir(query), entry.label)
+}
+
+/// Open `path` with its default application, or print it under `--print`
+pub fn open_file(path: &Path) {
+    if print_selection(&path.to_string_lossy()) {
+        return;
+    }
+    log(APP_NAME, &format!("opening: {}", path.display()));
+    if let Err(e) = Command::new("xdg-open")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        log(
+            APP_NAME,
+            &format!("xdg-open failed for {}: {}", path.display(), e),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk_skips_dotfiles_and_stops_at_max_depth() {
+        let root = std::env::temp_dir().join(format!("launch-gui-walk-{}", std::process::id()));
+        // `d1/../dN` is N levels down; files MAX_DEPTH directories down still count
+        let deep = (1..=MAX_DEPTH + 1).fold(root.clone(), |p, i| p.join(format!("d{}", i)));
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::create_dir_all(root.join(".hidden")).unwrap();
+        std::fs::write(root.join("top.txt"), "").unwrap();
+        std::fs::write(root.join(".dot.txt"), "").unwrap();
+        std::fs::write(root.join(".hidden/inner.txt"), "").unwrap();
+        std::fs::write(deep.join("too-deep.txt"), "").unwrap();
+        std::fs::write(deep.parent().unwrap().join("deepest.txt"), "").unwrap();
+
+        let found: Vec<String> = walk(&root)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        let _ = std::fs::remove_dir_all(&root);
+        assert_eq!(found, ["top.txt", "deepest.txt"]);
+    }
+
+    #[test]
+    fn split_query_bases() {
+        assert_eq!(split_query("/etc/hos"), (PathBuf::from("/etc"), "hos"));
+        assert_eq!(split_query("/us"), (PathBuf::from("/"), "us"));
+        assert_eq!(split_query("~/Doc"), (home(), "Doc"));
+        assert_eq!(split_query("./src/ma"), (home().join("src"), "ma"));
+    }
+}
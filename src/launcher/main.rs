@@ -2,7 +2,10 @@ mod app;
 mod calc;
 mod config;
 mod desktop;
+mod files;
 mod search;
+mod selftest;
+mod ssh;
 mod ui;
 
 use gtk4::prelude::*;
@@ -11,31 +14,52 @@ use std::process::Command;
 
 use app::{activate, setup_signals};
 use common::cli::{
-    cmd_config, cmd_generate_config, cmd_reload, get_pid, pidfile_path, remove_pid, write_pid,
+    cmd_config, cmd_generate_config, cmd_reload, cmd_version, get_pid, pidfile_path, print_usage,
+    query_arg, remove_pid, request_query, request_window, write_pid, WindowRequest,
 };
 use config::{default_config, default_css, APP_NAME};
+use desktop::{rebuild_cache, set_cache_disabled, set_print_field, terminal_command, PrintField};
 
-fn print_usage() {
-    eprintln!("{} - app launcher\n", APP_NAME);
-    eprintln!("Usage:");
-    eprintln!("  {}                      Start daemon", APP_NAME);
-    eprintln!("  {} toggle               Toggle window", APP_NAME);
-    eprintln!("  {} --theme <name>       Preview theme", APP_NAME);
-    eprintln!("  {} show-themes          List themes", APP_NAME);
-    eprintln!("  {} --config             Show config dir", APP_NAME);
-    eprintln!("  {} --generate-config    Create defaults", APP_NAME);
-    eprintln!("  {} --reload             Restart daemon", APP_NAME);
-    eprintln!("  {} --help               Show help", APP_NAME);
-}
+/// `--help`: arguments and what they do. A leading space marks an option
+/// of the line above.
+const USAGE: &[(&str, &str)] = &[
+    ("", "Start daemon"),
+    ("toggle", "Toggle window"),
+    ("open [text]", "Show window (focus if shown)"),
+    ("--query <text>", "Open with the search filled in"),
+    ("close", "Hide window"),
+    ("--theme <name>", "Preview theme"),
+    ("show-themes", "List themes"),
+    (" [--json]", "(names as a JSON array)"),
+    ("--preview-themes", "Cycle themes live"),
+    ("--config", "Show config dir"),
+    ("--generate-config", "Create defaults"),
+    ("--check-config", "Validate config"),
+    ("--edit-config", "Open config in $EDITOR"),
+    ("--dump-config", "Print effective config as JSON"),
+    ("--reload", "Restart daemon"),
+    ("--restart-if-unresponsive", "Restart daemon if it hangs"),
+    ("--no-cache", "Start daemon without the app cache"),
+    ("--rebuild-cache", "Rescan .desktop files"),
+    ("--oneshot", "Show once and exit (no daemon)"),
+    ("--print [name|exec]", "Print the chosen app and exit"),
+    ("--version", "Show version"),
+    ("--help", "Show help"),
+];
 
 fn main() {
+    common::i18n::init(APP_NAME);
     let args: Vec<String> = std::env::args().collect();
     let pidfile = pidfile_path(APP_NAME);
 
     if args.len() > 1 {
         match args[1].as_str() {
             "--help" | "-h" => {
-                print_usage();
+                print_usage(APP_NAME, "app launcher", USAGE);
+                return;
+            }
+            "--version" | "-V" => {
+                cmd_version(APP_NAME, env!("CARGO_PKG_VERSION"), env!("GIT_HASH"));
                 return;
             }
             "--config" => {
@@ -46,31 +70,106 @@ fn main() {
                 cmd_generate_config(APP_NAME, default_css(), default_config());
                 return;
             }
+            "--check-config" => {
+                common::cli::cmd_check_config(APP_NAME, &config::Config::load().base);
+                return;
+            }
+            "--dump-config" => {
+                println!("{}", config::Config::load().to_json().pretty());
+                return;
+            }
+            "--edit-config" | "edit-config" => {
+                let cfg = config::Config::load();
+                common::cli::cmd_edit_config(APP_NAME, default_css(), default_config(), &|exec| {
+                    terminal_command(&cfg.terminal, exec)
+                });
+                return;
+            }
             "--reload" => {
                 cmd_reload(APP_NAME, &pidfile);
                 return;
             }
-            "toggle" | "open" => {
-                if let Some(pid) = get_pid(&pidfile) {
-                    unsafe { libc::kill(pid, libc::SIGUSR1) };
-                } else {
-                    eprintln!("Daemon not running");
-                }
+            "--restart-if-unresponsive" => {
+                common::cli::cmd_restart_if_unresponsive(APP_NAME, &pidfile);
+                return;
+            }
+            // Hidden: timings for performance work
+            "--selftest" => {
+                selftest::run(&config::Config::load());
                 return;
             }
-            "close" => {
+            "--rebuild-cache" => {
+                let n = rebuild_cache();
+                println!("Cached {} desktop entries", n);
+                println!("Run '{} --reload' to pick them up", APP_NAME);
+                return;
+            }
+            // Falls through to the daemon startup below
+            "--no-cache" => set_cache_disabled(true),
+            // Opens a running daemon's window, else falls through to startup
+            "--query" => {
+                let Some(query) = query_arg(&args) else {
+                    eprintln!("Usage: {} --query <text>", APP_NAME);
+                    std::process::exit(1);
+                };
                 if let Some(pid) = get_pid(&pidfile) {
-                    unsafe { libc::kill(pid, libc::SIGTERM) };
+                    if let Err(e) = request_query(APP_NAME, &query)
+                        .and_then(|_| request_window(APP_NAME, pid, WindowRequest::Open))
+                    {
+                        eprintln!("Failed to send --query: {}", e);
+                        std::process::exit(1);
+                    }
+                    return;
                 }
-                return;
             }
-            "show-themes" | "--themes" => {
-                println!("Available themes:");
-                for (name, _) in common::paths::builtin_themes() {
-                    println!("  {}", name);
+            // Falls through to startup, skipping the pidfile
+            "--oneshot" => common::cli::set_oneshot(true),
+            // Oneshot window that prints the choice instead of launching it
+            "--print" => {
+                let field = match args.get(2).map(String::as_str) {
+                    None | Some("name") => PrintField::Name,
+                    Some("exec") => PrintField::Exec,
+                    Some(other) => {
+                        eprintln!("Usage: {} --print [name|exec], got '{}'", APP_NAME, other);
+                        std::process::exit(1);
+                    }
+                };
+                set_print_field(field);
+                common::cli::set_oneshot(true);
+            }
+            // Oneshot window with theme cycling on top
+            "--preview-themes" => {
+                common::cli::set_oneshot(true);
+                common::theme_preview::set_active(true);
+            }
+            "toggle" | "open" | "close" => {
+                let request = match args[1].as_str() {
+                    "open" => WindowRequest::Open,
+                    "close" => WindowRequest::Close,
+                    _ => WindowRequest::Toggle,
+                };
+                let query = query_arg(&args).filter(|_| request != WindowRequest::Close);
+                match get_pid(&pidfile) {
+                    Some(pid) => {
+                        let sent = match query {
+                            Some(q) => request_query(APP_NAME, &q),
+                            None => Ok(()),
+                        };
+                        if let Err(e) = sent.and_then(|_| request_window(APP_NAME, pid, request)) {
+                            eprintln!("Failed to send {}: {}", args[1], e);
+                            std::process::exit(1);
+                        }
+                    }
+                    // Nothing to close
+                    None if request == WindowRequest::Close => {}
+                    None => eprintln!("Daemon not running"),
                 }
                 return;
             }
+            "show-themes" | "--themes" | "--list-themes" => {
+                common::cli::cmd_list_themes(args.iter().any(|a| a == "--json"));
+                return;
+            }
             "-T" | "--theme" => {
                 if args.len() < 3 {
                     eprintln!("Usage: {} --theme <name>", APP_NAME);
@@ -82,9 +181,14 @@ fn main() {
                     return;
                 }
                 if let Some(pid) = get_pid(&pidfile) {
-                    unsafe { libc::kill(pid, libc::SIGTERM) };
-                    std::thread::sleep(std::time::Duration::from_millis(100));
-                    let _ = std::fs::remove_file(&pidfile);
+                    match common::cli::request_theme(APP_NAME, pid, theme) {
+                        Ok(()) => println!("Switched to theme: {}", theme),
+                        Err(e) => {
+                            eprintln!("Failed to switch theme: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                    return;
                 }
                 let exe = std::env::current_exe().expect("cannot find self");
                 let _ = Command::new(&exe)
@@ -97,18 +201,24 @@ fn main() {
             }
             other => {
                 eprintln!("Unknown option: {}", other);
-                print_usage();
+                print_usage(APP_NAME, "app launcher", USAGE);
                 std::process::exit(1);
             }
         }
     }
 
-    if let Some(pid) = get_pid(&pidfile) {
-        unsafe { libc::kill(pid, libc::SIGUSR1) };
-        return;
+    let oneshot = common::cli::is_oneshot();
+    if !oneshot {
+        if let Some(pid) = get_pid(&pidfile) {
+            unsafe { libc::kill(pid, libc::SIGUSR1) };
+            return;
+        }
+        write_pid(&pidfile);
+    }
+    // `--query`, also under `--oneshot`, for the window about to open
+    if let Some(query) = query_arg(&args) {
+        let _ = request_query(APP_NAME, &query);
     }
-
-    write_pid(&pidfile);
 
     let app = Application::builder()
         .application_id("com.vib1240n.launch-gui")
@@ -121,5 +231,7 @@ fn main() {
     });
 
     app.run_with_args::<String>(&[]);
-    remove_pid(&pidfile);
+    if !oneshot {
+        remove_pid(&pidfile);
+    }
 }
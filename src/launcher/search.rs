@@ -1,35 +1,133 @@
-use crate::desktop::{DesktopEntry, FREQUENCY};
+use crate::desktop::{program_name, DesktopEntry, FREQUENCY};
+use common::search::ignore_case;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-pub fn fuzzy_match(query: &str, text: &str) -> Option<i32> {
+/// Rows the list shows at most; narrowing the query reaches the rest
+pub const MAX_ROWS: usize = 50;
+
+/// How many most-launched apps lead the unfiltered list; 0 turns it off
+static RECENT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// `[behavior] show_recent` / `recent_count`
+pub fn set_recent_count(n: usize) {
+    RECENT_COUNT.store(n, Ordering::Relaxed);
+}
+
+/// The most launched entries this session, most first (ties by name), for
+/// the "Recent" section. Never-launched apps don't count.
+pub fn recent_entries(entries: &[DesktopEntry]) -> Vec<DesktopEntry> {
+    let limit = RECENT_COUNT.load(Ordering::Relaxed);
+    if limit == 0 {
+        return Vec::new();
+    }
+    let mut launched: Vec<(DesktopEntry, u32)> = FREQUENCY.with(|f| {
+        let freq = f.borrow();
+        entries
+            .iter()
+            .filter_map(|e| freq.get(&e.name).map(|&n| (e.clone(), n)))
+            .collect()
+    });
+    launched.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+    launched.into_iter().take(limit).map(|(e, _)| e).collect()
+}
+
+/// Scores `fuzzy_match` hands out, from the `[search]` config section
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchWeights {
+    /// Text equals the query
+    pub exact: i32,
+    /// Text starts with the query (shorter queries score slightly higher)
+    pub prefix: i32,
+    /// Query appears anywhere in the text
+    pub contains: i32,
+    /// Per character of a subsequence match, times the run length so far
+    pub consecutive: i32,
+    /// Extra per subsequence character at a word start (after a space, `-`,
+    /// `_`, `.` or `/`, or a camelCase capital)
+    pub word_start: i32,
+    /// Added per past launch of the entry
+    pub frequency: i32,
+    /// `[behavior] smartcase`: an uppercase letter makes the query match case
+    pub smartcase: bool,
+}
+
+impl Default for SearchWeights {
+    fn default() -> Self {
+        Self {
+            exact: 1000,
+            prefix: 500,
+            contains: 200,
+            consecutive: 10,
+            word_start: 30,
+            frequency: 50,
+            smartcase: true,
+        }
+    }
+}
+
+/// Whether `c` starts a word, given the character before it
+fn is_word_start(prev: Option<char>, c: char) -> bool {
+    match prev {
+        None => true,
+        Some(p) => " -_./".contains(p) || (p.is_lowercase() && c.is_uppercase()),
+    }
+}
+
+pub fn fuzzy_match(query: &str, text: &str, w: &SearchWeights) -> Option<i32> {
     if query.is_empty() {
         return Some(0);
     }
 
-    let q = query.to_lowercase();
-    let t = text.to_lowercase();
+    let ic = ignore_case(query, w.smartcase);
+    let fold = |c: char| {
+        if ic {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
+    };
+    let q: String = query.chars().map(fold).collect();
+    let t: String = text.chars().map(fold).collect();
 
     if t == q {
-        return Some(1000);
+        return Some(w.exact);
     }
     if t.starts_with(&q) {
-        return Some(500 + (100 - q.len() as i32));
+        return Some(w.prefix + (100 - q.len() as i32));
     }
     if t.contains(&q) {
-        return Some(200);
+        return Some(w.contains);
     }
 
     let mut qi = q.chars().peekable();
     let mut score = 0;
     let mut consecutive = 0;
+    // Word-start characters only, for acronyms like "vsc" -> Visual Studio Code
+    let mut initials = q.chars().peekable();
+    let mut prev = None;
 
-    for c in t.chars() {
-        if qi.peek() == Some(&c) {
+    for c in text.chars() {
+        let folded = fold(c);
+        let word_start = is_word_start(prev, c);
+        if qi.peek() == Some(&folded) {
             qi.next();
             consecutive += 1;
-            score += consecutive * 10;
+            score += consecutive * w.consecutive;
+            if word_start {
+                score += w.word_start;
+            }
         } else {
             consecutive = 0;
         }
+        if word_start && initials.peek() == Some(&folded) {
+            initials.next();
+        }
+        prev = Some(c);
+    }
+
+    if initials.peek().is_none() {
+        let acronym = q.chars().count() as i32 * (w.consecutive + w.word_start);
+        score = score.max(acronym);
     }
 
     if qi.peek().is_none() {
@@ -39,17 +137,35 @@ pub fn fuzzy_match(query: &str, text: &str) -> Option<i32> {
     }
 }
 
-pub fn filter_entries(entries: &[DesktopEntry], query: &str) -> Vec<DesktopEntry> {
+pub fn filter_entries(
+    entries: &[DesktopEntry],
+    query: &str,
+    w: &SearchWeights,
+) -> Vec<DesktopEntry> {
     if query.is_empty() {
-        return entries.to_vec();
+        // Recent apps first, then everything else in its usual order
+        let recent = recent_entries(entries);
+        let rest = entries
+            .iter()
+            .filter(|e| !recent.iter().any(|r| r.name == e.name))
+            .cloned();
+        return recent.iter().cloned().chain(rest).collect();
     }
 
     let mut matched: Vec<(DesktopEntry, i32)> = entries
         .iter()
         .filter_map(|e| {
-            let name_score = fuzzy_match(query, &e.name);
-            let desc_score = fuzzy_match(query, &e.description).map(|s| s / 2);
-            let best = name_score.max(desc_score);
+            let name_score = fuzzy_match(query, &e.name, w);
+            let desc_score = fuzzy_match(query, &e.description, w).map(|s| s / 2);
+            // The command and window class, for apps known by their binary
+            let program = program_name(&e.exec);
+            let program_score = [program.as_str(), e.wm_class.as_str()]
+                .iter()
+                .filter(|t| !t.is_empty())
+                .filter_map(|t| fuzzy_match(query, t, w))
+                .max()
+                .map(|s| s / 4);
+            let best = name_score.max(desc_score).max(program_score);
             best.map(|s| (e.clone(), s))
         })
         .collect();
@@ -58,7 +174,7 @@ pub fn filter_entries(entries: &[DesktopEntry], query: &str) -> Vec<DesktopEntry
         let freq = f.borrow();
         for (entry, score) in &mut matched {
             if let Some(&count) = freq.get(&entry.name) {
-                *score += (count * 50) as i32;
+                *score += count as i32 * w.frequency;
             }
         }
     });
@@ -67,11 +183,115 @@ pub fn filter_entries(entries: &[DesktopEntry], query: &str) -> Vec<DesktopEntry
     matched.into_iter().map(|(e, _)| e).collect()
 }
 
-pub fn get_filtered_entry(
-    entries: &[DesktopEntry],
-    query: &str,
-    idx: usize,
-) -> Option<DesktopEntry> {
-    let filtered = filter_entries(entries, query);
-    filtered.get(idx).cloned()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn app(name: &str, exec: &str) -> DesktopEntry {
+        DesktopEntry {
+            name: name.to_string(),
+            exec: exec.to_string(),
+            icon: String::new(),
+            description: String::new(),
+            terminal: false,
+            path: PathBuf::new(),
+            workdir: None,
+            wm_class: String::new(),
+            score: 0,
+        }
+    }
+
+    fn names(entries: &[DesktopEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn exact_beats_prefix_beats_contains() {
+        let w = SearchWeights::default();
+        let exact = fuzzy_match("files", "Files", &w).unwrap();
+        let prefix = fuzzy_match("files", "Files Manager", &w).unwrap();
+        let contains = fuzzy_match("files", "My Files", &w).unwrap();
+        assert!(exact > prefix && prefix > contains);
+        assert_eq!(fuzzy_match("xyz", "Files", &w), None);
+    }
+
+    #[test]
+    fn weights_change_the_ranking() {
+        let apps = [app("Terminal Emulator", "a"), app("My Terminal", "b")];
+        let w = SearchWeights::default();
+        assert_eq!(
+            names(&filter_entries(&apps, "terminal", &w))[0],
+            "Terminal Emulator"
+        );
+        let w = SearchWeights {
+            prefix: 0,
+            contains: 2000,
+            ..w
+        };
+        assert_eq!(
+            names(&filter_entries(&apps, "terminal", &w))[0],
+            "My Terminal"
+        );
+    }
+
+    #[test]
+    fn smartcase() {
+        let w = SearchWeights::default();
+        assert!(fuzzy_match("fire", "Firefox", &w).is_some());
+        assert!(fuzzy_match("Fire", "Firefox", &w).is_some());
+        assert_eq!(fuzzy_match("FIRE", "Firefox", &w), None);
+        let w = SearchWeights {
+            smartcase: false,
+            ..w
+        };
+        assert!(fuzzy_match("FIRE", "Firefox", &w).is_some());
+    }
+
+    #[test]
+    fn word_starts_score_higher() {
+        let w = SearchWeights::default();
+        let at_words = fuzzy_match("gc", "Gnome Calculator", &w).unwrap();
+        let mid_word = fuzzy_match("gc", "Magic Tool", &w).unwrap();
+        assert!(at_words > mid_word);
+        let camel = fuzzy_match("fm", "FileManager", &w).unwrap();
+        assert!(camel > fuzzy_match("fm", "Filmmaker", &w).unwrap());
+    }
+
+    #[test]
+    fn acronyms_score_every_initial() {
+        let w = SearchWeights::default();
+        // The greedy subsequence takes the `s` in "Visual"; the initials win
+        assert_eq!(
+            fuzzy_match("vsc", "Visual Studio Code", &w),
+            Some(3 * (w.consecutive + w.word_start))
+        );
+        assert!(fuzzy_match("vsc", "Visual Studio Code", &w) > fuzzy_match("vsc", "Vaseco", &w));
+    }
+
+    #[test]
+    fn program_name_and_wm_class_match() {
+        let mut browser = app(
+            "Web Browser",
+            "env MOZ_ENABLE_WAYLAND=1 /usr/lib/firefox/firefox %u",
+        );
+        let w = SearchWeights::default();
+        assert_eq!(
+            names(&filter_entries(&[browser.clone()], "firefox", &w)),
+            ["Web Browser"]
+        );
+        browser.exec = "/opt/fx/run".to_string();
+        browser.wm_class = "firefox".to_string();
+        assert_eq!(
+            names(&filter_entries(&[browser], "firefox", &w)),
+            ["Web Browser"]
+        );
+    }
+
+    #[test]
+    fn exec_flags_dont_match() {
+        let editor = app("Text Editor", "gedit --new-window --standalone");
+        let w = SearchWeights::default();
+        assert!(filter_entries(&[editor], "standalone", &w).is_empty());
+    }
 }
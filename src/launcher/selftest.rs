@@ -0,0 +1,45 @@
+use crate::config::Config;
+use crate::desktop::{cached_entries, scan_entries};
+use crate::search::{filter_entries, fuzzy_match};
+use common::selftest::{corpus, report, timed, QUERIES};
+
+/// Synthetic names each query is matched against
+const CORPUS_SIZE: usize = 10_000;
+
+/// `--selftest`: time loading and searching without opening a window.
+/// The desktop cache is read but never rewritten; a stale or missing one
+/// reads as 0 entries.
+pub fn run(cfg: &Config) {
+    let (cached, elapsed) = timed(cached_entries);
+    report(
+        "cached_entries",
+        cached.as_ref().map_or(0, Vec::len),
+        elapsed,
+    );
+    let (scanned, elapsed) = timed(scan_entries);
+    report("scan_entries", scanned.len(), elapsed);
+
+    for query in QUERIES {
+        let (matched, elapsed) = timed(|| filter_entries(&scanned, query, &cfg.search));
+        report(
+            &format!("filter_entries {:?}", query),
+            matched.len(),
+            elapsed,
+        );
+    }
+
+    let names = corpus(CORPUS_SIZE);
+    for query in QUERIES {
+        let (matched, elapsed) = timed(|| {
+            names
+                .iter()
+                .filter(|n| fuzzy_match(query, n, &cfg.search).is_some())
+                .count()
+        });
+        report(
+            &format!("fuzzy_match {:?} x{}", query, CORPUS_SIZE),
+            matched,
+            elapsed,
+        );
+    }
+}
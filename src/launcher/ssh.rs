@@ -0,0 +1,179 @@
+use crate::desktop::DesktopEntry;
+use std::path::{Path, PathBuf};
+
+/// The filter typed after an `ssh ` prefix, if `query` has one
+pub fn ssh_filter(query: &str) -> Option<&str> {
+    query.strip_prefix("ssh ").map(str::trim_start)
+}
+
+fn ssh_dir() -> PathBuf {
+    PathBuf::from(std::env::var("HOME").unwrap_or("/".into())).join(".ssh")
+}
+
+/// Hosts and ports only; anything else could break out of the `sh -c` line,
+/// and a leading `-` would be read by ssh as an option
+fn safe_host(h: &str) -> bool {
+    !h.is_empty()
+        && !h.starts_with('-')
+        && h.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-@:".contains(c))
+}
+
+/// `Host` aliases from an ssh config, skipping wildcard patterns
+fn config_hosts(path: &Path) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|l| {
+            let (key, rest) = l.trim().split_once(char::is_whitespace)?;
+            key.eq_ignore_ascii_case("host").then_some(rest)
+        })
+        .flat_map(|rest| rest.split_whitespace().map(str::to_string))
+        .filter(|h| !h.contains(['*', '?', '!']))
+        .collect()
+}
+
+/// Hosts from a known_hosts file as `(name, ssh args)`; `[host]:port`
+/// becomes `-p port host` and hashed entries are skipped
+fn known_hosts(path: &Path) -> Vec<(String, String)> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut hosts = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('|') {
+            continue;
+        }
+        let first = line.split_whitespace().next().unwrap_or("");
+        // `@cert-authority` / `@revoked` markers aren't hosts
+        if first.starts_with('@') {
+            continue;
+        }
+        for h in first.split(',') {
+            let entry = match h.strip_prefix('[').and_then(|b| b.split_once("]:")) {
+                Some((host, port)) => (
+                    format!("{}:{}", host, port),
+                    format!("-p {} {}", port, host),
+                ),
+                None => (h.to_string(), h.to_string()),
+            };
+            // Bare IPs are usually the same host again under another name
+            if entry.0.parse::<std::net::IpAddr>().is_err() {
+                hosts.push(entry);
+            }
+        }
+    }
+    hosts
+}
+
+/// Hosts from `~/.ssh/config` then `~/.ssh/known_hosts`, deduplicated, as
+/// terminal entries running `ssh`. Missing files just contribute nothing.
+pub fn ssh_entries() -> Vec<DesktopEntry> {
+    entries_in(&ssh_dir())
+}
+
+/// `ssh_entries` for the `config` and `known_hosts` in `dir`
+fn entries_in(dir: &Path) -> Vec<DesktopEntry> {
+    let config_path = dir.join("config");
+    let known_path = dir.join("known_hosts");
+    let from_config = config_hosts(&config_path)
+        .into_iter()
+        .map(|h| (h.clone(), h, &config_path));
+    let from_known = known_hosts(&known_path)
+        .into_iter()
+        .map(|(name, args)| (name, args, &known_path));
+
+    let mut seen = std::collections::HashSet::new();
+    from_config
+        .chain(from_known)
+        .filter(|(name, args, _)| {
+            safe_host(name) && args.split(' ').filter(|&a| a != "-p").all(safe_host)
+        })
+        .filter(|(name, _, _)| seen.insert(name.clone()))
+        .map(|(name, args, source)| DesktopEntry {
+            exec: format!("ssh {}", args),
+            icon: "network-server".to_string(),
+            description: source.display().to_string(),
+            terminal: true,
+            path: source.clone(),
+            workdir: None,
+            wm_class: String::new(),
+            score: 0,
+            name,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch `.ssh` dir holding `config` and `known_hosts`
+    fn ssh_dir_with(name: &str, config: &str, known: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("launch-gui-ssh-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("config"), config).unwrap();
+        std::fs::write(dir.join("known_hosts"), known).unwrap();
+        dir
+    }
+
+    #[test]
+    fn leading_dash_is_not_a_host() {
+        assert!(safe_host("web-1.example.com"));
+        assert!(!safe_host("-oProxyCommand=x"));
+        assert!(!safe_host("host;reboot"));
+        assert!(!safe_host(""));
+    }
+
+    #[test]
+    fn config_hosts_skip_patterns() {
+        let dir = ssh_dir_with(
+            "config",
+            "Host *\n  User me\nHost web db\n  Port 22\nhost *.lan !bad jump\n",
+            "",
+        );
+        let hosts = config_hosts(&dir.join("config"));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(hosts, ["web", "db", "jump"]);
+    }
+
+    #[test]
+    fn known_hosts_ports_hashes_and_markers() {
+        let known = "web,192.168.1.5 ssh-ed25519 AAAA\n\
+                     [git.example.com]:2222 ssh-ed25519 AAAA\n\
+                     |1|c2FsdA==|aGFzaA== ssh-ed25519 AAAA\n\
+                     @cert-authority *.example.com ssh-ed25519 AAAA\n\
+                     # comment\n";
+        let dir = ssh_dir_with("known", "", known);
+        let hosts = known_hosts(&dir.join("known_hosts"));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(
+            hosts,
+            [
+                ("web".to_string(), "web".to_string()),
+                (
+                    "git.example.com:2222".to_string(),
+                    "-p 2222 git.example.com".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn entries_are_deduplicated_and_checked() {
+        let dir = ssh_dir_with(
+            "entries",
+            "Host web -oProxyCommand=x\n",
+            "web ssh-ed25519 AAAA\n[-oX]:22 ssh-ed25519 AAAA\n[db]:2222 ssh-ed25519 AAAA\n",
+        );
+        let entries = entries_in(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        let execs: Vec<&str> = entries.iter().map(|e| e.exec.as_str()).collect();
+        assert_eq!(execs, ["ssh web", "ssh -p 2222 db"]);
+        assert_eq!(entries[0].path, dir.join("config"));
+    }
+}
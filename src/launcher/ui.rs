@@ -1,11 +1,40 @@
-use crate::calc::calc_eval;
+use crate::calc::{calc_eval, copy_text, CalcCopyFormat};
+use crate::config::Config;
 use crate::desktop::DesktopEntry;
-use crate::search::filter_entries;
-use common::css::char_truncate;
+use crate::files::{is_file_query, list_files, FileEntry};
+use crate::search::{filter_entries, recent_entries, SearchWeights, MAX_ROWS};
+use crate::ssh::{ssh_entries, ssh_filter};
+use common::css::{char_truncate, is_compact, row_icon_size};
+use common::i18n::{tr, trf};
 use gtk4::prelude::*;
 use gtk4::{Align, Box as GtkBox, Image, Label, ListBox, ListBoxRow, Orientation};
+use std::cell::RefCell;
 use std::path::PathBuf;
 
+thread_local! {
+    /// What the listed rows were built from, in row order: apps or ssh
+    /// hosts, or files. Selection reads these rather than re-running the
+    /// search, so a row always launches what it shows.
+    static LISTED_ENTRIES: RefCell<Vec<DesktopEntry>> = const { RefCell::new(Vec::new()) };
+    static LISTED_FILES: RefCell<Vec<FileEntry>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The app or ssh host shown in row `idx`
+pub fn listed_entry(idx: usize) -> Option<DesktopEntry> {
+    LISTED_ENTRIES.with(|l| l.borrow().get(idx).cloned())
+}
+
+/// The file shown in row `idx` of a file search
+pub fn listed_file(idx: usize) -> Option<FileEntry> {
+    LISTED_FILES.with(|l| l.borrow().get(idx).cloned())
+}
+
+/// Remember what the rows show, up to the `MAX_ROWS` that get a row
+fn set_listed(entries: &[DesktopEntry], files: &[FileEntry]) {
+    LISTED_ENTRIES.with(|l| *l.borrow_mut() = entries.iter().take(MAX_ROWS).cloned().collect());
+    LISTED_FILES.with(|l| *l.borrow_mut() = files.iter().take(MAX_ROWS).cloned().collect());
+}
+
 pub fn load_icon(icon_name: &str, size: i32) -> Option<Image> {
     if icon_name.is_empty() {
         return None;
@@ -32,20 +61,30 @@ pub fn load_icon(icon_name: &str, size: i32) -> Option<Image> {
     None
 }
 
-pub fn build_row(entry: &DesktopEntry) -> ListBoxRow {
+/// A bare row, marked `launch-row-compact` under `density = compact`
+fn new_row() -> ListBoxRow {
     let row = ListBoxRow::new();
     row.set_focusable(false);
+    if is_compact() {
+        row.add_css_class("launch-row-compact");
+    }
+    row
+}
+
+pub fn build_row(entry: &DesktopEntry) -> ListBoxRow {
+    let row = new_row();
+    let size = row_icon_size();
 
     let hbox = GtkBox::new(Orientation::Horizontal, 14);
     hbox.set_valign(Align::Center);
 
     let icon_box = GtkBox::new(Orientation::Vertical, 0);
-    icon_box.set_size_request(48, 48);
+    icon_box.set_size_request(size, size);
     icon_box.set_valign(Align::Center);
     icon_box.set_halign(Align::Center);
     icon_box.add_css_class("launch-icon-box");
 
-    if let Some(img) = load_icon(&entry.icon, 48) {
+    if let Some(img) = load_icon(&entry.icon, size) {
         img.set_valign(Align::Center);
         img.set_halign(Align::Center);
         icon_box.append(&img);
@@ -69,7 +108,7 @@ pub fn build_row(entry: &DesktopEntry) -> ListBoxRow {
     title.add_css_class("launch-title");
     content.append(&title);
 
-    if !entry.description.is_empty() {
+    if !entry.description.is_empty() && !is_compact() {
         let desc = Label::new(Some(&char_truncate(&entry.description, 60)));
         desc.set_xalign(0.0);
         desc.set_ellipsize(gtk4::pango::EllipsizeMode::End);
@@ -83,15 +122,16 @@ pub fn build_row(entry: &DesktopEntry) -> ListBoxRow {
     row
 }
 
-pub fn build_calc_row(expr: &str, result: &str) -> ListBoxRow {
-    let row = ListBoxRow::new();
-    row.set_focusable(false);
+/// The single row shown for a `=expr` query
+pub fn build_calc_row(expr: &str, result: &str, copy_format: CalcCopyFormat) -> ListBoxRow {
+    let row = new_row();
+    let size = row_icon_size();
 
     let hbox = GtkBox::new(Orientation::Horizontal, 14);
     hbox.set_valign(Align::Center);
 
     let icon_box = GtkBox::new(Orientation::Vertical, 0);
-    icon_box.set_size_request(48, 48);
+    icon_box.set_size_request(size, size);
     icon_box.set_valign(Align::Center);
     icon_box.add_css_class("launch-icon-box");
     let lbl = Label::new(Some("="));
@@ -110,46 +150,190 @@ pub fn build_calc_row(expr: &str, result: &str) -> ListBoxRow {
     title.add_css_class("launch-calc-result");
     content.append(&title);
 
-    let sub = Label::new(Some(&format!("= {}", expr)));
-    sub.set_xalign(0.0);
-    sub.add_css_class("launch-subtitle");
-    content.append(&sub);
+    if !is_compact() {
+        let sub = Label::new(Some(&format!("= {}", expr)));
+        sub.set_xalign(0.0);
+        sub.add_css_class("launch-subtitle");
+        content.append(&sub);
+    }
 
     hbox.append(&content);
+
+    // Spell out that Enter copies, and what exactly lands on the clipboard
+    let hint = GtkBox::new(Orientation::Horizontal, 0);
+    hint.set_valign(Align::Center);
+    let key = Label::new(Some("Enter"));
+    key.add_css_class("launch-status-key");
+    hint.append(&key);
+    let copied = copy_text(expr, result, copy_format);
+    let what = Label::new(Some(&trf("copy {}", &[&copied])));
+    what.add_css_class("launch-status-hint");
+    what.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+    what.set_max_width_chars(24);
+    hint.append(&what);
+    hbox.append(&hint);
+
     row.set_child(Some(&hbox));
     row
 }
 
+/// A file-search row, drawn like an app with a folder or file icon
+pub fn build_file_row(file: &FileEntry) -> ListBoxRow {
+    let icon = if file.is_dir {
+        "folder"
+    } else {
+        "text-x-generic"
+    };
+    build_row(&DesktopEntry {
+        name: file.label.clone(),
+        exec: String::new(),
+        icon: icon.to_string(),
+        description: file.path.to_string_lossy().to_string(),
+        terminal: false,
+        path: file.path.clone(),
+        workdir: None,
+        wm_class: String::new(),
+        score: 0,
+    })
+}
+
+fn section_header(text: &str) -> Label {
+    let label = Label::new(Some(text));
+    label.set_xalign(0.0);
+    label.add_css_class("launch-section-label");
+    label
+}
+
+/// The apps matching `query`, in the order their rows are built, recorded
+/// for `listed_entry`
+fn list_apps(entries: &[DesktopEntry], query: &str, weights: &SearchWeights) -> Vec<DesktopEntry> {
+    let filtered = filter_entries(entries, query, weights);
+    set_listed(&filtered, &[]);
+    filtered
+}
+
+/// Fill the list for `query`, at most `MAX_ROWS` rows. Returns how many
+/// matched, which can be more than were listed.
 pub fn populate_list(
     listbox: &ListBox,
     entries: &[DesktopEntry],
     query: &str,
-    calc_enabled: bool,
+    cfg: &Config,
 ) -> usize {
+    let initial = cfg.base.initial_selection;
     while let Some(row) = listbox.row_at_index(0) {
         listbox.remove(&row);
     }
+    if cfg.file_search && is_file_query(query) {
+        let found = list_files(query, &cfg.search);
+        for f in found.iter().take(MAX_ROWS) {
+            listbox.append(&build_file_row(f));
+        }
+        set_listed(&[], &found);
+        common::session::apply_initial_selection(listbox, initial, query);
+        return found.len();
+    }
+    crate::files::forget_walk();
 
-    if calc_enabled && query.starts_with('=') && query.len() > 1 {
+    if let Some(filter) = ssh_filter(query).filter(|_| cfg.ssh_hosts) {
+        let hosts = filter_entries(&ssh_entries(), filter, &cfg.search);
+        for h in hosts.iter().take(MAX_ROWS) {
+            listbox.append(&build_row(h));
+        }
+        set_listed(&hosts, &[]);
+        common::session::apply_initial_selection(listbox, initial, query);
+        return hosts.len();
+    }
+
+    if let Some(opts) = cfg
+        .calc_options()
+        .filter(|_| query.starts_with('=') && query.len() > 1)
+    {
         let expr = &query[1..];
-        if let Some(result) = calc_eval(expr) {
-            listbox.append(&build_calc_row(expr, &result));
-            if let Some(first) = listbox.row_at_index(0) {
-                listbox.select_row(Some(&first));
-            }
+        if let Some(result) = calc_eval(expr, opts) {
+            listbox.append(&build_calc_row(expr, &result, opts.copy_format));
+            set_listed(&[], &[]);
+            common::session::apply_initial_selection(listbox, initial, query);
             return 1;
         }
     }
 
-    let filtered = filter_entries(entries, query);
+    let filtered = list_apps(entries, query, &cfg.search);
     let count = filtered.len();
+    // Headers rather than rows, so row indices still match `filtered`
+    let recent = if query.is_empty() {
+        recent_entries(entries).len()
+    } else {
+        0
+    };
 
-    for e in filtered.iter().take(50) {
-        listbox.append(&build_row(e));
+    for (i, e) in filtered.iter().take(MAX_ROWS).enumerate() {
+        let row = build_row(e);
+        if recent > 0 && i == 0 {
+            row.set_header(Some(&section_header(tr(&cfg.recent_label))));
+        } else if recent > 0 && i == recent {
+            row.set_header(Some(&section_header(tr(&cfg.all_apps_label))));
+        }
+        listbox.append(&row);
     }
 
-    if let Some(first) = listbox.row_at_index(0) {
-        listbox.select_row(Some(&first));
-    }
+    common::session::apply_initial_selection(listbox, initial, query);
     count
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str) -> DesktopEntry {
+        DesktopEntry {
+            name: name.to_string(),
+            exec: name.to_lowercase(),
+            icon: String::new(),
+            description: String::new(),
+            terminal: false,
+            path: PathBuf::new(),
+            workdir: None,
+            wm_class: String::new(),
+            score: 0,
+        }
+    }
+
+    #[test]
+    fn last_visible_row_is_the_last_listed_entry() {
+        let apps: Vec<DesktopEntry> = (0..MAX_ROWS + 10)
+            .map(|i| app(&format!("App {}", i)))
+            .collect();
+        set_listed(&apps, &[]);
+        let last = MAX_ROWS - 1;
+        assert_eq!(
+            listed_entry(last).map(|e| e.name),
+            Some(apps[last].name.clone())
+        );
+        assert!(listed_entry(MAX_ROWS).is_none());
+    }
+
+    #[test]
+    fn rows_keep_the_order_they_were_shown_in() {
+        use crate::desktop::FREQUENCY;
+
+        let w = SearchWeights::default();
+        let apps = vec![app("Terminal"), app("Term Tool"), app("Termite")];
+        let shown: Vec<String> = list_apps(&apps, "term", &w)
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        // A launch in between reorders the next search, not the rows on
+        // screen that Enter and clicks resolve through listed_entry
+        FREQUENCY.with(|f| f.borrow_mut().insert(shown[2].clone(), 5));
+        let reordered = filter_entries(&apps, "term", &w);
+        assert_eq!(reordered[0].name, shown[2]);
+        for (row, name) in shown.iter().enumerate() {
+            assert_eq!(listed_entry(row).map(|e| e.name).as_ref(), Some(name));
+        }
+        // Until the list is rebuilt
+        list_apps(&apps, "term", &w);
+        assert_eq!(listed_entry(0).map(|e| e.name).as_ref(), Some(&shown[2]));
+        FREQUENCY.with(|f| f.borrow_mut().clear());
+    }
+}